@@ -0,0 +1,146 @@
+//! Animated drags: one frame per cursor move (see [`record_drag_frames`]),
+//! with the cursor and the drag's latest error highlighted, saved as a GIF.
+//!
+//! [`record_drag_frames`]: prototype_abstract::test_case::record_drag_frames
+
+use std::fs::File;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use euclid::vec2;
+use prototype_abstract::BoundingBox;
+use prototype_abstract::test_case::DragFrame;
+use tiny_skia::{Color, Paint, PathBuilder, Pixmap, Stroke, Transform};
+
+use crate::mismatch::{draw_errors, outline_tiles, tile_rect};
+use crate::{ImageRenderer, TILE_SIZE};
+
+/// How much longer the last frame is shown before the animation loops.
+const LAST_FRAME_HOLD: u16 = 4;
+
+fn cursor_color() -> Color {
+    Color::from_rgba8(80, 220, 255, 255)
+}
+
+fn cursor_shadow_color() -> Color {
+    Color::from_rgba8(0, 0, 0, 160)
+}
+
+/// Bounds containing every frame's world and cursor.
+pub fn frames_bounds(frames: &[DragFrame]) -> BoundingBox {
+    let cursors = BoundingBox::from_points(frames.iter().map(|f| f.cursor));
+    // Tile bounds exclude `max`, so add the tiles at the furthest cursors
+    let cursors = BoundingBox::new(cursors.min, cursors.max + vec2(1, 1));
+    frames
+        .iter()
+        .fold(cursors, |bounds, f| bounds.union(&f.world.bounds()))
+}
+
+impl ImageRenderer {
+    pub fn render_drag_frame(&self, frame: &DragFrame, bounds: BoundingBox) -> Pixmap {
+        let mut pixmap = self.render_world(&frame.world, bounds);
+        if let Some(error) = &frame.last_error {
+            outline_tiles(&mut pixmap, bounds, &[error.0]);
+            let errors = [error.clone()].into_iter().collect();
+            draw_errors(&mut pixmap, bounds, &errors, &Default::default());
+        }
+        draw_cursor(&mut pixmap, bounds, frame);
+        pixmap
+    }
+
+    /// Saves `frames` as a looping GIF, showing each for `frame_delay_ms`.
+    pub fn save_drag_gif<P: AsRef<Path>>(
+        &self,
+        frames: &[DragFrame],
+        frame_delay_ms: u16,
+        path: P,
+    ) -> Result<()> {
+        let bounds = frames_bounds(frames);
+        let gif_size = |tiles: i32| {
+            u16::try_from(i64::from(tiles) * i64::from(TILE_SIZE))
+                .with_context(|| format!("{tiles} tiles is too large for a GIF"))
+        };
+        let width = gif_size(bounds.width())?;
+        let height = gif_size(bounds.height())?;
+        let mut encoder = gif::Encoder::new(File::create(path)?, width, height, &[])?;
+        encoder.set_repeat(gif::Repeat::Infinite)?;
+
+        for (i, frame) in frames.iter().enumerate() {
+            let pixmap = self.render_drag_frame(frame, bounds);
+            let mut rgba = pixmap
+                .pixels()
+                .iter()
+                .flat_map(|p| {
+                    let c = p.demultiply();
+                    [c.red(), c.green(), c.blue(), c.alpha()]
+                })
+                .collect::<Vec<_>>();
+            let mut gif_frame = gif::Frame::from_rgba_speed(width, height, &mut rgba, 10);
+            // GIF delays are in hundredths of a second
+            gif_frame.delay = frame_delay_ms / 10;
+            if i + 1 == frames.len() {
+                gif_frame.delay *= LAST_FRAME_HOLD;
+            }
+            encoder.write_frame(&gif_frame)?;
+        }
+        Ok(())
+    }
+}
+
+/// A ring around the cursor's tile.
+fn draw_cursor(pixmap: &mut Pixmap, bounds: BoundingBox, frame: &DragFrame) {
+    let rect = tile_rect(bounds, frame.cursor);
+    let radius = TILE_SIZE as f32 * 0.35;
+    let Some(path) = PathBuilder::from_circle(
+        rect.left() + TILE_SIZE as f32 / 2.0,
+        rect.top() + TILE_SIZE as f32 / 2.0,
+        radius,
+    ) else {
+        return;
+    };
+    let mut paint = Paint::default();
+    for (color, width) in [(cursor_shadow_color(), 8.0), (cursor_color(), 4.0)] {
+        paint.set_color(color);
+        let stroke = Stroke {
+            width,
+            ..Default::default()
+        };
+        pixmap.stroke_path(&path, &paint, &stroke, Transform::identity(), None);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prototype_abstract::test_case::{DragStep, parse_world, record_drag_frames};
+    use prototype_abstract::{BELT_TIERS, Direction, pos};
+
+    #[test]
+    fn test_save_drag_gif() {
+        let renderer = ImageRenderer::new().unwrap();
+        let (before, _) = parse_world("_ X _ X _").unwrap();
+        let frames = record_drag_frames(
+            &before,
+            BELT_TIERS[0],
+            pos(0, 0),
+            Direction::East,
+            &[DragStep::MoveTo(pos(4, 0)), DragStep::MoveTo(pos(3, 1))],
+        )
+        .unwrap();
+        // The first click, 4 moves east, then one diagonally off the line
+        assert_eq!(frames.len(), 6);
+        assert_eq!(
+            frames_bounds(&frames),
+            BoundingBox::new(pos(0, 0), pos(5, 2))
+        );
+
+        let out_dir = tempfile::tempdir().unwrap();
+        let path = out_dir.path().join("drag.gif");
+        renderer.save_drag_gif(&frames, 300, &path).unwrap();
+        let decoder = gif::DecodeOptions::new()
+            .read_info(File::open(&path).unwrap())
+            .unwrap();
+        assert_eq!(decoder.width() as u32, 5 * TILE_SIZE);
+        assert_eq!(decoder.into_iter().count(), frames.len());
+    }
+}
@@ -0,0 +1,406 @@
+//! Overlays drawn over a rendered world, to point things out: highlighted
+//! tiles, errors (with their kind), the drag, links between paired
+//! undergrounds, and text labels.
+
+use prototype_abstract::smart_belt::action::Error;
+use prototype_abstract::{BeltCollidable, BoundingBox, TilePosition, WorldImpl as World, pos};
+use rusttype::{Scale, point};
+use tiny_skia::{
+    Color, FillRule, Paint, PathBuilder, Pixmap, PremultipliedColorU8, Rect, Stroke, StrokeDash,
+    Transform,
+};
+
+use crate::mismatch::{Errors, tile_rect};
+use crate::{ImageRenderer, TILE_SIZE};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Annotation {
+    /// A highlighted tile, as `*` in grids.
+    Marker(TilePosition),
+    /// A dot in the top right of the tile, with the kind of error below.
+    Error(TilePosition, Error),
+    /// A line from the center of `start` to the center of `end`, with an
+    /// arrowhead at `end`.
+    DragRay {
+        start: TilePosition,
+        end: TilePosition,
+    },
+    /// Dashed lines between each pair of undergrounds in the world.
+    UndergroundLinks,
+    /// Text in the top left of the tile.
+    Label(TilePosition, String),
+}
+
+impl Annotation {
+    /// Errors from a drag, as annotations.
+    pub fn errors(errors: &Errors) -> Vec<Annotation> {
+        let mut errors = errors.iter().cloned().collect::<Vec<_>>();
+        errors.sort_by_key(|(p, error)| (p.y, p.x, error.clone()));
+        errors
+            .into_iter()
+            .map(|(p, error)| Annotation::Error(p, error))
+            .collect()
+    }
+
+    /// Annotations are drawn in this order, whatever order they are given in,
+    /// so that text ends up on top.
+    fn layer(&self) -> u8 {
+        match self {
+            Annotation::Marker(_) => 0,
+            Annotation::UndergroundLinks => 1,
+            Annotation::DragRay { .. } => 2,
+            Annotation::Error(..) => 3,
+            Annotation::Label(..) => 4,
+        }
+    }
+}
+
+/// Short enough to fit under a tile.
+pub fn error_label(error: &Error) -> &'static str {
+    match error {
+        Error::TooFarToConnect => "too far",
+        Error::EntityInTheWay => "in the way",
+        Error::CannotUpgradeUnderground => "no upgrade",
+        Error::BeltLineBroken => "line broken",
+    }
+}
+
+/// Pairs of undergrounds in `world`, input first, with either end in
+/// `bounds`.
+pub fn underground_links(world: &World, bounds: BoundingBox) -> Vec<(TilePosition, TilePosition)> {
+    let mut links = Vec::new();
+    let world_bounds = world.bounds();
+    for y in world_bounds.min.y..world_bounds.max.y {
+        for x in world_bounds.min.x..world_bounds.max.x {
+            let p = pos(x, y);
+            if let Some(BeltCollidable::UndergroundBelt(ug)) = world.get(p)
+                && ug.is_input
+                && let Some((pair_pos, _)) = world.get_ug_pair(p, ug)
+                && (bounds.contains(p) || bounds.contains(pair_pos))
+            {
+                links.push((p, pair_pos));
+            }
+        }
+    }
+    links
+}
+
+/// Annotations in the order to draw them.
+pub(crate) fn in_layers(annotations: &[Annotation]) -> Vec<&Annotation> {
+    let mut annotations = annotations.iter().collect::<Vec<_>>();
+    annotations.sort_by_key(|a| a.layer());
+    annotations
+}
+
+fn marker_fill_color() -> Color {
+    Color::from_rgba8(255, 220, 0, 70)
+}
+
+fn marker_outline_color() -> Color {
+    Color::from_rgba8(255, 220, 0, 255)
+}
+
+fn link_color() -> Color {
+    Color::from_rgba8(120, 220, 255, 230)
+}
+
+fn ray_color() -> Color {
+    Color::from_rgba8(255, 255, 255, 220)
+}
+
+fn error_color() -> Color {
+    Color::from_rgba8(255, 40, 40, 255)
+}
+
+fn label_background_color() -> Color {
+    Color::from_rgba8(20, 20, 20, 190)
+}
+
+fn label_text_color() -> Color {
+    Color::from_rgba8(255, 255, 255, 255)
+}
+
+const LABEL_SIZE: f32 = TILE_SIZE as f32 / 5.0;
+const LABEL_PADDING: f32 = 2.0;
+
+impl ImageRenderer {
+    /// [`Self::render_world`], with `annotations` drawn over it.
+    pub fn render_world_annotated(
+        &self,
+        world: &World,
+        bounds: BoundingBox,
+        annotations: &[Annotation],
+    ) -> Pixmap {
+        let mut pixmap = self.render_world(world, bounds);
+        self.annotate(&mut pixmap, world, bounds, annotations);
+        pixmap
+    }
+
+    /// Draws `annotations` over `pixmap`, a render of `world` in `bounds`.
+    pub fn annotate(
+        &self,
+        pixmap: &mut Pixmap,
+        world: &World,
+        bounds: BoundingBox,
+        annotations: &[Annotation],
+    ) {
+        for annotation in in_layers(annotations) {
+            match annotation {
+                Annotation::Marker(p) => draw_marker(pixmap, tile_rect(bounds, *p)),
+                Annotation::UndergroundLinks => {
+                    for (input, output) in underground_links(world, bounds) {
+                        draw_link(
+                            pixmap,
+                            tile_center(bounds, input),
+                            tile_center(bounds, output),
+                        );
+                    }
+                }
+                Annotation::DragRay { start, end } => {
+                    draw_ray(
+                        pixmap,
+                        tile_center(bounds, *start),
+                        tile_center(bounds, *end),
+                    );
+                }
+                Annotation::Error(p, error) => {
+                    let rect = tile_rect(bounds, *p);
+                    draw_error_dot(pixmap, rect);
+                    let text = error_label(error);
+                    let width = self.text_width(text, LABEL_SIZE);
+                    let x = rect.left() + (rect.width() - width) / 2.0;
+                    let y = rect.bottom() - LABEL_SIZE - LABEL_PADDING;
+                    self.draw_label(pixmap, text, x, y);
+                }
+                Annotation::Label(p, text) => {
+                    let rect = tile_rect(bounds, *p);
+                    self.draw_label(
+                        pixmap,
+                        text,
+                        rect.left() + LABEL_PADDING,
+                        rect.top() + LABEL_PADDING,
+                    );
+                }
+            }
+        }
+    }
+
+    fn text_width(&self, text: &str, size: f32) -> f32 {
+        let scale = Scale::uniform(size);
+        self.font
+            .layout(text, scale, point(0.0, 0.0))
+            .last()
+            .map_or(0.0, |glyph| {
+                glyph.position().x + glyph.unpositioned().h_metrics().advance_width
+            })
+    }
+
+    /// Text with its top left at `(x, y)`, on a dark box so it shows over
+    /// any sprite.
+    fn draw_label(&self, pixmap: &mut Pixmap, text: &str, x: f32, y: f32) {
+        let width = self.text_width(text, LABEL_SIZE);
+        if let Some(rect) = Rect::from_xywh(
+            x - LABEL_PADDING,
+            y - LABEL_PADDING,
+            width + 2.0 * LABEL_PADDING,
+            LABEL_SIZE + 2.0 * LABEL_PADDING,
+        ) {
+            let mut paint = Paint::default();
+            paint.set_color(label_background_color());
+            pixmap.fill_rect(rect, &paint, Transform::identity(), None);
+        }
+        self.draw_text(pixmap, text, x, y, LABEL_SIZE, label_text_color());
+    }
+
+    fn draw_text(&self, pixmap: &mut Pixmap, text: &str, x: f32, y: f32, size: f32, color: Color) {
+        let scale = Scale::uniform(size);
+        let ascent = self.font.v_metrics(scale).ascent;
+        let (width, height) = (pixmap.width() as i32, pixmap.height() as i32);
+        let pixels = pixmap.pixels_mut();
+        for glyph in self.font.layout(text, scale, point(x, y + ascent)) {
+            let Some(bb) = glyph.pixel_bounding_box() else {
+                continue;
+            };
+            glyph.draw(|gx, gy, coverage| {
+                let (px, py) = (bb.min.x + gx as i32, bb.min.y + gy as i32);
+                if (0..width).contains(&px) && (0..height).contains(&py) {
+                    let i = (py * width + px) as usize;
+                    pixels[i] = blend(pixels[i], color, coverage);
+                }
+            });
+        }
+    }
+}
+
+/// `color` over `dst`, with its alpha scaled by `coverage`.
+fn blend(dst: PremultipliedColorU8, color: Color, coverage: f32) -> PremultipliedColorU8 {
+    let alpha = color.alpha() * coverage.clamp(0.0, 1.0);
+    let over = |src: f32, dst: u8| (src * alpha * 255.0 + dst as f32 * (1.0 - alpha)).round() as u8;
+    PremultipliedColorU8::from_rgba(
+        over(color.red(), dst.red()),
+        over(color.green(), dst.green()),
+        over(color.blue(), dst.blue()),
+        over(1.0, dst.alpha()),
+    )
+    .unwrap_or(dst)
+}
+
+fn tile_center(bounds: BoundingBox, p: TilePosition) -> (f32, f32) {
+    let rect = tile_rect(bounds, p);
+    (
+        rect.left() + rect.width() / 2.0,
+        rect.top() + rect.height() / 2.0,
+    )
+}
+
+fn draw_marker(pixmap: &mut Pixmap, rect: Rect) {
+    let mut paint = Paint::default();
+    paint.set_color(marker_fill_color());
+    pixmap.fill_rect(rect, &paint, Transform::identity(), None);
+
+    // Inset so the whole stroke stays inside the tile
+    let Some(inset) = Rect::from_ltrb(
+        rect.left() + 1.5,
+        rect.top() + 1.5,
+        rect.right() - 1.5,
+        rect.bottom() - 1.5,
+    ) else {
+        return;
+    };
+    paint.set_color(marker_outline_color());
+    let stroke = Stroke {
+        width: 3.0,
+        ..Default::default()
+    };
+    let path = PathBuilder::from_rect(inset);
+    pixmap.stroke_path(&path, &paint, &stroke, Transform::identity(), None);
+}
+
+fn draw_link(pixmap: &mut Pixmap, from: (f32, f32), to: (f32, f32)) {
+    let mut pb = PathBuilder::new();
+    pb.move_to(from.0, from.1);
+    pb.line_to(to.0, to.1);
+    let Some(path) = pb.finish() else {
+        return;
+    };
+    let mut paint = Paint::default();
+    paint.set_color(link_color());
+    let dash = TILE_SIZE as f32 / 8.0;
+    let stroke = Stroke {
+        width: 3.0,
+        dash: StrokeDash::new(vec![dash, dash], 0.0),
+        ..Default::default()
+    };
+    pixmap.stroke_path(&path, &paint, &stroke, Transform::identity(), None);
+}
+
+/// A dot at `start`, and a line to an arrowhead at `end`.
+fn draw_ray(pixmap: &mut Pixmap, start: (f32, f32), end: (f32, f32)) {
+    let mut paint = Paint::default();
+    paint.set_color(ray_color());
+    let head = TILE_SIZE as f32 / 4.0;
+    let (dx, dy) = (end.0 - start.0, end.1 - start.1);
+    let length = (dx * dx + dy * dy).sqrt();
+
+    if let Some(dot) = PathBuilder::from_circle(start.0, start.1, head / 3.0) {
+        pixmap.fill_path(&dot, &paint, FillRule::Winding, Transform::identity(), None);
+    }
+    if length < head {
+        return;
+    }
+    let (ux, uy) = (dx / length, dy / length);
+    let base = (end.0 - ux * head, end.1 - uy * head);
+
+    let mut pb = PathBuilder::new();
+    pb.move_to(start.0, start.1);
+    pb.line_to(base.0, base.1);
+    if let Some(line) = pb.finish() {
+        let stroke = Stroke {
+            width: 4.0,
+            ..Default::default()
+        };
+        pixmap.stroke_path(&line, &paint, &stroke, Transform::identity(), None);
+    }
+
+    let mut pb = PathBuilder::new();
+    pb.move_to(end.0, end.1);
+    pb.line_to(base.0 - uy * head / 2.0, base.1 + ux * head / 2.0);
+    pb.line_to(base.0 + uy * head / 2.0, base.1 - ux * head / 2.0);
+    pb.close();
+    if let Some(arrow) = pb.finish() {
+        pixmap.fill_path(
+            &arrow,
+            &paint,
+            FillRule::Winding,
+            Transform::identity(),
+            None,
+        );
+    }
+}
+
+fn draw_error_dot(pixmap: &mut Pixmap, rect: Rect) {
+    let radius = TILE_SIZE as f32 / 6.0;
+    let Some(path) = PathBuilder::from_circle(
+        rect.right() - radius - 2.0,
+        rect.top() + radius + 2.0,
+        radius,
+    ) else {
+        return;
+    };
+    let mut paint = Paint::default();
+    paint.set_color(error_color());
+    pixmap.fill_path(
+        &path,
+        &paint,
+        FillRule::Winding,
+        Transform::identity(),
+        None,
+    );
+    paint.set_color(label_background_color());
+    pixmap.stroke_path(
+        &path,
+        &paint,
+        &Stroke::default(),
+        Transform::identity(),
+        None,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prototype_abstract::test_case::parse_world;
+
+    #[test]
+    fn test_annotations() {
+        let renderer = ImageRenderer::new().unwrap();
+        let (world, markers) = parse_world(">i _ *>o\n_ _ _").unwrap();
+        let bounds = BoundingBox::new(pos(0, 0), pos(3, 2));
+        assert_eq!(underground_links(&world, bounds), [(pos(0, 0), pos(2, 0))]);
+
+        let plain = renderer.render_world(&world, bounds);
+        let annotated = renderer.render_world_annotated(
+            &world,
+            bounds,
+            &[
+                Annotation::Label(pos(0, 1), "A".to_string()),
+                Annotation::Error(pos(1, 1), Error::EntityInTheWay),
+                Annotation::DragRay {
+                    start: pos(0, 1),
+                    end: pos(2, 1),
+                },
+                Annotation::UndergroundLinks,
+                Annotation::Marker(markers[0]),
+            ],
+        );
+        assert_eq!(annotated.width(), plain.width());
+        // Marker outline, in the otherwise-untouched top right tile
+        let corner = |pixmap: &Pixmap| pixmap.pixel(2 * TILE_SIZE + 1, 1).unwrap();
+        assert_ne!(corner(&annotated), corner(&plain));
+        assert_eq!(
+            corner(&annotated),
+            marker_outline_color().premultiply().to_color_u8()
+        );
+        assert!(renderer.text_width("in the way", LABEL_SIZE) < TILE_SIZE as f32);
+    }
+}
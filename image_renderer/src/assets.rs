@@ -0,0 +1,102 @@
+//! The sprite sheets and font the renderer draws with. They are embedded in
+//! the binary, so it works wherever it is copied to; an override directory
+//! can replace any of them (e.g. with modded tier sprites) by file name.
+
+use std::borrow::Cow;
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+
+macro_rules! embed_assets {
+    ($($name:literal),* $(,)?) => {
+        &[$((
+            $name,
+            include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), "/assets/", $name)),
+        )),*]
+    };
+}
+
+/// Every file the renderer loads, by path relative to `assets/`.
+pub const EMBEDDED_ASSETS: &[(&str, &[u8])] = embed_assets![
+    "transport-belt.png",
+    "fast-transport-belt.png",
+    "express-transport-belt.png",
+    "underground-belt-structure.png",
+    "fast-underground-belt-structure.png",
+    "express-underground-belt-structure.png",
+    "splitter-east.png",
+    "splitter-east-top_patch.png",
+    "fast-splitter-east.png",
+    "fast-splitter-east-top_patch.png",
+    "express-splitter-east.png",
+    "express-splitter-east-top_patch.png",
+    "splitter-west.png",
+    "splitter-west-top_patch.png",
+    "fast-splitter-west.png",
+    "fast-splitter-west-top_patch.png",
+    "express-splitter-west.png",
+    "express-splitter-west-top_patch.png",
+    "splitter-south.png",
+    "fast-splitter-south.png",
+    "express-splitter-south.png",
+    "splitter-north.png",
+    "fast-splitter-north.png",
+    "express-splitter-north.png",
+    "fonts/DejaVuSansCondensed-Bold.ttf",
+];
+
+#[derive(Debug, Clone, Default)]
+pub struct Assets {
+    override_dir: Option<PathBuf>,
+}
+
+impl Assets {
+    /// Only the embedded assets.
+    pub fn embedded() -> Self {
+        Self::default()
+    }
+
+    /// Files in `dir` take the place of the embedded assets of the same name;
+    /// any that are missing fall back to the embedded ones.
+    pub fn with_overrides(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            override_dir: Some(dir.into()),
+        }
+    }
+
+    pub fn read(&self, name: &str) -> Result<Cow<'static, [u8]>> {
+        if let Some(dir) = &self.override_dir {
+            let path = dir.join(name);
+            if path.is_file() {
+                let data =
+                    fs::read(&path).with_context(|| format!("Reading {}", path.display()))?;
+                return Ok(Cow::Owned(data));
+            }
+        }
+        EMBEDDED_ASSETS
+            .iter()
+            .find(|(embedded_name, _)| *embedded_name == name)
+            .map(|(_, data)| Cow::Borrowed(*data))
+            .with_context(|| format!("No asset named {name}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_override_falls_back_to_embedded() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("transport-belt.png"), b"modded").unwrap();
+        let assets = Assets::with_overrides(dir.path());
+
+        assert_eq!(&*assets.read("transport-belt.png").unwrap(), b"modded");
+        assert!(matches!(
+            assets.read("fast-transport-belt.png").unwrap(),
+            Cow::Borrowed(_)
+        ));
+        assert!(assets.read("missing.png").is_err());
+    }
+}
@@ -0,0 +1,118 @@
+//! Renders a test suite case's drag as an animated GIF, one frame per cursor
+//! move (see `image_renderer::animation`).
+//!
+//! ```sh
+//! cargo run -p image_renderer --bin animate_drag -- ../test_suite/obstacles.yaml \
+//!     --case "Underground extension" --variant wiggle -o drag.gif
+//! ```
+
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::{Context, Result, bail};
+use clap::{Parser, ValueEnum};
+use image_renderer::ImageRenderer;
+use prototype_abstract::test_case::{DragFrame, SuiteCase, TestVariant, record_drag_frames};
+use serde_yaml::Value;
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Variant {
+    Normal,
+    Wiggle,
+    MegaWiggle,
+    ForwardBack,
+}
+
+impl From<Variant> for TestVariant {
+    fn from(variant: Variant) -> Self {
+        match variant {
+            Variant::Normal => TestVariant::Normal,
+            Variant::Wiggle => TestVariant::Wiggle,
+            Variant::MegaWiggle => TestVariant::MegaWiggle,
+            Variant::ForwardBack => TestVariant::ForwardBack,
+        }
+    }
+}
+
+#[derive(Debug, Parser)]
+struct Args {
+    #[arg(help = "Test suite file")]
+    file: PathBuf,
+    #[arg(long, short, help = "Name of the case to animate [default: the first]")]
+    case: Option<String>,
+    #[arg(
+        long,
+        value_enum,
+        default_value = "normal",
+        help = "How to move the cursor, for cases without explicit steps"
+    )]
+    variant: Variant,
+    #[arg(long, short, default_value = "drag.gif")]
+    output: PathBuf,
+    #[arg(long, default_value_t = 300, help = "Milliseconds per frame")]
+    delay: u16,
+}
+
+/// Frames of every drag in `case`, one after another.
+fn case_frames(case: &SuiteCase, variant: TestVariant) -> Result<Vec<DragFrame>> {
+    match case {
+        SuiteCase::Drag(case) => {
+            let test = &case.entities;
+            let steps = test.variant_steps(variant)?;
+            record_drag_frames(
+                &test.before,
+                test.tier,
+                test.start_pos,
+                test.belt_direction,
+                &steps,
+            )
+        }
+        SuiteCase::Steps(case) => record_drag_frames(
+            &case.before,
+            case.tier,
+            case.start_pos,
+            case.belt_direction,
+            &case.steps,
+        ),
+        SuiteCase::Scenario(case) => {
+            let mut frames: Vec<DragFrame> = Vec::new();
+            for drag in &case.drags {
+                let world = frames.last().map_or(&case.before, |f| &f.world).clone();
+                frames.extend(record_drag_frames(
+                    &world,
+                    drag.tier,
+                    drag.start_pos,
+                    drag.belt_direction,
+                    &drag.steps,
+                )?);
+            }
+            Ok(frames)
+        }
+    }
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+    let text = fs::read_to_string(&args.file)?;
+    let values: Vec<Value> =
+        serde_yaml::from_str(&text).with_context(|| format!("In {:?}", args.file))?;
+    let value = match &args.case {
+        Some(name) => values
+            .iter()
+            .find(|v| v.get("name").and_then(Value::as_str) == Some(name))
+            .with_context(|| format!("No case named {name:?} in {:?}", args.file))?,
+        None => values.first().context("No cases in file")?,
+    };
+    let case = SuiteCase::from_value(value)?;
+    if matches!(case, SuiteCase::Drag(ref c) if c.forward_back)
+        != matches!(args.variant, Variant::ForwardBack)
+    {
+        bail!("Only 'forward_back' cases can (and must) use --variant forward-back");
+    }
+
+    let frames = case_frames(&case, args.variant.into())?;
+    let renderer = ImageRenderer::new()?;
+    renderer.save_drag_gif(&frames, args.delay, &args.output)?;
+    println!("Wrote {} frames to {}", frames.len(), args.output.display());
+    Ok(())
+}
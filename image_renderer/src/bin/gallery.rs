@@ -0,0 +1,52 @@
+//! Writes a static HTML gallery of the test suite (see
+//! `image_renderer::gallery`).
+//!
+//! ```sh
+//! cargo run -p image_renderer --bin gallery -- -o gallery --check
+//! ```
+
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use clap::Parser;
+use image_renderer::gallery::write_gallery;
+use image_renderer::{ImageRenderer, yaml_files};
+use serde_yaml::Value;
+
+#[derive(Debug, Parser)]
+struct Args {
+    #[arg(
+        help = "Test suite files, or directories of them",
+        default_value = concat!(env!("CARGO_MANIFEST_DIR"), "/../test_suite")
+    )]
+    paths: Vec<PathBuf>,
+    #[arg(long, short, default_value = "gallery")]
+    out_dir: PathBuf,
+    #[arg(long, short, help = "Run each case and mark it pass or fail")]
+    check: bool,
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+    let mut files = Vec::new();
+    for path in yaml_files(&args.paths)? {
+        let text = fs::read_to_string(&path).with_context(|| format!("Reading {path:?}"))?;
+        let values: Vec<Value> =
+            serde_yaml::from_str(&text).with_context(|| format!("In {path:?}"))?;
+        let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+        files.push((file_name.to_string(), values));
+    }
+
+    let renderer = ImageRenderer::new()?;
+    let summary = write_gallery(&renderer, &files, &args.out_dir, args.check)?;
+    println!(
+        "Wrote {} cases to {}",
+        summary.cases,
+        args.out_dir.join("index.html").display()
+    );
+    if summary.failures > 0 {
+        println!("{} failing cases", summary.failures);
+    }
+    Ok(())
+}
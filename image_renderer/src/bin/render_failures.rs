@@ -0,0 +1,96 @@
+//! Runs test suite cases and, for each failing one, writes a PNG of before,
+//! expected and actual side by side (see `image_renderer::mismatch`).
+//!
+//! This is separate from `cargo test`: `prototype_abstract`'s tests can't
+//! depend on the renderer, which depends on them.
+//!
+//! ```sh
+//! cargo run -p image_renderer --bin render_failures -- -o failures
+//! ```
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use clap::Parser;
+use image_renderer::{ImageRenderer, yaml_files};
+use prototype_abstract::suite_test_names::sanitized_case_name;
+use prototype_abstract::test_case::{DragMismatch, SuiteCase};
+use serde_yaml::Value;
+
+#[derive(Debug, Parser)]
+struct Args {
+    #[arg(
+        help = "Test suite files, or directories of them",
+        default_value = concat!(env!("CARGO_MANIFEST_DIR"), "/../test_suite")
+    )]
+    paths: Vec<PathBuf>,
+    #[arg(long, short, default_value = "failures")]
+    out_dir: PathBuf,
+}
+
+/// The failing transform and variant, e.g. `[transform 3] [flip] [wiggle]`,
+/// from the context the check added to the error.
+fn failing_variant(error: &anyhow::Error) -> String {
+    error
+        .chain()
+        .take_while(|e| !e.is::<DragMismatch>())
+        .map(|e| e.to_string())
+        .filter(|e| e.starts_with('['))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn render_file_failures(
+    file_stem: &str,
+    values: &[Value],
+    out_dir: &Path,
+    renderer: &ImageRenderer,
+) -> Result<usize> {
+    let mut failures = 0;
+    for (i, value) in values.iter().enumerate() {
+        let name = value
+            .get("name")
+            .and_then(Value::as_str)
+            .unwrap_or("Unnamed");
+        let result = SuiteCase::from_value(value).and_then(|case| case.check_all_variants());
+        let Err(error) = result else {
+            continue;
+        };
+        failures += 1;
+        let variant = failing_variant(&error);
+        match error.downcast_ref::<DragMismatch>() {
+            Some(mismatch) => {
+                let img_path = out_dir.join(format!(
+                    "{file_stem}__{}.png",
+                    sanitized_case_name(value, i)
+                ));
+                renderer.save_mismatch_png(mismatch, &img_path)?;
+                println!("✗ {file_stem}: {name} {variant} -> {}", img_path.display());
+            }
+            None => println!("✗ {file_stem}: {name} {variant}: {}", error.root_cause()),
+        }
+    }
+    Ok(failures)
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+    let mut files = Vec::new();
+    for path in yaml_files(&args.paths)? {
+        let text = fs::read_to_string(&path).with_context(|| format!("Reading {path:?}"))?;
+        let values: Vec<Value> =
+            serde_yaml::from_str(&text).with_context(|| format!("In {path:?}"))?;
+        let file_stem = path.file_stem().unwrap_or_default().to_string_lossy();
+        files.push((file_stem.to_string(), values));
+    }
+
+    fs::create_dir_all(&args.out_dir)?;
+    let renderer = ImageRenderer::new()?;
+    let mut failures = 0;
+    for (file_stem, values) in &files {
+        failures += render_file_failures(file_stem, values, &args.out_dir, &renderer)?;
+    }
+    println!("{failures} failing cases");
+    Ok(())
+}
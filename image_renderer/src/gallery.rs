@@ -0,0 +1,339 @@
+//! A static HTML page of the whole YAML test suite, for reading the spec
+//! without the game. Each case shows its worlds rendered with
+//! [`ImageRenderer::render_world`] (before, after, and `after_for_reverse` or
+//! each scenario drag's expected world), its flags and expected errors, and
+//! optionally whether it passes.
+
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+use anyhow::Result;
+use euclid::vec2;
+use itertools::Itertools;
+use prototype_abstract::suite_test_names::sanitized_case_name;
+use prototype_abstract::test_case::SuiteCase;
+use prototype_abstract::{BoundingBox, TilePosition, WorldImpl as World};
+use serde_yaml::Value;
+
+use crate::ImageRenderer;
+use crate::mismatch::{Errors, draw_errors};
+
+const STYLE: &str = "
+body { font-family: sans-serif; background: #1e1e1e; color: #ddd; margin: 2em; }
+a { color: #8cf; }
+.case { border-left: 4px solid #555; margin: 1em 0; padding: 0.2em 1em; }
+.case.pass { border-color: #4c4; }
+.case.fail { border-color: #e44; }
+.flags code { background: #333; padding: 0 0.3em; margin-right: 0.3em; }
+.panels { display: flex; flex-wrap: wrap; gap: 1em; align-items: flex-start; }
+figure { margin: 0; }
+figcaption { font-size: 0.85em; color: #aaa; }
+pre { background: #2a2a2a; padding: 0.5em; overflow-x: auto; }
+";
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct GallerySummary {
+    pub cases: usize,
+    /// Cases that don't parse, or (when checked) fail.
+    pub failures: usize,
+}
+
+struct Panel<'a> {
+    caption: String,
+    world: &'a World,
+    errors: Option<&'a Errors>,
+}
+
+/// Writes `index.html`, and its images under `images/`, to `out_dir`, for
+/// `(file name, cases)` pairs. If `check`, runs every case's variants and
+/// marks it pass or fail.
+pub fn write_gallery(
+    renderer: &ImageRenderer,
+    files: &[(String, Vec<Value>)],
+    out_dir: &Path,
+    check: bool,
+) -> Result<GallerySummary> {
+    let image_dir = out_dir.join("images");
+    fs::create_dir_all(&image_dir)?;
+
+    let mut summary = GallerySummary::default();
+    let mut body = String::new();
+    writeln!(body, "<h1>Test suite</h1>\n<ul>")?;
+    for (file, values) in files {
+        writeln!(
+            body,
+            "<li><a href=\"#{}\">{}</a> ({} cases)</li>",
+            file_stem(file),
+            escape_html(file),
+            values.len()
+        )?;
+    }
+    writeln!(body, "</ul>")?;
+
+    for (file, values) in files {
+        let stem = file_stem(file);
+        writeln!(body, "<h2 id=\"{stem}\">{}</h2>", escape_html(file))?;
+        for (i, value) in values.iter().enumerate() {
+            summary.cases += 1;
+            let name = value
+                .get("name")
+                .and_then(Value::as_str)
+                .unwrap_or("Unnamed");
+            let image_prefix = format!("{stem}__{}", sanitized_case_name(value, i));
+
+            let case = SuiteCase::from_value(value);
+            let result = match &case {
+                Err(e) => Some(Err(format!("Doesn't parse: {e:#}"))),
+                Ok(case) if check => Some(case.check_all_variants().map_err(|e| format!("{e:#}"))),
+                Ok(_) => None,
+            };
+            let class = match &result {
+                Some(Ok(())) => " pass",
+                Some(Err(_)) => {
+                    summary.failures += 1;
+                    " fail"
+                }
+                None => "",
+            };
+
+            writeln!(body, "<div class=\"case{class}\">")?;
+            writeln!(body, "<h3>{}</h3>", escape_html(name))?;
+            if let Ok(case) = &case {
+                write_case(&mut body, renderer, case, &image_dir, &image_prefix)?;
+            }
+            if let Some(Err(message)) = &result {
+                writeln!(body, "<pre>{}</pre>", escape_html(message))?;
+            }
+            writeln!(body, "</div>")?;
+        }
+    }
+
+    let html = format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>Test suite</title>
+<style>{STYLE}</style>
+</head>
+<body>
+{body}</body>
+</html>
+"#
+    );
+    fs::write(out_dir.join("index.html"), html)?;
+    Ok(summary)
+}
+
+fn write_case(
+    body: &mut String,
+    renderer: &ImageRenderer,
+    case: &SuiteCase,
+    image_dir: &Path,
+    image_prefix: &str,
+) -> Result<()> {
+    let (flags, errors, panels, points) = match case {
+        SuiteCase::Drag(case) => {
+            let test = &case.entities;
+            let mut flags = vec![
+                format!("tier {}", test.tier.tier_index() + 1),
+                format!("{:?}", test.belt_direction).to_lowercase(),
+                format!("start {}", show_pos(test.start_pos)),
+                format!("end {}", show_pos(test.end_pos)),
+            ];
+            let mut points = vec![test.start_pos, test.end_pos];
+            if case.forward_back {
+                flags.push(format!("forward_back to {}", show_pos(test.leftmost_pos)));
+                points.push(test.leftmost_pos);
+            }
+            if case.not_reversible {
+                flags.push("not_reversible".to_string());
+            }
+            if case.trace.is_some() {
+                flags.push("trace".to_string());
+            }
+            let mut panels = vec![
+                Panel {
+                    caption: "before".to_string(),
+                    world: &test.before,
+                    errors: None,
+                },
+                Panel {
+                    caption: "after".to_string(),
+                    world: &test.after,
+                    errors: Some(&test.expected_errors),
+                },
+            ];
+            if let Some(after_for_reverse) = &case.after_for_reverse {
+                panels.push(Panel {
+                    caption: "after_for_reverse".to_string(),
+                    world: after_for_reverse,
+                    errors: None,
+                });
+            }
+            (flags, show_errors(&test.expected_errors), panels, points)
+        }
+        SuiteCase::Steps(case) => {
+            let flags = vec![
+                format!("tier {}", case.tier.tier_index() + 1),
+                format!("{:?}", case.belt_direction).to_lowercase(),
+                format!("start {}", show_pos(case.start_pos)),
+                format!("{} steps", case.steps.len()),
+            ];
+            let panels = vec![
+                Panel {
+                    caption: "before".to_string(),
+                    world: &case.before,
+                    errors: None,
+                },
+                Panel {
+                    caption: "after".to_string(),
+                    world: &case.after,
+                    errors: Some(&case.expected_errors),
+                },
+            ];
+            let errors = show_errors(&case.expected_errors);
+            (flags, errors, panels, vec![case.start_pos])
+        }
+        SuiteCase::Scenario(case) => {
+            let flags = vec![format!("{} drags", case.drags.len())];
+            let mut panels = vec![Panel {
+                caption: "before".to_string(),
+                world: &case.before,
+                errors: None,
+            }];
+            let mut errors = Vec::new();
+            for (j, drag) in case.drags.iter().enumerate() {
+                let Some((world, drag_errors)) = &drag.expected else {
+                    continue;
+                };
+                panels.push(Panel {
+                    caption: format!("after drag {j}"),
+                    world,
+                    errors: Some(drag_errors),
+                });
+                errors.extend(
+                    show_errors(drag_errors)
+                        .into_iter()
+                        .map(|e| format!("drag {j}: {e}")),
+                );
+            }
+            let points = case.drags.iter().map(|d| d.start_pos).collect();
+            (flags, errors, panels, points)
+        }
+    };
+
+    writeln!(
+        body,
+        "<p class=\"flags\">{}</p>",
+        flags
+            .iter()
+            .map(|f| format!("<code>{}</code>", escape_html(f)))
+            .join("")
+    )?;
+
+    // The same bounds for every panel, so they line up
+    let bounds = panels
+        .iter()
+        .fold(BoundingBox::from_points(&points), |b, p| {
+            b.union(&p.world.bounds())
+        });
+    let bounds = BoundingBox::new(bounds.min, bounds.max.max(bounds.min + vec2(1, 1)));
+    writeln!(body, "<div class=\"panels\">")?;
+    for panel in &panels {
+        let file_name = format!("{image_prefix}__{}.png", panel.caption.replace(' ', "_"));
+        let mut pixmap = renderer.render_world(panel.world, bounds);
+        if let Some(errors) = panel.errors {
+            draw_errors(&mut pixmap, bounds, errors, errors);
+        }
+        pixmap.save_png(image_dir.join(&file_name))?;
+        writeln!(
+            body,
+            "<figure><img src=\"images/{file_name}\" alt=\"{caption}\"><figcaption>{caption}</figcaption></figure>",
+            caption = escape_html(&panel.caption)
+        )?;
+    }
+    writeln!(body, "</div>")?;
+
+    if !errors.is_empty() {
+        writeln!(body, "<p>Expected errors:</p>\n<ul>")?;
+        for error in errors {
+            writeln!(body, "<li>{}</li>", escape_html(&error))?;
+        }
+        writeln!(body, "</ul>")?;
+    }
+    Ok(())
+}
+
+fn file_stem(file: &str) -> &str {
+    file.strip_suffix(".yaml").unwrap_or(file)
+}
+
+fn show_pos(p: TilePosition) -> String {
+    format!("({}, {})", p.x, p.y)
+}
+
+/// Sorted by position, so the page is the same every time.
+fn show_errors(errors: &Errors) -> Vec<String> {
+    errors
+        .iter()
+        .sorted_by_key(|(p, e)| (p.y, p.x, e.clone()))
+        .map(|(p, e)| format!("{:?} at {}", e, show_pos(*p)))
+        .collect()
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gallery_marks_failing_cases() {
+        let renderer = ImageRenderer::new().unwrap();
+        let values: Vec<Value> = serde_yaml::from_str(
+            r#"
+- name: Obstacle
+  before: _ X _
+  after: ">i X >o"
+- name: Wrong <obstacle>
+  before: _ X _
+  after: "> X >"
+  after_for_reverse: "< X <"
+"#,
+        )
+        .unwrap();
+        let out_dir = tempfile::tempdir().unwrap();
+        let summary = write_gallery(
+            &renderer,
+            &[("obstacles.yaml".to_string(), values)],
+            out_dir.path(),
+            true,
+        )
+        .unwrap();
+        assert_eq!(
+            summary,
+            GallerySummary {
+                cases: 2,
+                failures: 1
+            }
+        );
+
+        let html = fs::read_to_string(out_dir.path().join("index.html")).unwrap();
+        assert!(html.contains("<h3>Wrong &lt;obstacle&gt;</h3>"));
+        assert_eq!(html.matches("class=\"case pass\"").count(), 1);
+        assert_eq!(html.matches("class=\"case fail\"").count(), 1);
+        assert!(
+            out_dir
+                .path()
+                .join("images/obstacles__wrong_obstacle__after_for_reverse.png")
+                .exists()
+        );
+    }
+}
@@ -1,14 +1,29 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use euclid::{Point2D, Rect, Size2D, Vector2D};
 use prototype_abstract::{
-    BeltCollidable, BeltConnectable, BoundingBox, Direction, Splitter, TilePosition,
-    WorldImpl as World,
+    BELT_TIERS, BeltCollidable, BeltConnectable, BeltConnectableTrait, BeltTier, BoundingBox,
+    Direction, Splitter, TilePosition, WorldImpl as World,
+};
+use std::cell::RefCell;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tiny_skia::{
+    Color, FillRule, Paint, PathBuilder, Pixmap, PremultipliedColorU8, Rect as SkiaRect, Stroke,
+    Transform,
 };
-use std::path::Path;
-use tiny_skia::{Pixmap, PremultipliedColorU8};
 
+pub mod animation;
+pub mod annotations;
+pub mod assets;
+pub mod gallery;
+pub mod mismatch;
+pub mod svg;
+pub mod tiers;
 pub mod tilemaps;
-use tilemaps::Tilemaps;
+use assets::Assets;
+use rusttype::Font;
+use tiers::{BUILT_IN_TIERS, TierStyle};
+use tilemaps::TierTilemaps;
 
 pub struct PixelSpace;
 pub type PixelPoint = Point2D<i32, PixelSpace>;
@@ -50,21 +65,131 @@ fn blocker_color() -> PremultipliedColorU8 {
     PremultipliedColorU8::from_rgba(250, 200, 200, 255).unwrap()
 }
 
+fn impassable_color() -> PremultipliedColorU8 {
+    PremultipliedColorU8::from_rgba(30, 50, 80, 255).unwrap()
+}
+
+fn impassable_hatch_color() -> PremultipliedColorU8 {
+    PremultipliedColorU8::from_rgba(45, 74, 115, 255).unwrap()
+}
+
+fn loader_color() -> Color {
+    Color::from_rgba8(110, 110, 110, 255)
+}
+
+fn loader_mouth_color() -> Color {
+    Color::from_rgba8(30, 30, 30, 255)
+}
+
+/// Clockwise from east, as the y axis points down.
+pub(crate) fn rotation_degrees(direction: Direction) -> i32 {
+    match direction {
+        Direction::East => 0,
+        Direction::South => 90,
+        Direction::West => 180,
+        Direction::North => 270,
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 enum RenderLayer {
     Bottom,
     Top,
 }
 
+/// A tier's sprites, and its color for what has no sprites.
+struct Tier {
+    tier: BeltTier,
+    tilemaps: TierTilemaps,
+    color: Color,
+}
+
 pub struct ImageRenderer {
-    tilemaps: Tilemaps,
+    assets: Assets,
+    tiers: Vec<Tier>,
+    /// Tiers without a style set, recolored as by default when first
+    /// rendered.
+    recolored: RefCell<Vec<Tier>>,
+    font: Font<'static>,
 }
 
 impl ImageRenderer {
+    /// A renderer using the sprites embedded in the binary.
     pub fn new() -> Result<Self> {
-        let assets_path = Path::new(env!("CARGO_MANIFEST_DIR")).join("assets");
-        let tilemaps = Tilemaps::load_from_assets(&assets_path)?;
-        Ok(ImageRenderer { tilemaps })
+        Self::with_assets(&Assets::embedded())
+    }
+
+    pub fn with_assets(assets: &Assets) -> Result<Self> {
+        let tiers = BELT_TIERS
+            .iter()
+            .zip(BUILT_IN_TIERS)
+            .map(|(&tier, (prefix, [r, g, b]))| {
+                Ok(Tier {
+                    tier,
+                    tilemaps: TierTilemaps::load(assets, prefix)?,
+                    color: Color::from_rgba8(r, g, b, 255),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let font_name = "fonts/DejaVuSansCondensed-Bold.ttf";
+        let font = Font::try_from_vec(assets.read(font_name)?.into_owned())
+            .with_context(|| format!("{font_name} is not a font"))?;
+        Ok(ImageRenderer {
+            assets: assets.clone(),
+            tiers,
+            recolored: RefCell::new(Vec::new()),
+            font,
+        })
+    }
+
+    /// Draws `tier` in `style`, rather than its [`TierStyle::default_for`].
+    /// Tiers without a style of their own are recolored the first time they
+    /// are rendered.
+    pub fn set_tier_style(&mut self, tier: BeltTier, style: &TierStyle) -> Result<()> {
+        let tilemaps = match style {
+            TierStyle::Sheets { prefix, .. } => TierTilemaps::load(&self.assets, prefix)?,
+            TierStyle::HueShift(degrees) => self.tiers[0].tilemaps.hue_rotated(*degrees),
+        };
+        let [r, g, b] = style.color();
+        let new_tier = Tier {
+            tier,
+            tilemaps,
+            color: Color::from_rgba8(r, g, b, 255),
+        };
+        match self.tiers.iter_mut().find(|t| t.tier == tier) {
+            Some(existing) => *existing = new_tier,
+            None => self.tiers.push(new_tier),
+        }
+        self.recolored.get_mut().retain(|t| t.tier != tier);
+        Ok(())
+    }
+
+    /// Recolors the tiers in `bounds` without a style set, if not done yet.
+    fn recolor_new_tiers(&self, world: &World, bounds: BoundingBox) {
+        let mut recolored = self.recolored.borrow_mut();
+        for y in bounds.min.y..bounds.max.y {
+            for x in bounds.min.x..bounds.max.x {
+                let Some(tier) = world
+                    .get(prototype_abstract::pos(x, y))
+                    .and_then(|entity| BeltConnectable::try_from(entity).ok())
+                    .map(|connectable| connectable.tier())
+                else {
+                    continue;
+                };
+                if self.tiers.iter().chain(&*recolored).any(|t| t.tier == tier) {
+                    continue;
+                }
+                let TierStyle::HueShift(degrees) = TierStyle::default_for(tier) else {
+                    unreachable!("Only the built-in tiers have sheets by default");
+                };
+                let [r, g, b] = TierStyle::HueShift(degrees).color();
+                recolored.push(Tier {
+                    tier,
+                    tilemaps: self.tiers[0].tilemaps.hue_rotated(degrees),
+                    color: Color::from_rgba8(r, g, b, 255),
+                });
+            }
+        }
     }
 
     pub fn render_world(&self, world: &World, bounds: BoundingBox) -> Pixmap {
@@ -77,8 +202,11 @@ impl ImageRenderer {
 
         self.draw_checkerboard_background(&mut pixmap, bounds);
 
+        self.recolor_new_tiers(world, bounds);
+        let recolored = self.recolored.borrow();
+        let tiers = self.tiers.iter().chain(&*recolored).collect::<Vec<_>>();
         for layer in &[RenderLayer::Bottom, RenderLayer::Top] {
-            self.render_all_entities(&mut pixmap, bounds, world, *layer);
+            self.render_all_entities(&mut pixmap, bounds, world, &tiers, *layer);
         }
 
         pixmap
@@ -113,14 +241,13 @@ impl ImageRenderer {
         pixmap: &mut Pixmap,
         bounds: BoundingBox,
         world: &World,
+        tiers: &[&Tier],
         layer: RenderLayer,
     ) {
         for y in bounds.min.y..bounds.max.y {
             for x in bounds.min.x..bounds.max.x {
                 let pos = prototype_abstract::pos(x, y);
-                if let Some(entity) = world.get(pos) {
-                    self.render_entity(pixmap, entity, bounds, pos, world, layer);
-                }
+                self.render_entity(pixmap, bounds, pos, world, tiers, layer);
             }
         }
     }
@@ -128,53 +255,58 @@ impl ImageRenderer {
     fn render_entity(
         &self,
         canvas: &mut Pixmap,
-        entity: &BeltCollidable,
         bounds: BoundingBox,
         pos: TilePosition,
         world: &World,
+        tiers: &[&Tier],
         layer: RenderLayer,
     ) {
+        let Some(entity) = world.get(pos) else {
+            return;
+        };
+        // Every tier in the world has an entry, styled or by default
+        let tier_of = |tier: BeltTier| *tiers.iter().find(|t| t.tier == tier).unwrap();
         let pixel_pos = ((pos - bounds.min.cast_unit()) * (TILE_SIZE as i32))
             .to_point()
             .cast_unit();
 
         match (BeltConnectable::try_from(entity).ok(), layer) {
             (Some(BeltConnectable::Belt(belt)), RenderLayer::Bottom) => {
-                let tier_idx = belt.tier.tier_index();
+                let tier = tier_of(belt.tier);
                 self.render_belt(
                     canvas,
                     belt.direction,
                     world.input_direction_at(pos),
                     pixel_pos,
-                    tier_idx,
+                    tier,
                 );
             }
             (Some(BeltConnectable::UndergroundBelt(underground)), RenderLayer::Bottom) => {
-                let tier_idx = underground.tier.tier_index();
+                let tier = tier_of(underground.tier);
                 self.render_underground_belt_base(
                     canvas,
                     underground.direction,
                     underground.is_input,
                     pixel_pos,
-                    tier_idx,
+                    tier,
                 );
             }
             (Some(BeltConnectable::UndergroundBelt(underground)), RenderLayer::Top) => {
-                let tier_idx = underground.tier.tier_index();
+                let tier = tier_of(underground.tier);
                 self.render_underground_belt_structure(
                     canvas,
                     underground.direction,
                     underground.is_input,
                     pixel_pos,
-                    tier_idx,
+                    tier,
                 );
             }
             (Some(BeltConnectable::Splitter(splitter)), RenderLayer::Bottom) => {
-                let tier_idx = splitter.tier.tier_index();
-                self.render_belt(canvas, splitter.direction, None, pixel_pos, tier_idx);
+                let tier = tier_of(splitter.tier);
+                self.render_belt(canvas, splitter.direction, None, pixel_pos, tier);
             }
             (Some(BeltConnectable::Splitter(splitter)), RenderLayer::Top) => {
-                let tier_idx = splitter.tier.tier_index();
+                let tier = tier_of(splitter.tier);
                 let is_head = if let Some(tail_pos) = get_tail_pos(&splitter, pos, bounds)
                     && matches!(world.get(tail_pos), Some(BeltCollidable::Splitter(_)))
                 {
@@ -187,9 +319,27 @@ impl ImageRenderer {
                     splitter.direction,
                     is_head,
                     pixel_pos,
-                    tier_idx,
+                    tier,
+                );
+            }
+            (Some(BeltConnectable::LoaderLike(loader)), RenderLayer::Bottom) => {
+                // The structure covers the half of the belt that isn't used
+                let tier = tier_of(loader.tier);
+                self.render_belt(canvas, loader.direction, None, pixel_pos, tier);
+            }
+            (Some(BeltConnectable::LoaderLike(loader)), RenderLayer::Top) => {
+                let tier = tier_of(loader.tier);
+                self.render_loader_structure(
+                    canvas,
+                    loader.direction,
+                    loader.is_input,
+                    pixel_pos,
+                    tier,
                 );
             }
+            (None, RenderLayer::Bottom) if entity.is_impassable_tile() => {
+                self.render_impassable(canvas, pixel_pos);
+            }
             (_, RenderLayer::Bottom) => {
                 self.render_blocker(canvas, pixel_pos);
             }
@@ -203,7 +353,7 @@ impl ImageRenderer {
         output_direction: Direction,
         input_direction: Option<Direction>,
         pixel_pos: PixelPoint,
-        tier_index: usize,
+        tier: &Tier,
     ) {
         const CURVED_INDICES: [[u8; 4]; 3] = [[11, 8, 4, 7], [0, 2, 1, 3], [6, 10, 9, 5]];
 
@@ -211,7 +361,7 @@ impl ImageRenderer {
         let output_py = direction_to_python_index(output_direction);
         let direction_diff = (output_py + 4 - input_py + 1) % 4;
         let tile_index = CURVED_INDICES[direction_diff][input_py];
-        self.tilemaps.belt[tier_index].draw(
+        tier.tilemaps.belt.draw(
             canvas,
             pixel_pos - Vector2D::splat(TILE_SIZE as i32 / 2),
             (ANIMATION_FRAME, tile_index),
@@ -226,7 +376,7 @@ impl ImageRenderer {
         direction: Direction,
         is_input: bool,
         pixel_pos: PixelPoint,
-        tier_index: usize,
+        tier: &Tier,
     ) {
         let indices = if !is_input {
             [14, 12, 18, 16]
@@ -235,7 +385,7 @@ impl ImageRenderer {
         };
         let tile_index = indices[direction_to_python_index(direction)];
 
-        self.tilemaps.belt[tier_index].draw(
+        tier.tilemaps.belt.draw(
             canvas,
             pixel_pos - Vector2D::splat(TILE_SIZE as i32 / 2),
             (ANIMATION_FRAME, tile_index),
@@ -250,7 +400,7 @@ impl ImageRenderer {
         direction: Direction,
         is_input: bool,
         pixel_pos: PixelPoint,
-        tier_index: usize,
+        tier: &Tier,
     ) {
         let indices = if !is_input {
             [3, 2, 1, 0]
@@ -259,7 +409,7 @@ impl ImageRenderer {
         };
         let tile_index = indices[direction_to_python_index(direction)];
 
-        self.tilemaps.underground[tier_index].draw(
+        tier.tilemaps.underground.draw(
             canvas,
             pixel_pos - Vector2D::splat(TILE_SIZE as i32),
             (tile_index, is_input as u8),
@@ -274,7 +424,7 @@ impl ImageRenderer {
         direction: Direction,
         is_head: bool,
         pixel_pos: PixelPoint,
-        tier_index: usize,
+        tier: &Tier,
     ) {
         match direction {
             Direction::East => {
@@ -287,7 +437,7 @@ impl ImageRenderer {
                             (5 * TILE_SIZE) / 16
                         } as i32,
                     );
-                self.tilemaps.splitter_east[tier_index][if !is_head { 1 } else { 0 }].draw(
+                tier.tilemaps.splitter_east[if !is_head { 1 } else { 0 }].draw(
                     canvas,
                     adjusted_pos,
                     ((ANIMATION_FRAME % 8), (ANIMATION_FRAME / 8) % 4),
@@ -296,7 +446,7 @@ impl ImageRenderer {
                 );
             }
             Direction::West => {
-                self.tilemaps.splitter_west[tier_index][if !is_head { 0 } else { 1 }].draw(
+                tier.tilemaps.splitter_west[if !is_head { 0 } else { 1 }].draw(
                     canvas,
                     pixel_pos - Vector2D::new(0, (5 * TILE_SIZE as i32) / 16),
                     ((ANIMATION_FRAME % 8), (ANIMATION_FRAME / 8) % 4),
@@ -316,7 +466,7 @@ impl ImageRenderer {
                         fractional_to_pixel_vector(1.0 - 13.0 / 32.0, 1.0),
                     )
                 };
-                self.tilemaps.splitter_north[tier_index].draw(
+                tier.tilemaps.splitter_north.draw(
                     canvas,
                     pixel_pos,
                     ((ANIMATION_FRAME % 8), (ANIMATION_FRAME / 8) % 4),
@@ -339,7 +489,7 @@ impl ImageRenderer {
                         fractional_to_pixel_vector(1.0 - 14.0 / 32.0, 1.0),
                     )
                 };
-                self.tilemaps.splitter_south[tier_index].draw(
+                tier.tilemaps.splitter_south.draw(
                     canvas,
                     adjusted_pos,
                     (ANIMATION_FRAME % 8, (ANIMATION_FRAME / 8) % 4),
@@ -350,7 +500,65 @@ impl ImageRenderer {
         }
     }
 
+    /// There are no loader sprites, so this is a box over the half of the tile
+    /// the belt doesn't use, outlined in the tier's color, with a dark mouth
+    /// where the belt goes in or out.
+    fn render_loader_structure(
+        &self,
+        canvas: &mut Pixmap,
+        direction: Direction,
+        is_input: bool,
+        pixel_pos: PixelPoint,
+        tier: &Tier,
+    ) {
+        // In tiles, with the loader facing +x and the tile spanning -0.5..0.5
+        let half_tile = TILE_SIZE as f32 / 2.0;
+        let transform = Transform::from_translate(
+            pixel_pos.x as f32 + half_tile,
+            pixel_pos.y as f32 + half_tile,
+        )
+        .pre_rotate(rotation_degrees(direction) as f32)
+        .pre_scale(TILE_SIZE as f32, TILE_SIZE as f32);
+        let (x, mouth_x) = if is_input { (0.0, 0.0) } else { (-0.45, -0.1) };
+
+        let body = PathBuilder::from_rect(SkiaRect::from_xywh(x, -0.45, 0.45, 0.9).unwrap());
+        let mut paint = Paint::default();
+        paint.set_color(loader_color());
+        canvas.fill_path(&body, &paint, FillRule::Winding, transform, None);
+        paint.set_color(tier.color);
+        let stroke = Stroke {
+            width: 0.05,
+            ..Default::default()
+        };
+        canvas.stroke_path(&body, &paint, &stroke, transform, None);
+
+        let mouth = SkiaRect::from_xywh(mouth_x, -0.3, 0.1, 0.6).unwrap();
+        paint.set_color(loader_mouth_color());
+        canvas.fill_rect(mouth, &paint, transform, None);
+    }
+
     fn render_blocker(&self, canvas: &mut Pixmap, pixel_pos: PixelPoint) {
+        self.fill_tile(canvas, pixel_pos, |_, _| blocker_color());
+    }
+
+    /// Water or void: hatched, to tell it apart from entities in the way.
+    fn render_impassable(&self, canvas: &mut Pixmap, pixel_pos: PixelPoint) {
+        self.fill_tile(canvas, pixel_pos, |x, y| {
+            if (x + y) % (TILE_SIZE / 2) < TILE_SIZE / 16 {
+                impassable_hatch_color()
+            } else {
+                impassable_color()
+            }
+        });
+    }
+
+    /// Sets each pixel of the tile to `color(x, y)`, relative to the tile.
+    fn fill_tile(
+        &self,
+        canvas: &mut Pixmap,
+        pixel_pos: PixelPoint,
+        color: impl Fn(u32, u32) -> PremultipliedColorU8,
+    ) {
         let canvas_size = (canvas.width(), canvas.height());
         let pixels = canvas.pixels_mut();
         let tile_bounds = (
@@ -363,7 +571,8 @@ impl ImageRenderer {
         for y in tile_bounds.1..tile_bounds.3 {
             let row_start = (y * canvas_size.0) as usize;
             for x in tile_bounds.0..tile_bounds.2 {
-                pixels[row_start + x as usize] = blocker_color();
+                pixels[row_start + x as usize] =
+                    color(x - pixel_pos.x as u32, y - pixel_pos.y as u32);
             }
         }
     }
@@ -380,6 +589,29 @@ impl ImageRenderer {
     }
 }
 
+/// `*.yaml` files in `paths`, where directories are expanded to the files in
+/// them, in name order.
+pub fn yaml_files(paths: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for path in paths {
+        if path.is_dir() {
+            let mut dir_files = fs::read_dir(path)
+                .and_then(|entries| {
+                    entries
+                        .map(|entry| entry.map(|e| e.path()))
+                        .collect::<Result<Vec<_>, _>>()
+                })
+                .with_context(|| format!("Reading {path:?}"))?;
+            dir_files.retain(|p| p.extension().is_some_and(|e| e == "yaml"));
+            dir_files.sort();
+            files.extend(dir_files);
+        } else {
+            files.push(path.clone());
+        }
+    }
+    Ok(files)
+}
+
 pub fn get_tail_pos(
     splitter: &Splitter,
     position: TilePosition,
@@ -397,7 +629,10 @@ pub fn get_tail_pos(
 mod tests {
     use super::*;
     use prototype_abstract::{
-        Belt, Splitter, UndergroundBelt, WorldImpl as World, belts::YELLOW_BELT, pos,
+        Belt, Splitter, UndergroundBelt, WorldImpl as World,
+        belts::{BeltTierData, YELLOW_BELT},
+        pos,
+        test_case::parse_world,
     };
 
     #[test]
@@ -451,4 +686,59 @@ mod tests {
 
         let _ = pixmap.save_png("test.png");
     }
+
+    #[test]
+    fn test_render_tiers_past_the_built_in_ones() {
+        static TURBO: BeltTierData = BeltTierData {
+            name: "Turbo",
+            underground_distance: 11,
+        };
+        let turbo = BeltTier::new(&TURBO);
+        let mut renderer = ImageRenderer::new().expect("Failed to create renderer");
+        let bounds = prototype_abstract::bounds_new(pos(0, 0), pos(2, 1));
+        let render = |renderer: &ImageRenderer, tier| {
+            let mut world = World::new();
+            world.build(pos(0, 0), Belt::new(Direction::East, tier).into());
+            world.build(pos(1, 0), Splitter::new(Direction::East, tier).into());
+            renderer.render_world(&world, bounds)
+        };
+
+        let recolored = render(&renderer, turbo);
+        assert_ne!(recolored.data(), render(&renderer, YELLOW_BELT).data());
+        // Recolored once, for every render
+        assert_eq!(render(&renderer, turbo).data(), recolored.data());
+        assert_eq!(renderer.recolored.borrow().len(), 1);
+
+        renderer
+            .set_tier_style(
+                turbo,
+                &TierStyle::Sheets {
+                    prefix: "express-".to_string(),
+                    color: [0, 0, 0],
+                },
+            )
+            .unwrap();
+        assert!(renderer.recolored.borrow().is_empty());
+        assert_eq!(
+            render(&renderer, turbo).data(),
+            render(&renderer, BELT_TIERS[2]).data()
+        );
+    }
+
+    #[test]
+    fn test_render_obstacles_and_loaders_distinctly() {
+        let renderer = ImageRenderer::new().expect("Failed to create renderer");
+        let (world, _) = parse_world("X # >I >O").unwrap();
+        let bounds = prototype_abstract::bounds_new(pos(0, 0), pos(4, 1));
+        let pixmap = renderer.render_world(&world, bounds);
+
+        // A point in the back half of each tile: the belt of the input loader,
+        // and the structure of the output loader
+        let y = TILE_SIZE / 2;
+        let sample = |tile: u32| pixmap.pixel(tile * TILE_SIZE + TILE_SIZE / 4, y).unwrap();
+        assert_eq!(sample(0), blocker_color());
+        assert_eq!(sample(1), impassable_color());
+        assert_ne!(sample(2), blocker_color());
+        assert_eq!(sample(3), loader_color().premultiply().to_color_u8());
+    }
 }
@@ -4,20 +4,29 @@ use std::{
     str::FromStr,
 };
 
-use anyhow::{Context, Result};
-use clap::Parser;
+use anyhow::{Context, Result, bail};
+use clap::{Parser, ValueEnum};
 use comrak::{
     Arena, format_commonmark,
     nodes::{NodeLink, NodeValue},
     parse_document,
 };
-use image_renderer::{ImageRenderer, get_tail_pos};
+use euclid::vec2;
+use image_renderer::{
+    ImageRenderer, annotations::Annotation, assets::Assets, get_tail_pos, svg::SvgRenderer,
+};
 
-use prototype_abstract::{BeltCollidable, Splitter, WorldImpl, bounds_new, pos};
+use prototype_abstract::test_case::{DragStep, parse_labeled_world, run_drag_steps};
+use prototype_abstract::{
+    BELT_TIERS, BeltCollidable, BeltTier, BoundingBox, Direction, Splitter, TilePosition,
+    WorldImpl, bounds_new, pos,
+};
 
 #[derive(Clone)]
 struct FacImg {
     world: WorldImpl,
+    /// `*` markers, which are highlighted.
+    markers: Vec<TilePosition>,
     width: usize,
     height: usize,
 }
@@ -26,7 +35,7 @@ impl FromStr for FacImg {
     type Err = anyhow::Error;
 
     fn from_str(s: &str) -> Result<Self> {
-        let (world, _) = prototype_abstract::test_case::parse_world(s)?;
+        let (world, markers) = prototype_abstract::test_case::parse_world(s)?;
 
         // Calculate dimensions from the input text to preserve the original grid size
         let lines: Vec<&str> = s.lines().collect();
@@ -39,12 +48,77 @@ impl FromStr for FacImg {
 
         Ok(FacImg {
             world,
+            markers,
             width,
             height,
         })
     }
 }
 
+/// A drag to simulate: a grid with `[start]` and `[end]` markers, optionally
+/// preceded by `direction: <dir>` (default: from start to end) and
+/// `tier: <n>` (default: 1) lines.
+#[derive(Clone)]
+struct FacDrag {
+    before: WorldImpl,
+    width: usize,
+    height: usize,
+    start: TilePosition,
+    end: TilePosition,
+    direction: Direction,
+    tier: BeltTier,
+}
+
+impl FromStr for FacDrag {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut direction = None;
+        let mut tier = BELT_TIERS[0];
+        let mut lines = s.lines().peekable();
+        while let Some((key, value)) = lines.peek().and_then(|line| line.split_once(':')) {
+            match key.trim() {
+                "direction" => direction = Some(serde_yaml::from_str(value)?),
+                "tier" => {
+                    let n: usize = value.trim().parse()?;
+                    tier = BeltTier::from_number(n).with_context(|| {
+                        format!("'tier' {n} is not between 1 and {}", BELT_TIERS.len())
+                    })?;
+                }
+                key => bail!("Unknown fac-drag option {key:?}"),
+            }
+            lines.next();
+        }
+
+        let grid = lines.collect::<Vec<_>>().join("\n");
+        let parsed = parse_labeled_world(&grid)?;
+        let label = |name: &str| {
+            parsed
+                .labels
+                .get(name)
+                .copied()
+                .with_context(|| format!("fac-drag needs a [{name}] marker"))
+        };
+        let (start, end) = (label("start")?, label("end")?);
+        let step = end - start;
+        let direction = match direction {
+            Some(direction) => direction,
+            None => Direction::from_vector(vec2(step.x.signum(), step.y.signum()))
+                .context("[end] must be in a straight line from, and not at, [start]")?,
+        };
+
+        Ok(FacDrag {
+            before: parsed.world,
+            width: parsed.size.x as usize,
+            height: parsed.size.y as usize,
+            start,
+            end,
+            direction,
+            tier,
+        })
+    }
+}
+
 fn apply_splitter_completion(world: &mut WorldImpl) -> Result<()> {
     let bounds = world.bounds();
     if bounds.is_empty() {
@@ -72,50 +146,143 @@ fn apply_splitter_completion(world: &mut WorldImpl) -> Result<()> {
 
     Ok(())
 }
-fn render(mut grid: FacImg, img_out_path: &Path, renderer: &ImageRenderer) -> Result<()> {
+fn render(mut grid: FacImg, img_out_path: &Path, renderer: &Renderer) -> Result<()> {
     apply_splitter_completion(&mut grid.world)?;
     let bounds = bounds_new(pos(0, 0), pos(grid.width as i32, grid.height as i32));
-    renderer.save_png(&grid.world, bounds, img_out_path)?;
+    let markers = grid.markers.iter().map(|&p| Annotation::Marker(p));
+    renderer.save(
+        &grid.world,
+        bounds,
+        &markers.collect::<Vec<_>>(),
+        img_out_path,
+    )?;
 
     Ok(())
 }
 
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Format {
+    Png,
+    /// Vector shapes rather than sprites; sharp at any zoom, and much smaller
+    Svg,
+}
+
+enum Renderer {
+    Png(Box<ImageRenderer>),
+    Svg(SvgRenderer),
+}
+
+impl Renderer {
+    fn new(format: Format, assets: &Assets) -> Result<Self> {
+        Ok(match format {
+            Format::Png => Renderer::Png(Box::new(ImageRenderer::with_assets(assets)?)),
+            Format::Svg => Renderer::Svg(SvgRenderer::new()),
+        })
+    }
+
+    fn extension(&self) -> &'static str {
+        match self {
+            Renderer::Png(_) => "png",
+            Renderer::Svg(_) => "svg",
+        }
+    }
+
+    fn save(
+        &self,
+        world: &WorldImpl,
+        bounds: BoundingBox,
+        annotations: &[Annotation],
+        path: &Path,
+    ) -> Result<()> {
+        match self {
+            Renderer::Png(renderer) => renderer
+                .render_world_annotated(world, bounds, annotations)
+                .save_png(path)?,
+            Renderer::Svg(renderer) => fs::write(
+                path,
+                renderer.render_world_annotated(world, bounds, annotations),
+            )?,
+        }
+        Ok(())
+    }
+}
+
+/// Runs the drag, and renders the worlds before it (with the drag drawn on)
+/// and after it (with its errors).
+fn render_drag(
+    drag: FacDrag,
+    before_out_path: &Path,
+    after_out_path: &Path,
+    renderer: &Renderer,
+) -> Result<()> {
+    let mut after = drag.before.clone();
+    let errors = run_drag_steps(
+        &mut after,
+        drag.tier,
+        drag.start,
+        drag.direction,
+        &[DragStep::MoveTo(drag.end)],
+    )?;
+
+    let bounds = bounds_new(pos(0, 0), pos(drag.width as i32, drag.height as i32));
+    let mut before = drag.before;
+    apply_splitter_completion(&mut before)?;
+    apply_splitter_completion(&mut after)?;
+    let ray = Annotation::DragRay {
+        start: drag.start,
+        end: drag.end,
+    };
+    renderer.save(&before, bounds, &[ray], before_out_path)?;
+    renderer.save(&after, bounds, &Annotation::errors(&errors), after_out_path)?;
+    Ok(())
+}
+
 fn process_markdown(
     input: &str,
     image_out_dir: &Path,
     file_out_path: &Path,
     prefix: &str,
+    renderer: &Renderer,
 ) -> Result<()> {
     let arena = Arena::new();
     let root = parse_document(&arena, input, &Default::default());
 
-    let renderer = ImageRenderer::new()?;
+    let ext = renderer.extension();
     let imgs = root
         .descendants()
         .filter_map(|node| {
             if let NodeValue::CodeBlock(cb) = &node.data.borrow().value
-                && cb.info == "fac-img"
+                && (cb.info == "fac-img" || cb.info == "fac-drag")
             {
-                Some((node, cb.literal.clone()))
+                Some((node, cb.info.clone(), cb.literal.clone()))
             } else {
                 None
             }
         })
         .enumerate()
-        .map(|(id, (node, literal))| {
-            let img_out_path = image_out_dir.join(format!("{}_{}.png", prefix, id));
+        .map(|(id, (node, info, literal))| {
             let file_out_path = file_out_path.to_path_buf();
-            (
-                id,
-                node,
-                try_make_img(&file_out_path, &img_out_path, &literal, &renderer),
-            )
+            let links = if info == "fac-drag" {
+                let img_out_path =
+                    |stage| image_out_dir.join(format!("{prefix}_{id}_{stage}.{ext}"));
+                try_make_drag_imgs(
+                    &file_out_path,
+                    [&img_out_path("before"), &img_out_path("after")],
+                    &literal,
+                    renderer,
+                )
+                .map(Vec::from)
+            } else {
+                let img_out_path = image_out_dir.join(format!("{}_{}.{}", prefix, id, ext));
+                try_make_img(&file_out_path, &img_out_path, &literal, renderer).map(|l| vec![l])
+            };
+            (id, node, links)
         })
         .collect::<Vec<_>>();
 
     for (id, node, result) in imgs {
-        let link = match result {
-            Ok(link) => link.clone(),
+        let links = match result {
+            Ok(links) => links,
             Err(err) => {
                 eprintln!("Failed to process image {}: {}", id, err);
                 continue;
@@ -123,8 +290,10 @@ fn process_markdown(
         };
 
         let paragraph = arena.alloc(NodeValue::Paragraph.into());
-        let img_node = arena.alloc(NodeValue::Image(link).into());
-        paragraph.append(img_node);
+        for link in links {
+            let img_node = arena.alloc(NodeValue::Image(link).into());
+            paragraph.append(img_node);
+        }
         node.insert_after(paragraph);
         node.detach();
     }
@@ -137,10 +306,28 @@ fn try_make_img(
     file_out_path: &Path,
     img_out_path: &Path,
     literal: &str,
-    renderer: &ImageRenderer,
+    renderer: &Renderer,
 ) -> Result<NodeLink, anyhow::Error> {
     let img = FacImg::from_str(literal)?;
     render(img, img_out_path, renderer)?;
+    image_link(file_out_path, img_out_path)
+}
+
+fn try_make_drag_imgs(
+    file_out_path: &Path,
+    [before_out_path, after_out_path]: [&Path; 2],
+    literal: &str,
+    renderer: &Renderer,
+) -> Result<[NodeLink; 2], anyhow::Error> {
+    let drag = FacDrag::from_str(literal)?;
+    render_drag(drag, before_out_path, after_out_path, renderer)?;
+    Ok([
+        image_link(file_out_path, before_out_path)?,
+        image_link(file_out_path, after_out_path)?,
+    ])
+}
+
+fn image_link(file_out_path: &Path, img_out_path: &Path) -> Result<NodeLink> {
     Ok(NodeLink {
         url: pathdiff::diff_paths(img_out_path, file_out_path.parent().unwrap())
             .with_context(|| "Failed to generate image link")?
@@ -158,7 +345,7 @@ fn remove_old_images(image_out_dir: &Path, prefix: &str) {
         let path = entry.path();
         if let Some(name) = path.file_name().and_then(|n| n.to_str())
             && name.starts_with(&format!("{}_", prefix))
-            && name.ends_with(".png")
+            && (name.ends_with(".png") || name.ends_with(".svg"))
         {
             std::fs::remove_file(&path).ok();
         }
@@ -179,6 +366,13 @@ struct Args {
         help = "Remove older images matching the naming scheme"
     )]
     remove_old: bool,
+    #[arg(long, short, value_enum, default_value = "png")]
+    format: Format,
+    #[arg(
+        long,
+        help = "Directory of sprites to use in place of the built-in ones of the same name"
+    )]
+    assets: Option<PathBuf>,
 }
 
 fn main() -> Result<()> {
@@ -199,5 +393,48 @@ fn main() -> Result<()> {
     if args.remove_old {
         remove_old_images(&args.img_dir, &prefix);
     }
-    process_markdown(&input, &args.img_dir, &output_md, &prefix)
+    let assets = match args.assets {
+        Some(dir) => Assets::with_overrides(dir),
+        None => Assets::embedded(),
+    };
+    let renderer = Renderer::new(args.format, &assets)?;
+    process_markdown(&input, &args.img_dir, &output_md, &prefix, &renderer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn drag_error(s: &str) -> String {
+        format!("{:#}", FacDrag::from_str(s).err().unwrap())
+    }
+
+    #[test]
+    fn test_fac_drag_infers_direction() {
+        let drag = FacDrag::from_str("_ [end]_ X [start]_").unwrap();
+        assert_eq!((drag.start, drag.end), (pos(3, 0), pos(1, 0)));
+        assert_eq!(drag.direction, Direction::West);
+        assert_eq!(drag.tier.tier_index(), 0);
+        assert_eq!((drag.width, drag.height), (4, 1));
+
+        let drag = FacDrag::from_str("[start]_\n_\n[end]_").unwrap();
+        assert_eq!(drag.direction, Direction::South);
+    }
+
+    #[test]
+    fn test_fac_drag_options() {
+        let drag = FacDrag::from_str("direction: west\ntier: 2\n[end]_ X [start]_").unwrap();
+        assert_eq!(drag.direction, Direction::West);
+        assert_eq!(drag.tier.tier_index(), 1);
+        assert_eq!((drag.start, drag.end), (pos(2, 0), pos(0, 0)));
+    }
+
+    #[test]
+    fn test_fac_drag_errors() {
+        assert!(drag_error("[start]_ _ _").contains("needs a [end] marker"));
+        assert!(drag_error("[start]_ _\n_ [end]_").contains("straight line"));
+        assert!(drag_error("[start][end]_").contains("straight line"));
+        assert!(drag_error("speed: 2\n[start]_ [end]_").contains("Unknown fac-drag option"));
+        assert!(drag_error("tier: 9\n[start]_ [end]_").contains("not between 1 and"));
+    }
 }
@@ -0,0 +1,222 @@
+//! Renders a failed test case ([`DragMismatch`]) as before, expected and
+//! actual side by side. Tiles that render differently between expected and
+//! actual (including only in curvature) are outlined, and errors are drawn as
+//! dots: orange if expected and actual agree on it, red if not.
+
+use prototype_abstract::test_case::DragMismatch;
+use prototype_abstract::{BoundingBox, TilePosition, WorldImpl as World, pos};
+use std::collections::HashSet;
+use std::path::Path;
+
+use anyhow::Result;
+use tiny_skia::{
+    Color, FillRule, Paint, PathBuilder, Pixmap, PixmapPaint, Rect, Stroke, Transform,
+};
+
+use crate::{ImageRenderer, TILE_SIZE};
+
+const GAP: u32 = TILE_SIZE / 2;
+
+pub type Errors = HashSet<(TilePosition, prototype_abstract::smart_belt::action::Error)>;
+
+fn background_color() -> Color {
+    Color::from_rgba8(30, 30, 30, 255)
+}
+
+fn diff_outline_color() -> Color {
+    Color::from_rgba8(255, 40, 40, 255)
+}
+
+fn matching_error_color() -> Color {
+    Color::from_rgba8(255, 170, 0, 255)
+}
+
+fn mismatched_error_color() -> Color {
+    Color::from_rgba8(255, 40, 40, 255)
+}
+
+impl ImageRenderer {
+    pub fn render_mismatch(&self, mismatch: &DragMismatch) -> Pixmap {
+        let mut bounds = mismatch.bounds();
+        if bounds.is_empty() {
+            bounds = BoundingBox::new(pos(0, 0), pos(1, 1));
+        }
+        let panel_width = bounds.width() as u32 * TILE_SIZE;
+        let panel_height = bounds.height() as u32 * TILE_SIZE;
+        let mut canvas = Pixmap::new(3 * panel_width + 4 * GAP, panel_height + 2 * GAP).unwrap();
+        canvas.fill(background_color());
+
+        let differing = differing_tiles(&mismatch.expected, &mismatch.actual, bounds);
+        let panels = [
+            (&mismatch.before, None),
+            (
+                &mismatch.expected,
+                Some((&mismatch.expected_errors, &mismatch.actual_errors)),
+            ),
+            (
+                &mismatch.actual,
+                Some((&mismatch.actual_errors, &mismatch.expected_errors)),
+            ),
+        ];
+        for (i, (world, errors)) in panels.into_iter().enumerate() {
+            let x = GAP + i as u32 * (panel_width + GAP);
+            let mut panel = self.render_world(world, bounds);
+            if let Some((errors, other_errors)) = errors {
+                outline_tiles(&mut panel, bounds, &differing);
+                draw_errors(&mut panel, bounds, errors, other_errors);
+            }
+            canvas.draw_pixmap(
+                x as i32,
+                GAP as i32,
+                panel.as_ref(),
+                &PixmapPaint::default(),
+                Transform::identity(),
+                None,
+            );
+        }
+        canvas
+    }
+
+    pub fn save_mismatch_png<P: AsRef<Path>>(
+        &self,
+        mismatch: &DragMismatch,
+        path: P,
+    ) -> Result<()> {
+        self.render_mismatch(mismatch).save_png(path)?;
+        Ok(())
+    }
+
+    /// [`Self::render_world`], with a dot on each error's tile.
+    pub fn render_world_with_errors(
+        &self,
+        world: &World,
+        bounds: BoundingBox,
+        errors: &Errors,
+    ) -> Pixmap {
+        let mut pixmap = self.render_world(world, bounds);
+        draw_errors(&mut pixmap, bounds, errors, &Errors::new());
+        pixmap
+    }
+}
+
+fn differing_tiles(expected: &World, actual: &World, bounds: BoundingBox) -> Vec<TilePosition> {
+    let mut tiles = Vec::new();
+    for y in bounds.min.y..bounds.max.y {
+        for x in bounds.min.x..bounds.max.x {
+            let p = pos(x, y);
+            let looks = |world: &World| (world.get(p).cloned(), world.input_direction_at(p));
+            if looks(expected) != looks(actual) {
+                tiles.push(p);
+            }
+        }
+    }
+    tiles
+}
+
+pub(crate) fn tile_rect(bounds: BoundingBox, p: TilePosition) -> Rect {
+    let offset = (p - bounds.min) * TILE_SIZE as i32;
+    Rect::from_xywh(
+        offset.x as f32,
+        offset.y as f32,
+        TILE_SIZE as f32,
+        TILE_SIZE as f32,
+    )
+    .unwrap()
+}
+
+pub(crate) fn outline_tiles(panel: &mut Pixmap, bounds: BoundingBox, tiles: &[TilePosition]) {
+    let mut paint = Paint::default();
+    paint.set_color(diff_outline_color());
+    let stroke = Stroke {
+        width: 4.0,
+        ..Default::default()
+    };
+    for &p in tiles {
+        // Inset so the whole stroke stays inside the tile
+        let rect = tile_rect(bounds, p);
+        let rect = Rect::from_ltrb(
+            rect.left() + 2.0,
+            rect.top() + 2.0,
+            rect.right() - 2.0,
+            rect.bottom() - 2.0,
+        )
+        .unwrap();
+        let path = PathBuilder::from_rect(rect);
+        panel.stroke_path(&path, &paint, &stroke, Transform::identity(), None);
+    }
+}
+
+/// A dot in the top right corner of each error's tile.
+pub(crate) fn draw_errors(
+    panel: &mut Pixmap,
+    bounds: BoundingBox,
+    errors: &Errors,
+    other_errors: &Errors,
+) {
+    let radius = TILE_SIZE as f32 / 6.0;
+    for error in errors {
+        let rect = tile_rect(bounds, error.0);
+        let Some(path) = PathBuilder::from_circle(
+            rect.right() - radius - 2.0,
+            rect.top() + radius + 2.0,
+            radius,
+        ) else {
+            continue;
+        };
+        let mut paint = Paint::default();
+        paint.set_color(if other_errors.contains(error) {
+            matching_error_color()
+        } else {
+            mismatched_error_color()
+        });
+        panel.fill_path(
+            &path,
+            &paint,
+            FillRule::Winding,
+            Transform::identity(),
+            None,
+        );
+        paint.set_color(background_color());
+        panel.stroke_path(
+            &path,
+            &paint,
+            &Stroke::default(),
+            Transform::identity(),
+            None,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prototype_abstract::test_case::parse_world;
+
+    #[test]
+    fn test_render_mismatch_side_by_side() {
+        let renderer = ImageRenderer::new().unwrap();
+        let (before, _) = parse_world("_ X").unwrap();
+        let (expected, _) = parse_world(">i X >o").unwrap();
+        let (actual, markers) = parse_world("> *X >").unwrap();
+        let mismatch = DragMismatch {
+            before,
+            expected,
+            actual,
+            expected_errors: HashSet::new(),
+            actual_errors: [(
+                markers[0],
+                prototype_abstract::smart_belt::action::Error::EntityInTheWay,
+            )]
+            .into_iter()
+            .collect(),
+        };
+        assert_eq!(
+            differing_tiles(&mismatch.expected, &mismatch.actual, mismatch.bounds()),
+            [pos(0, 0), pos(2, 0)]
+        );
+
+        let pixmap = renderer.render_mismatch(&mismatch);
+        assert_eq!(pixmap.width(), 3 * 3 * TILE_SIZE + 4 * GAP);
+        assert_eq!(pixmap.height(), TILE_SIZE + 2 * GAP);
+    }
+}
@@ -0,0 +1,436 @@
+//! SVG output: the worlds [`ImageRenderer::render_world`] draws, as vector
+//! shapes rather than sprites, so they stay sharp when zoomed and are a
+//! fraction of the size.
+//!
+//! Coordinates are in tiles, scaled to [`TILE_SIZE`] pixels per tile by the
+//! `width`/`height` attributes. Each entity is drawn in its own frame,
+//! rotated so that its direction is +x and the tile spans -0.5..0.5.
+//!
+//! [`ImageRenderer::render_world`]: crate::ImageRenderer::render_world
+
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+use anyhow::Result;
+use prototype_abstract::{
+    BeltCollidable, BeltConnectable, BeltConnectableTrait, BoundingBox, TilePosition,
+    WorldImpl as World, pos,
+};
+
+use crate::annotations::{Annotation, error_label, in_layers, underground_links};
+use crate::mismatch::Errors;
+use crate::tiers::tier_color;
+use crate::{TILE_SIZE, rotation_degrees};
+
+const CHECKERBOARD_LIGHT: &str = "#505050";
+const CHECKERBOARD_DARK: &str = "#3c3c3c";
+const BLOCKER: &str = "#fac8c8";
+const IMPASSABLE: &str = "#1e3250";
+const IMPASSABLE_HATCH: &str = "#2d4a73";
+const TRACK: &str = "#2e2e2e";
+const TRACK_EDGE: &str = "#5a5a5a";
+const OUTLINE: &str = "#1e1e1e";
+const LOADER: &str = "#6e6e6e";
+const ERROR: &str = "#ff2828";
+const MARKER: &str = "#ffdc00";
+const LINK: &str = "#78dcff";
+const RAY: &str = "#ffffff";
+const LABEL_BACKGROUND: &str = "#141414";
+const LABEL_SIZE: f32 = 0.2;
+
+#[derive(Debug, Clone, Copy)]
+enum RenderLayer {
+    Bottom,
+    Top,
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SvgRenderer;
+
+impl SvgRenderer {
+    pub fn new() -> Self {
+        Self
+    }
+
+    pub fn render_world(&self, world: &World, bounds: BoundingBox) -> String {
+        self.render_world_with_errors(world, bounds, &Errors::new())
+    }
+
+    /// [`Self::render_world`], with a dot on each error's tile.
+    pub fn render_world_with_errors(
+        &self,
+        world: &World,
+        bounds: BoundingBox,
+        errors: &Errors,
+    ) -> String {
+        let bounds = if bounds.is_empty() {
+            BoundingBox::new(bounds.min, bounds.min + euclid::vec2(1, 1))
+        } else {
+            bounds
+        };
+        let size = bounds.size();
+        let mut svg = String::new();
+        // Writing to a String can't fail
+        let _ = writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="0 0 {} {}">"#,
+            size.width as u32 * TILE_SIZE,
+            size.height as u32 * TILE_SIZE,
+            size.width,
+            size.height
+        );
+        draw_checkerboard_background(&mut svg, bounds);
+        for layer in [RenderLayer::Bottom, RenderLayer::Top] {
+            for y in bounds.min.y..bounds.max.y {
+                for x in bounds.min.x..bounds.max.x {
+                    let p = pos(x, y);
+                    if let Some(entity) = world.get(p) {
+                        draw_entity(&mut svg, entity, bounds, p, world, layer);
+                    }
+                }
+            }
+        }
+        draw_errors(&mut svg, bounds, errors);
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    /// [`Self::render_world`], with `annotations` drawn over it.
+    pub fn render_world_annotated(
+        &self,
+        world: &World,
+        bounds: BoundingBox,
+        annotations: &[Annotation],
+    ) -> String {
+        let mut svg = self.render_world(world, bounds);
+        svg.truncate(svg.len() - "</svg>\n".len());
+        for annotation in in_layers(annotations) {
+            draw_annotation(&mut svg, world, bounds, annotation);
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    pub fn save_svg<P: AsRef<Path>>(
+        &self,
+        world: &World,
+        bounds: BoundingBox,
+        path: P,
+    ) -> Result<()> {
+        fs::write(path, self.render_world(world, bounds))?;
+        Ok(())
+    }
+}
+
+fn draw_checkerboard_background(svg: &mut String, bounds: BoundingBox) {
+    let size = bounds.size();
+    let _ = writeln!(
+        svg,
+        r#"<rect width="{}" height="{}" fill="{CHECKERBOARD_DARK}"/>"#,
+        size.width, size.height
+    );
+    for y in 0..size.height {
+        for x in (0..size.width).filter(|x| (x + y) % 2 == 0) {
+            let _ = writeln!(
+                svg,
+                r#"<rect x="{x}" y="{y}" width="1" height="1" fill="{CHECKERBOARD_LIGHT}"/>"#
+            );
+        }
+    }
+}
+
+fn draw_entity(
+    svg: &mut String,
+    entity: &BeltCollidable,
+    bounds: BoundingBox,
+    p: TilePosition,
+    world: &World,
+    layer: RenderLayer,
+) {
+    let offset = p - bounds.min;
+    let Ok(connectable) = BeltConnectable::try_from(entity) else {
+        if let RenderLayer::Bottom = layer {
+            if entity.is_impassable_tile() {
+                draw_impassable(svg, offset.x, offset.y);
+            } else {
+                let _ = writeln!(
+                    svg,
+                    r#"<rect x="{}" y="{}" width="1" height="1" fill="{BLOCKER}"/>"#,
+                    offset.x, offset.y
+                );
+            }
+        }
+        return;
+    };
+
+    let direction = connectable.direction();
+    let [r, g, b] = tier_color(connectable.tier());
+    let tier_color = &format!("#{r:02x}{g:02x}{b:02x}");
+    let mut shapes = String::new();
+    match (connectable, layer) {
+        (BeltConnectable::Belt(_), RenderLayer::Bottom) => {
+            let input = world.input_direction_at(p).unwrap_or(direction);
+            let entry = if input == direction.rotate_cw() {
+                (0.0, -0.5)
+            } else if input == direction.rotate_ccw() {
+                (0.0, 0.5)
+            } else {
+                (-0.5, 0.0)
+            };
+            draw_track(&mut shapes, entry, (0.5, 0.0), tier_color);
+        }
+        (BeltConnectable::Splitter(_), RenderLayer::Bottom) => {
+            draw_track(&mut shapes, (-0.5, 0.0), (0.5, 0.0), tier_color);
+        }
+        (BeltConnectable::Splitter(_), RenderLayer::Top) => {
+            let _ = write!(
+                shapes,
+                r#"<rect x="-0.2" y="-0.5" width="0.4" height="1" fill="{tier_color}" stroke="{OUTLINE}" stroke-width="0.04"/>"#
+            );
+        }
+        (BeltConnectable::UndergroundBelt(ug), RenderLayer::Bottom) => {
+            draw_half_track(&mut shapes, ug.is_input, tier_color);
+        }
+        (BeltConnectable::UndergroundBelt(ug), RenderLayer::Top) => {
+            draw_half_structure(&mut shapes, ug.is_input, tier_color, OUTLINE);
+        }
+        (BeltConnectable::LoaderLike(loader), RenderLayer::Bottom) => {
+            draw_half_track(&mut shapes, loader.is_input, tier_color);
+        }
+        (BeltConnectable::LoaderLike(loader), RenderLayer::Top) => {
+            draw_half_structure(&mut shapes, loader.is_input, LOADER, tier_color);
+        }
+        (BeltConnectable::Belt(_), RenderLayer::Top) => {}
+    }
+    if !shapes.is_empty() {
+        let _ = writeln!(
+            svg,
+            r#"<g transform="translate({} {}) rotate({})">{shapes}</g>"#,
+            offset.x as f32 + 0.5,
+            offset.y as f32 + 0.5,
+            rotation_degrees(direction)
+        );
+    }
+}
+
+/// Water or void: hatched, to tell it apart from entities in the way.
+fn draw_impassable(svg: &mut String, x: i32, y: i32) {
+    let _ = writeln!(
+        svg,
+        r#"<g transform="translate({x} {y})"><rect width="1" height="1" fill="{IMPASSABLE}"/><path d="M 0 0.5 L 0.5 0 M 0 1 L 1 0 M 0.5 1 L 1 0.5" stroke="{IMPASSABLE_HATCH}" stroke-width="0.08"/></g>"#
+    );
+}
+
+/// A belt from `entry` to `exit` (curving through the tile center), with an
+/// arrow in the tier's color.
+fn draw_track(shapes: &mut String, entry: (f32, f32), exit: (f32, f32), tier_color: &str) {
+    let path = format!("M {} {} Q 0 0 {} {}", entry.0, entry.1, exit.0, exit.1);
+    let _ = write!(
+        shapes,
+        r#"<path d="{path}" fill="none" stroke="{TRACK_EDGE}" stroke-width="0.84"/><path d="{path}" fill="none" stroke="{TRACK}" stroke-width="0.7"/>"#
+    );
+    draw_arrow(shapes, tier_color);
+}
+
+fn draw_arrow(shapes: &mut String, color: &str) {
+    let _ = write!(
+        shapes,
+        r#"<path d="M -0.12 -0.2 L 0.1 0 L -0.12 0.2" fill="none" stroke="{color}" stroke-width="0.1" stroke-linecap="round" stroke-linejoin="round"/>"#
+    );
+}
+
+/// Underground belts and loaders take the belt in at the back if inputs, and
+/// out at the front if outputs.
+fn draw_half_track(shapes: &mut String, is_input: bool, tier_color: &str) {
+    let (entry, exit) = if is_input {
+        ((-0.5, 0.0), (0.05, 0.0))
+    } else {
+        ((-0.05, 0.0), (0.5, 0.0))
+    };
+    draw_track(shapes, entry, exit, tier_color);
+}
+
+/// The structure on the other half of the tile to [`draw_half_track`], with
+/// a dark mouth where the belt enters or leaves it.
+fn draw_half_structure(shapes: &mut String, is_input: bool, fill: &str, stroke: &str) {
+    let (x, mouth_x) = if is_input { (0.0, 0.0) } else { (-0.45, -0.1) };
+    let _ = write!(
+        shapes,
+        r#"<rect x="{x}" y="-0.45" width="0.45" height="0.9" rx="0.08" fill="{fill}" stroke="{stroke}" stroke-width="0.05"/><rect x="{mouth_x}" y="-0.3" width="0.1" height="0.6" fill="{OUTLINE}"/>"#
+    );
+}
+
+/// A dot in the top right corner of each error's tile.
+fn draw_errors(svg: &mut String, bounds: BoundingBox, errors: &Errors) {
+    let mut positions = errors.iter().map(|(p, _)| *p).collect::<Vec<_>>();
+    positions.sort_by_key(|p| (p.y, p.x));
+    positions.dedup();
+    for p in positions {
+        let offset = p - bounds.min;
+        draw_error_dot(svg, offset.x, offset.y);
+    }
+}
+
+fn draw_error_dot(svg: &mut String, x: i32, y: i32) {
+    let radius = 1.0 / 6.0;
+    let inset = 2.0 / TILE_SIZE as f32;
+    let _ = writeln!(
+        svg,
+        r#"<circle cx="{}" cy="{}" r="{radius}" fill="{ERROR}" stroke="{OUTLINE}" stroke-width="{inset}"/>"#,
+        x as f32 + 1.0 - radius - inset,
+        y as f32 + radius + inset
+    );
+}
+
+fn tile_center(bounds: BoundingBox, p: TilePosition) -> (f32, f32) {
+    let offset = p - bounds.min;
+    (offset.x as f32 + 0.5, offset.y as f32 + 0.5)
+}
+
+fn draw_annotation(svg: &mut String, world: &World, bounds: BoundingBox, annotation: &Annotation) {
+    match annotation {
+        Annotation::Marker(p) => {
+            let offset = *p - bounds.min;
+            let _ = writeln!(
+                svg,
+                r#"<rect x="{}" y="{}" width="0.953" height="0.953" fill="{MARKER}" fill-opacity="0.27" stroke="{MARKER}" stroke-width="0.047"/>"#,
+                offset.x as f32 + 0.0235,
+                offset.y as f32 + 0.0235
+            );
+        }
+        Annotation::UndergroundLinks => {
+            for (input, output) in underground_links(world, bounds) {
+                let (from, to) = (tile_center(bounds, input), tile_center(bounds, output));
+                let _ = writeln!(
+                    svg,
+                    r#"<line x1="{}" y1="{}" x2="{}" y2="{}" stroke="{LINK}" stroke-width="0.047" stroke-dasharray="0.125"/>"#,
+                    from.0, from.1, to.0, to.1
+                );
+            }
+        }
+        Annotation::DragRay { start, end } => {
+            draw_ray(svg, tile_center(bounds, *start), tile_center(bounds, *end))
+        }
+        Annotation::Error(p, error) => {
+            let offset = *p - bounds.min;
+            draw_error_dot(svg, offset.x, offset.y);
+            let (x, _) = tile_center(bounds, *p);
+            let y = offset.y as f32 + 1.0 - LABEL_SIZE / 2.0 - 0.03;
+            draw_label(svg, error_label(error), x, y, "middle");
+        }
+        Annotation::Label(p, text) => {
+            let offset = *p - bounds.min;
+            let x = offset.x as f32 + 0.03;
+            let y = offset.y as f32 + LABEL_SIZE / 2.0 + 0.03;
+            draw_label(svg, text, x, y, "start");
+        }
+    }
+}
+
+/// A dot at `start`, and a line to an arrowhead at `end`.
+fn draw_ray(svg: &mut String, start: (f32, f32), end: (f32, f32)) {
+    let head = 0.25;
+    let _ = writeln!(
+        svg,
+        r#"<circle cx="{}" cy="{}" r="{}" fill="{RAY}" fill-opacity="0.86"/>"#,
+        start.0,
+        start.1,
+        head / 3.0
+    );
+    let (dx, dy) = (end.0 - start.0, end.1 - start.1);
+    let length = (dx * dx + dy * dy).sqrt();
+    if length < head {
+        return;
+    }
+    let (ux, uy) = (dx / length, dy / length);
+    let base = (end.0 - ux * head, end.1 - uy * head);
+    let _ = writeln!(
+        svg,
+        r#"<g fill="{RAY}" stroke="{RAY}" fill-opacity="0.86" stroke-opacity="0.86"><line x1="{}" y1="{}" x2="{}" y2="{}" stroke-width="0.0625"/><path d="M {} {} L {} {} L {} {} Z" stroke="none"/></g>"#,
+        start.0,
+        start.1,
+        base.0,
+        base.1,
+        end.0,
+        end.1,
+        base.0 - uy * head / 2.0,
+        base.1 + ux * head / 2.0,
+        base.0 + uy * head / 2.0,
+        base.1 - ux * head / 2.0
+    );
+}
+
+/// Text centered vertically on `y`, on a dark box so it shows over any
+/// entity.
+fn draw_label(svg: &mut String, text: &str, x: f32, y: f32, anchor: &str) {
+    let _ = writeln!(
+        svg,
+        r#"<text x="{x}" y="{y}" font-family="DejaVu Sans Condensed, sans-serif" font-weight="bold" font-size="{LABEL_SIZE}" dominant-baseline="central" text-anchor="{anchor}" fill="white" stroke="{LABEL_BACKGROUND}" stroke-width="0.06" stroke-opacity="0.75" paint-order="stroke">{}</text>"#,
+        escape_xml(text)
+    );
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prototype_abstract::smart_belt::action::Error;
+    use prototype_abstract::test_case::parse_world;
+
+    #[test]
+    fn test_render_world_svg() {
+        let (world, markers) = parse_world("> >i *X >o v\n_ vO _ >s v\n# _ _ _ <").unwrap();
+        let bounds = BoundingBox::new(pos(0, 0), pos(5, 3));
+        let errors = [(markers[0], Error::EntityInTheWay)].into_iter().collect();
+        let svg = SvgRenderer::new().render_world_with_errors(&world, bounds, &errors);
+
+        assert!(svg.starts_with(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="320" height="192" viewBox="0 0 5 3">"#
+        ));
+        assert!(svg.trim_end().ends_with("</svg>"));
+        // One group per layer each entity is drawn in: belts 1, undergrounds,
+        // splitters and loaders 2, the impassable tile 1; the obstacle in the
+        // way is a plain rect
+        assert_eq!(svg.matches("<g ").count(), 4 + 2 * 4 + 1);
+        assert_eq!(svg.matches(&format!(r#"fill="{BLOCKER}""#)).count(), 1);
+        assert_eq!(svg.matches(&format!(r#"fill="{IMPASSABLE}""#)).count(), 1);
+        assert_eq!(svg.matches("<circle").count(), 1);
+        // The belt at the bottom right curves in from the south-going belt above
+        assert!(svg.contains(
+            r#"<g transform="translate(4.5 2.5) rotate(180)"><path d="M 0 0.5 Q 0 0 0.5 0""#
+        ));
+    }
+
+    #[test]
+    fn test_render_world_annotated_svg() {
+        let (world, markers) = parse_world(">i _ *>o\n_ _ _").unwrap();
+        let bounds = BoundingBox::new(pos(0, 0), pos(3, 2));
+        let svg = SvgRenderer::new().render_world_annotated(
+            &world,
+            bounds,
+            &[
+                Annotation::Label(pos(0, 1), "<A>".to_string()),
+                Annotation::Error(pos(1, 1), Error::EntityInTheWay),
+                Annotation::DragRay {
+                    start: pos(0, 1),
+                    end: pos(2, 1),
+                },
+                Annotation::UndergroundLinks,
+                Annotation::Marker(markers[0]),
+            ],
+        );
+        assert!(svg.trim_end().ends_with("</svg>"));
+        assert_eq!(svg.matches("<svg").count(), 1);
+        assert!(svg.contains(r#"<line x1="0.5" y1="0.5" x2="2.5" y2="0.5""#));
+        assert!(svg.contains(">in the way</text>"));
+        assert!(svg.contains(">&lt;A&gt;</text>"));
+        // Markers are drawn first, whatever the order given
+        assert!(svg.find(MARKER) < svg.find(LINK));
+    }
+}
@@ -0,0 +1,111 @@
+//! How each belt tier looks. The built-in tiers have their own sprite sheets;
+//! others (e.g. turbo, or modded tiers) either load sheets named like them, or
+//! recolor the yellow tier's by rotating their hue.
+
+use prototype_abstract::{BELT_TIERS, BeltTier};
+
+/// Sheet name prefixes and (chevron) colors of [`BELT_TIERS`].
+pub(crate) const BUILT_IN_TIERS: [(&str, [u8; 3]); 3] = [
+    ("", [232, 185, 35]),
+    ("fast-", [224, 64, 58]),
+    ("express-", [58, 155, 224]),
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TierStyle {
+    /// Sheets named as the yellow tier's, with `prefix` in front (e.g.
+    /// `"turbo-"` for `turbo-transport-belt.png`), and the color to draw the
+    /// tier in where there are no sprites.
+    Sheets { prefix: String, color: [u8; 3] },
+    /// The yellow tier's sheets, with their hue rotated by this many degrees.
+    HueShift(f32),
+}
+
+impl TierStyle {
+    /// Tiers without a style of their own get a hue shift picked from their
+    /// name, so that different tiers look different.
+    pub fn default_for(tier: BeltTier) -> Self {
+        if let Some(i) = BELT_TIERS.iter().position(|&t| t == tier) {
+            let (prefix, color) = BUILT_IN_TIERS[i];
+            return TierStyle::Sheets {
+                prefix: prefix.to_string(),
+                color,
+            };
+        }
+        // FNV-1a, as it is stable across runs and platforms
+        let hash = tier.name.bytes().fold(0x811c9dc5u32, |hash, byte| {
+            (hash ^ byte as u32).wrapping_mul(0x01000193)
+        });
+        // Away from yellow's own hue
+        TierStyle::HueShift(60.0 + (hash % 240) as f32)
+    }
+
+    pub fn color(&self) -> [u8; 3] {
+        match self {
+            TierStyle::Sheets { color, .. } => *color,
+            TierStyle::HueShift(degrees) => hue_rotated(BUILT_IN_TIERS[0].1, *degrees),
+        }
+    }
+}
+
+/// The color `tier` is drawn in, with its default style.
+pub fn tier_color(tier: BeltTier) -> [u8; 3] {
+    TierStyle::default_for(tier).color()
+}
+
+/// Rotates the hue of linear RGB colors, keeping their luminance (as the
+/// `hue-rotate` CSS filter does).
+pub fn hue_rotation_matrix(degrees: f32) -> [[f32; 3]; 3] {
+    let (sin, cos) = degrees.to_radians().sin_cos();
+    [
+        [
+            0.213 + cos * 0.787 - sin * 0.213,
+            0.715 - cos * 0.715 - sin * 0.715,
+            0.072 - cos * 0.072 + sin * 0.928,
+        ],
+        [
+            0.213 - cos * 0.213 + sin * 0.143,
+            0.715 + cos * 0.285 + sin * 0.140,
+            0.072 - cos * 0.072 - sin * 0.283,
+        ],
+        [
+            0.213 - cos * 0.213 - sin * 0.787,
+            0.715 - cos * 0.715 + sin * 0.715,
+            0.072 + cos * 0.928 + sin * 0.072,
+        ],
+    ]
+}
+
+fn hue_rotated(rgb: [u8; 3], degrees: f32) -> [u8; 3] {
+    let rgb = rgb.map(f32::from);
+    hue_rotation_matrix(degrees).map(|row| {
+        let value = row[0] * rgb[0] + row[1] * rgb[1] + row[2] * rgb[2];
+        value.round().clamp(0.0, 255.0) as u8
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prototype_abstract::belts::BeltTierData;
+
+    static TURBO: BeltTierData = BeltTierData {
+        name: "Turbo",
+        underground_distance: 11,
+    };
+
+    #[test]
+    fn test_default_styles() {
+        assert_eq!(tier_color(BELT_TIERS[1]), BUILT_IN_TIERS[1].1);
+
+        let turbo = BeltTier::new(&TURBO);
+        let TierStyle::HueShift(degrees) = TierStyle::default_for(turbo) else {
+            panic!("Tiers past the built-in ones should be recolored");
+        };
+        assert!((60.0..300.0).contains(&degrees));
+        assert_ne!(tier_color(turbo), BUILT_IN_TIERS[0].1);
+
+        assert_eq!(hue_rotated([232, 185, 35], 0.0), [232, 185, 35]);
+        assert_eq!(hue_rotated([80, 80, 80], 123.0), [80, 80, 80]);
+    }
+}
@@ -1,9 +1,11 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use euclid::Size2D;
 use euclid::Vector2D;
 use std::path::Path;
-use tiny_skia::{Pixmap, Transform};
+use tiny_skia::{Pixmap, PremultipliedColorU8, Transform};
 
+use crate::assets::Assets;
+use crate::tiers::hue_rotation_matrix;
 use crate::{PixelBox, PixelPoint, PixelSize, PixelSpace};
 
 #[derive(Debug, Clone)]
@@ -46,6 +48,22 @@ impl Tilemap {
         Ok(Self::new(pixmap, entry_size))
     }
 
+    pub fn hue_rotated(&self, degrees: f32) -> Self {
+        let mut pixmap = self.pixmap.clone();
+        let matrix = hue_rotation_matrix(degrees);
+        for pixel in pixmap.pixels_mut() {
+            // The rotation is linear, so it applies to premultiplied colors as
+            // is; clamping to alpha keeps them valid
+            let rgb = [pixel.red(), pixel.green(), pixel.blue()].map(f32::from);
+            let [r, g, b] = matrix.map(|row| {
+                let value = row[0] * rgb[0] + row[1] * rgb[1] + row[2] * rgb[2];
+                value.round().clamp(0.0, pixel.alpha() as f32) as u8
+            });
+            *pixel = PremultipliedColorU8::from_rgba(r, g, b, pixel.alpha()).unwrap();
+        }
+        Self::new(pixmap, self.entry_size)
+    }
+
     fn get_tile_source_rect(&self, tile_pos: (u8, u8)) -> PixelBox {
         let src_x = tile_pos.0 as i32 * self.entry_size.width as i32;
         let src_y = tile_pos.1 as i32 * self.entry_size.height as i32;
@@ -125,97 +143,68 @@ impl Tilemap {
     }
 }
 
-pub struct Tilemaps {
-    pub belt: [Tilemap; 3],
-    pub underground: [Tilemap; 3],
-    pub splitter_east: [[Tilemap; 2]; 3],
-    pub splitter_west: [[Tilemap; 2]; 3],
-    pub splitter_north: [Tilemap; 3],
-    pub splitter_south: [Tilemap; 3],
+/// The sprite sheets of one belt tier.
+#[derive(Debug, Clone)]
+pub struct TierTilemaps {
+    pub belt: Tilemap,
+    pub underground: Tilemap,
+    pub splitter_east: [Tilemap; 2],
+    pub splitter_west: [Tilemap; 2],
+    pub splitter_north: Tilemap,
+    pub splitter_south: Tilemap,
 }
 
-impl Tilemaps {
-    pub fn load_from_assets<P: AsRef<Path>>(assets_path: P) -> Result<Self> {
-        let assets_path = assets_path.as_ref();
-
-        let load = |path: &str, width: u32, height: u32| {
-            Tilemap::load(assets_path.join(path), PixelSize::new(width, height))
+impl TierTilemaps {
+    /// Sheets named as the yellow tier's, with `prefix` in front: `"fast-"`
+    /// and `"express-"` for the other built-in tiers.
+    pub fn load(assets: &Assets, prefix: &str) -> Result<Self> {
+        let decode = |name: &str| -> Result<Pixmap> {
+            let name = format!("{prefix}{name}");
+            Pixmap::decode_png(&assets.read(&name)?).with_context(|| format!("Loading {name}"))
+        };
+        let load = |name: &str, width: u32, height: u32| -> Result<Tilemap> {
+            Ok(Tilemap::new(decode(name)?, PixelSize::new(width, height)))
+        };
+        // Splitter sheets are 8x4 animation frames, whose size varies by tier
+        // (e.g. express west is 752x344 = 94x86 frames, where the others are
+        // 720x344 = 90x86)
+        let load_splitter = |name: &str| -> Result<Tilemap> {
+            let pixmap = decode(name)?;
+            let entry_size = PixelSize::new(pixmap.width() / 8, pixmap.height() / 4);
+            Ok(Tilemap::new(pixmap, entry_size))
         };
 
-        // Belt dimensions vary by tier due to animation frames
-        // Yellow: 2048x2560 = 16x20 tiles of 128x128
-        // Fast/Express: 4096x2560 = 32x20 tiles of 128x128
-        let belt = [
-            load("transport-belt.png", 128, 128)?,
-            load("fast-transport-belt.png", 128, 128)?,
-            load("express-transport-belt.png", 128, 128)?,
-        ];
-
-        // Underground structures are consistent across tiers: 768x768 = 4x4 tiles of 192x192
-        let underground = [
-            load("underground-belt-structure.png", 192, 192)?,
-            load("fast-underground-belt-structure.png", 192, 192)?,
-            load("express-underground-belt-structure.png", 192, 192)?,
-        ];
-
-        // Splitter dimensions: 720x336 = 8x4 tiles of 90x84
-        // Top patch: 720x416 = 8x4 tiles of 90x104
-        let splitter_east = [
-            [
-                load("splitter-east.png", 90, 84)?,
-                load("splitter-east-top_patch.png", 90, 104)?,
-            ],
-            [
-                load("fast-splitter-east.png", 90, 84)?,
-                load("fast-splitter-east-top_patch.png", 90, 104)?,
-            ],
-            [
-                load("express-splitter-east.png", 90, 84)?,
-                load("express-splitter-east-top_patch.png", 90, 104)?,
-            ],
-        ];
-
-        // West splitter dimensions vary slightly for express tier
-        // Yellow/Fast: 720x344 = 8x4 tiles of 90x86, top patch: 720x384 = 8x4 tiles of 90x96
-        // Express: 752x344 = 8x4 tiles of 94x86, top patch: 752x384 = 8x4 tiles of 94x96
-        let splitter_west = [
-            [
-                load("splitter-west.png", 90, 86)?,
-                load("splitter-west-top_patch.png", 90, 96)?,
-            ],
-            [
-                load("fast-splitter-west.png", 90, 86)?,
-                load("fast-splitter-west-top_patch.png", 90, 96)?,
+        Ok(Self {
+            // Yellow: 2048x2560 = 16x20 tiles of 128x128
+            // Fast/Express: 4096x2560 = 32x20 tiles of 128x128
+            belt: load("transport-belt.png", 128, 128)?,
+            // 768x768 = 4x4 tiles of 192x192
+            underground: load("underground-belt-structure.png", 192, 192)?,
+            splitter_east: [
+                load_splitter("splitter-east.png")?,
+                load_splitter("splitter-east-top_patch.png")?,
             ],
-            [
-                load("express-splitter-west.png", 94, 86)?,
-                load("express-splitter-west-top_patch.png", 94, 96)?,
+            splitter_west: [
+                load_splitter("splitter-west.png")?,
+                load_splitter("splitter-west-top_patch.png")?,
             ],
-        ];
-
-        // South: 1312x256 = 8x4 tiles of 164x64
-        let splitter_south = [
-            load("splitter-south.png", 164, 64)?,
-            load("fast-splitter-south.png", 164, 64)?,
-            load("express-splitter-south.png", 164, 64)?,
-        ];
-
-        // North: 1280x280 = 8x4 tiles of 160x70
-        let splitter_north = [
-            load("splitter-north.png", 160, 70)?,
-            load("fast-splitter-north.png", 160, 70)?,
-            load("express-splitter-north.png", 160, 70)?,
-        ];
-
-        Ok(Self {
-            belt,
-            underground,
-            splitter_east,
-            splitter_west,
-            splitter_north,
-            splitter_south,
+            splitter_north: load_splitter("splitter-north.png")?,
+            splitter_south: load_splitter("splitter-south.png")?,
         })
     }
+
+    /// Every sheet with its hue rotated, for a tier without sprites of its own.
+    pub fn hue_rotated(&self, degrees: f32) -> Self {
+        let rotate = |tilemap: &Tilemap| tilemap.hue_rotated(degrees);
+        Self {
+            belt: rotate(&self.belt),
+            underground: rotate(&self.underground),
+            splitter_east: self.splitter_east.each_ref().map(rotate),
+            splitter_west: self.splitter_west.each_ref().map(rotate),
+            splitter_north: rotate(&self.splitter_north),
+            splitter_south: rotate(&self.splitter_south),
+        }
+    }
 }
 
 #[cfg(test)]
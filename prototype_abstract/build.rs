@@ -1,16 +1,11 @@
-use serde::Deserialize;
 use std::env;
 use std::fs;
 use std::path::PathBuf;
 
-#[derive(Deserialize)]
-#[serde(rename_all = "snake_case")]
-struct TestCaseFlags {
-    #[serde(default)]
-    not_reversible: bool,
-    #[serde(default)]
-    forward_back: bool,
-}
+#[path = "src/suite_test_names.rs"]
+mod suite_test_names;
+
+use suite_test_names::{case_test_fns, sanitized_case_name, test_fn_name};
 
 fn main() {
     let manifest_dir = env::var("CARGO_MANIFEST_DIR").unwrap();
@@ -50,60 +45,15 @@ fn main() {
         // Parse YAML to get test cases and generate macro calls
         let test_cases = serde_yaml::from_str::<Vec<serde_yaml::Value>>(&content).unwrap();
         for (i, test_case) in test_cases.iter().enumerate() {
-            let test_name = if let Some(name) = test_case.get("name").and_then(|v| v.as_str()) {
-                let sanitized_name =
-                    name.to_lowercase()
-                        .chars()
-                        .fold(String::new(), |mut acc, c| {
-                            if c.is_alphanumeric() {
-                                acc.push(c);
-                            } else if !acc.ends_with('_') {
-                                acc.push('_');
-                            }
-                            acc
-                        });
-                sanitized_name.trim_end_matches('_').to_string()
-            } else {
-                format!("{:03}", i + 1)
-            };
-
+            let test_name = sanitized_case_name(test_case, i);
             let yaml_content = serde_yaml::to_string(&test_case).unwrap();
 
-            // Parse flags to determine which variants to generate
-            let flags: TestCaseFlags =
-                serde_yaml::from_value(test_case.clone()).unwrap_or(TestCaseFlags {
-                    not_reversible: false,
-                    forward_back: false,
-                });
-
-            // Build list of test variants to generate based on flags
-            let mut test_fns = vec![];
-
-            if flags.forward_back {
-                // For forward_back tests, generate ForwardBack variants
-                test_fns.push(("run_test_case_forward_back", "_normal"));
-                if !flags.not_reversible {
-                    test_fns.push(("run_test_case_forward_back_reverse", "_reverse"));
-                }
-            } else {
-                // For normal tests, generate normal and wiggle variants
-                test_fns.push(("run_test_case", "_normal"));
-                if !flags.not_reversible {
-                    test_fns.push(("run_test_case_reverse", "_reverse"));
-                }
-                test_fns.push(("run_test_case_wiggle", "_wiggle"));
-                test_fns.push(("run_test_case_mega_wiggle", "_mega_wiggle"));
-                if !flags.not_reversible {
-                    test_fns.push(("run_test_case_wiggle_reverse", "_wiggle_reverse"));
-                    test_fns.push(("run_test_case_mega_wiggle_reverse", "_mega_wiggle_reverse"));
-                }
-            }
-
-            for (fn_name, suffix) in test_fns {
+            for (fn_name, suffix) in case_test_fns(test_case) {
+                let test_fn = test_fn_name(&test_name, suffix);
                 generated_code.push_str(&format!(
                     r##"
     #[test]
-    fn test_{test_name}{suffix}() {{
+    fn {test_fn}() {{
         crate::{fn_name}(r#"{0}"#);
     }}
 "##,
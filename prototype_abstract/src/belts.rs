@@ -35,13 +35,27 @@ impl Debug for BeltTier {
 }
 
 impl BeltTier {
+    /// A tier other than [`BELT_TIERS`], e.g. turbo or a modded tier. Tiers
+    /// are only equal if made from the same data.
+    pub const fn new(data: &'static BeltTierData) -> Self {
+        BeltTier(data)
+    }
+
     /// Returns the zero-based index of this tier in the BELT_TIERS array.
     /// - 0 = Yellow (YELLOW_BELT)
     /// - 1 = Red (RED_BELT)
     /// - 2 = Blue (BLUE_BELT)
+    ///
+    /// Tiers not in the array count as 0.
     pub fn tier_index(&self) -> usize {
         BELT_TIERS.iter().position(|&t| t == *self).unwrap_or(0)
     }
+
+    /// The tier numbered `n` (1-based, as in grids and test cases) in
+    /// [`BELT_TIERS`], if any.
+    pub fn from_number(n: usize) -> Option<BeltTier> {
+        BELT_TIERS.get(n.checked_sub(1)?).copied()
+    }
 }
 
 pub static YELLOW_BELT: BeltTier = BeltTier(&BeltTierData {
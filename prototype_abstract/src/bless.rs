@@ -0,0 +1,673 @@
+//! "Blessing" for the YAML test suite: runs every case, shows how failing
+//! cases differ from their `after`/`expected_errors`/`trace`, and can rewrite
+//! those fields in place with the current output. A `trace` is only rewritten
+//! if the case has one; add `trace: []` to a case to record it.
+//!
+//! Rewriting edits the file text rather than re-serializing it, so comments,
+//! ordering and other keys (e.g. `not_reversible`, `forward_back`) are kept as
+//! written. A case is only rewritten if the new version passes every variant
+//! the suite runs (reversed, wiggled, and under all transforms), and still
+//! infers the same drag.
+//! Scenarios are checked, but never rewritten.
+
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, bail};
+use itertools::{EitherOrBoth, Itertools};
+use serde_yaml::Value;
+
+use crate::smart_belt::action::Error;
+use crate::test_case::{
+    DragTestCase, SuiteCase, TestVariant, align_columns, parse_labeled_world, print_world,
+    run_drag_steps, run_test_case,
+};
+use crate::trace::{TraceStep, with_trace};
+use crate::{BoundingBox, TilePosition, WorldImpl, pos};
+
+#[derive(Debug)]
+pub struct FileReport {
+    pub path: PathBuf,
+    pub num_cases: usize,
+    pub failures: Vec<CaseFailure>,
+    /// If the file was rewritten with the blessed cases.
+    pub written: bool,
+}
+
+#[derive(Debug)]
+pub struct CaseFailure {
+    pub name: String,
+    /// The first failing variant, and why.
+    pub failure: String,
+    /// How the plain (untransformed) drag differs from the case, if it does.
+    pub diff: Option<CaseDiff>,
+    /// The rewritten case, or why it can't be rewritten.
+    pub blessed: Result<Vec<String>, String>,
+    /// Lines of the case in the file.
+    lines: Range<usize>,
+}
+
+#[derive(Debug)]
+pub struct CaseDiff {
+    pub expected: Vec<String>,
+    pub actual: Vec<String>,
+    pub expected_errors: Vec<String>,
+    pub actual_errors: Vec<String>,
+    /// Empty if the case has no trace.
+    pub expected_trace: Vec<String>,
+    pub actual_trace: Vec<String>,
+}
+
+impl FileReport {
+    pub fn num_blessed(&self) -> usize {
+        self.failures.iter().filter(|f| f.blessed.is_ok()).count()
+    }
+}
+
+impl fmt::Display for FileReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let file_name = self.path.file_name().unwrap_or_default().to_string_lossy();
+        write!(
+            f,
+            "{file_name}: {} of {} cases fail",
+            self.failures.len(),
+            self.num_cases
+        )?;
+        if self.written {
+            write!(f, ", {} blessed", self.num_blessed())?;
+        }
+        writeln!(f)?;
+        for failure in &self.failures {
+            write!(f, "{failure}")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for CaseFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "  ✗ {}", self.name)?;
+        match &self.diff {
+            Some(diff) => {
+                let first_line = self.failure.lines().next().unwrap_or_default();
+                let first_line = first_line.trim_end_matches([':', ' ']);
+                writeln!(f, "    {first_line}")?;
+                write!(f, "{diff}")?;
+            }
+            None => {
+                for line in self.failure.lines() {
+                    writeln!(f, "    {line}")?;
+                }
+            }
+        }
+        match &self.blessed {
+            Ok(_) => writeln!(f, "    can bless"),
+            Err(reason) => writeln!(f, "    cannot bless: {reason}"),
+        }
+    }
+}
+
+impl fmt::Display for CaseDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.expected != self.actual {
+            writeln!(f, "    after:")?;
+            for (expected, actual) in self.expected.iter().zip(&self.actual) {
+                if expected == actual {
+                    writeln!(f, "        {expected}")?;
+                } else {
+                    writeln!(f, "      - {expected}")?;
+                    writeln!(f, "      + {actual}")?;
+                }
+            }
+        }
+        if self.expected_errors != self.actual_errors {
+            writeln!(
+                f,
+                "    expected_errors: [{}] -> [{}]",
+                self.expected_errors.join(", "),
+                self.actual_errors.join(", ")
+            )?;
+        }
+        if self.expected_trace != self.actual_trace {
+            writeln!(f, "    trace:")?;
+            for step in self.expected_trace.iter().zip_longest(&self.actual_trace) {
+                match step {
+                    EitherOrBoth::Both(expected, actual) if expected == actual => {
+                        writeln!(f, "        {expected}")?
+                    }
+                    EitherOrBoth::Both(expected, actual) => {
+                        writeln!(f, "      - {expected}")?;
+                        writeln!(f, "      + {actual}")?;
+                    }
+                    EitherOrBoth::Left(expected) => writeln!(f, "      - {expected}")?,
+                    EitherOrBoth::Right(actual) => writeln!(f, "      + {actual}")?,
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Checks every `*.yaml` file in `dir`, in name order.
+/// If `write`, rewrites failing cases that can be blessed.
+pub fn bless_suite(dir: &Path, write: bool) -> Result<Vec<FileReport>> {
+    let mut paths = fs::read_dir(dir)?
+        .map(|entry| entry.map(|e| e.path()))
+        .filter_ok(|path| path.extension().is_some_and(|e| e == "yaml"))
+        .collect::<Result<Vec<_>, _>>()?;
+    paths.sort();
+    paths.iter().map(|path| bless_file(path, write)).collect()
+}
+
+pub fn bless_file(path: &Path, write: bool) -> Result<FileReport> {
+    let text = fs::read_to_string(path).with_context(|| format!("Failed to read {path:?}"))?;
+    let (failures, num_cases) = check_suite_text(&text).with_context(|| format!("In {path:?}"))?;
+
+    let written = write && failures.iter().any(|f| f.blessed.is_ok());
+    if written {
+        fs::write(path, bless_text(&text, &failures))?;
+    }
+    Ok(FileReport {
+        path: path.to_path_buf(),
+        num_cases,
+        failures,
+        written,
+    })
+}
+
+/// The failing cases in a suite file, and the number of cases.
+fn check_suite_text(text: &str) -> Result<(Vec<CaseFailure>, usize)> {
+    let values: Vec<Value> = serde_yaml::from_str(text)?;
+    let lines = text.lines().collect_vec();
+    let blocks = case_blocks(&lines);
+    if blocks.len() != values.len() {
+        bail!(
+            "Found {} cases, but {} lines starting with '- '",
+            values.len(),
+            blocks.len()
+        );
+    }
+
+    let failures = values
+        .iter()
+        .zip(&blocks)
+        .filter_map(|(value, block)| check_case_value(value, &lines, block.clone()))
+        .collect();
+    Ok((failures, values.len()))
+}
+
+/// `text` with the blessed cases swapped in.
+fn bless_text(text: &str, failures: &[CaseFailure]) -> String {
+    let lines = text.lines().collect_vec();
+    let mut new_lines: Vec<String> = Vec::new();
+    let mut last = 0;
+    for failure in failures {
+        if let Ok(blessed) = &failure.blessed {
+            new_lines.extend(
+                lines[last..failure.lines.start]
+                    .iter()
+                    .map(|l| l.to_string()),
+            );
+            new_lines.extend(blessed.iter().cloned());
+            last = failure.lines.end;
+        }
+    }
+    new_lines.extend(lines[last..].iter().map(|l| l.to_string()));
+
+    let mut new_text = new_lines.join("\n");
+    if text.ends_with('\n') {
+        new_text.push('\n');
+    }
+    new_text
+}
+
+/// Line ranges of the top-level list items; each runs up to the next item.
+fn case_blocks(lines: &[&str]) -> Vec<Range<usize>> {
+    let starts = lines
+        .iter()
+        .positions(|l| l.starts_with("- "))
+        .collect_vec();
+    starts
+        .iter()
+        .zip(starts.iter().skip(1).chain([&lines.len()]))
+        .map(|(&start, &end)| start..end)
+        .collect()
+}
+
+fn check_case_value(value: &Value, lines: &[&str], block: Range<usize>) -> Option<CaseFailure> {
+    let name = value
+        .get("name")
+        .and_then(Value::as_str)
+        .unwrap_or("Unnamed")
+        .to_string();
+    let case = match SuiteCase::from_value(value) {
+        Ok(case) => case,
+        Err(e) => {
+            return Some(CaseFailure {
+                name,
+                failure: format!("Failed to parse: {e:#}"),
+                diff: None,
+                blessed: Err("the case doesn't parse".to_string()),
+                lines: block,
+            });
+        }
+    };
+    let failure = format!("{:#}", case.check_all_variants().err()?);
+
+    let (diff, blessed) = match case_diff(&case) {
+        Ok(Some(diff)) => (
+            Some(diff),
+            bless_case(&case, value, &lines[block.clone()]).map_err(|e| format!("{e:#}")),
+        ),
+        Ok(None) => (
+            None,
+            Err("the plain drag matches; the failure is in another variant".to_string()),
+        ),
+        Err(e) => (None, Err(format!("{e:#}"))),
+    };
+    Some(CaseFailure {
+        name,
+        failure,
+        diff,
+        blessed,
+        lines: block,
+    })
+}
+
+type Errors = HashSet<(TilePosition, Error)>;
+
+/// The expected and actual results of the plain (untransformed) drag.
+struct CaseResults<'a> {
+    before: &'a WorldImpl,
+    expected: &'a WorldImpl,
+    expected_errors: &'a Errors,
+    expected_trace: Option<&'a [TraceStep]>,
+    actual: WorldImpl,
+    actual_errors: Errors,
+    actual_trace: Vec<TraceStep>,
+}
+
+fn case_results(case: &SuiteCase) -> Result<CaseResults<'_>> {
+    match case {
+        SuiteCase::Drag(test_case) => {
+            let entities = &test_case.entities;
+            let ((actual, actual_errors), actual_trace) =
+                with_trace(|| run_test_case(entities, TestVariant::Normal));
+            Ok(CaseResults {
+                before: &entities.before,
+                expected: &entities.after,
+                expected_errors: &entities.expected_errors,
+                expected_trace: test_case.trace.as_deref(),
+                actual,
+                actual_errors,
+                actual_trace,
+            })
+        }
+        SuiteCase::Steps(test_case) => {
+            let mut actual = test_case.before.clone();
+            let (actual_errors, actual_trace) = with_trace(|| {
+                run_drag_steps(
+                    &mut actual,
+                    test_case.tier,
+                    test_case.start_pos,
+                    test_case.belt_direction,
+                    &test_case.steps,
+                )
+            });
+            Ok(CaseResults {
+                before: &test_case.before,
+                expected: &test_case.after,
+                expected_errors: &test_case.expected_errors,
+                expected_trace: test_case.trace.as_deref(),
+                actual,
+                actual_errors: actual_errors?,
+                actual_trace,
+            })
+        }
+        SuiteCase::Scenario(_) => bail!("Blessing scenarios is not supported"),
+    }
+}
+
+fn case_diff(case: &SuiteCase) -> Result<Option<CaseDiff>> {
+    let results = case_results(case)?;
+    let trace_matches = results
+        .expected_trace
+        .is_none_or(|trace| trace == results.actual_trace);
+    if *results.expected == results.actual
+        && *results.expected_errors == results.actual_errors
+        && trace_matches
+    {
+        return Ok(None);
+    }
+    let mut bounds = results
+        .before
+        .bounds()
+        .union(&results.expected.bounds())
+        .union(&results.actual.bounds());
+    bounds.min = pos(0, 0);
+    let print = |world: &WorldImpl, errors: &Errors| {
+        let (markers, names) = sorted_errors(errors);
+        let rows = print_world(world, bounds, &markers)
+            .lines()
+            .map(str::to_string)
+            .collect();
+        (rows, names)
+    };
+    let (expected, expected_errors) = print(results.expected, results.expected_errors);
+    let (actual, actual_errors) = print(&results.actual, &results.actual_errors);
+    let (expected_trace, actual_trace) = match results.expected_trace {
+        Some(trace) => (
+            trace.iter().map(TraceStep::to_string).collect(),
+            results
+                .actual_trace
+                .iter()
+                .map(TraceStep::to_string)
+                .collect(),
+        ),
+        None => (Vec::new(), Vec::new()),
+    };
+    Ok(Some(CaseDiff {
+        expected,
+        actual,
+        expected_errors,
+        actual_errors,
+        expected_trace,
+        actual_trace,
+    }))
+}
+
+/// Error positions and names in reading order, as `*` markers are paired with them.
+fn sorted_errors(errors: &Errors) -> (Vec<TilePosition>, Vec<String>) {
+    errors
+        .iter()
+        .map(|(p, e)| {
+            let name =
+                serde_yaml::to_string(e).map_or_else(|e| e.to_string(), |s| s.trim().to_string());
+            (*p, name)
+        })
+        .sorted_by_key(|(p, name)| (p.y, p.x, name.clone()))
+        .unzip()
+}
+
+/// The case's lines with `after`, `expected_errors` and `trace` (if it has
+/// one) set to the plain drag's result.
+fn bless_case(case: &SuiteCase, value: &Value, block: &[&str]) -> Result<Vec<String>> {
+    let CaseResults {
+        actual,
+        actual_errors,
+        actual_trace,
+        ..
+    } = case_results(case)?;
+    let old_after = value
+        .get("after")
+        .and_then(Value::as_str)
+        .context("Case has no 'after'")?;
+    let (markers, names) = sorted_errors(&actual_errors);
+    let after = render_grid(old_after, &actual, &markers)?;
+    let trace = value
+        .get("trace")
+        .map(|_| actual_trace.iter().map(TraceStep::to_string).collect_vec());
+    let new_block = rewrite_fields(block, &after, &names, trace.as_deref())?;
+
+    let new_value = serde_yaml::from_str::<Vec<Value>>(&new_block.join("\n"))?
+        .pop()
+        .context("Rewritten case is empty")?;
+    let new_case = SuiteCase::from_value(&new_value).context("Rewritten case doesn't parse")?;
+    if let (SuiteCase::Drag(old), SuiteCase::Drag(new)) = (case, &new_case) {
+        let drag = |t: &DragTestCase| {
+            let e = &t.entities;
+            (
+                e.start_pos,
+                e.end_pos,
+                e.leftmost_pos,
+                e.belt_direction,
+                e.tier.tier_index(),
+            )
+        };
+        if drag(old) != drag(new) {
+            bail!(
+                "the new 'after' changes the inferred drag; give 'start', 'end', 'direction' or 'tier' explicitly"
+            );
+        }
+    }
+    new_case
+        .check_all_variants()
+        .context("the rewritten case still fails")?;
+    Ok(new_block)
+}
+
+/// `world` printed in the shape of `old_grid`, keeping its `[label]`s.
+/// Rows past the old grid, and words past the end of an old row, are only
+/// kept if not empty.
+fn render_grid(old_grid: &str, world: &WorldImpl, markers: &[TilePosition]) -> Result<String> {
+    let old = parse_labeled_world(old_grid)?;
+    let world_max = if world.entities.is_empty() {
+        pos(0, 0)
+    } else {
+        world.bounds().max
+    };
+    let max = pos(world_max.x.max(old.size.x), world_max.y.max(old.size.y));
+    let bounds = BoundingBox::new(pos(0, 0), max);
+    if bounds.is_empty() {
+        return Ok(String::new());
+    }
+
+    let mut rows: Vec<Vec<String>> = print_world(world, bounds, markers)
+        .lines()
+        .map(|line| line.split_whitespace().map(str::to_string).collect())
+        .collect();
+    for (label, p) in &old.labels {
+        let word = &mut rows[p.y as usize][p.x as usize];
+        *word = format!("[{label}]{word}");
+    }
+    let old_widths = old_grid
+        .lines()
+        .map(|line| line.split_whitespace().count())
+        .collect_vec();
+    for (y, row) in rows.iter_mut().enumerate() {
+        let old_width = old_widths.get(y).copied().unwrap_or(0);
+        while row.len() > old_width && row.last().is_some_and(|w| w == "_") {
+            row.pop();
+        }
+    }
+    while rows.len() > old.size.y as usize
+        && rows.last().is_some_and(|r| r.iter().all(|w| w == "_"))
+    {
+        rows.pop();
+    }
+    Ok(align_columns(&rows.iter().map(|r| r.join(" ")).join("\n")))
+}
+
+/// Replaces the case's top-level `after`, `expected_errors` and, if given,
+/// `trace`, leaving every other line as is.
+fn rewrite_fields(
+    block: &[&str],
+    after: &str,
+    errors: &[String],
+    trace: Option<&[String]>,
+) -> Result<Vec<String>> {
+    let mut lines = block.iter().map(|l| l.to_string()).collect_vec();
+
+    let after_range = find_key(&lines, "after").context("Case has no 'after'")?;
+    let key_line = &lines[after_range.start];
+    let prefix = key_line[..2].to_string();
+    let was_block = key_line[2 + "after:".len()..].trim().starts_with('|');
+    let mut new_after = Vec::new();
+    if was_block || after.lines().count() > 1 {
+        new_after.push(format!("{prefix}after: |"));
+        new_after.extend(after.lines().map(|l| {
+            if l.is_empty() {
+                String::new()
+            } else {
+                format!("    {l}")
+            }
+        }));
+    } else {
+        new_after.push(format!("{prefix}after: \"{after}\""));
+    }
+    let after_end = after_range.start + new_after.len();
+    lines.splice(after_range, new_after);
+
+    let new_errors =
+        (!errors.is_empty()).then(|| format!("  expected_errors: [{}]", errors.join(", ")));
+    match find_key(&lines, "expected_errors") {
+        Some(range) => {
+            lines.splice(range, new_errors);
+        }
+        None => {
+            lines.splice(after_end..after_end, new_errors);
+        }
+    }
+
+    if let Some(trace) = trace {
+        let range = find_key(&lines, "trace").context("Case has no 'trace'")?;
+        let new_trace = if trace.is_empty() {
+            vec!["  trace: []".to_string()]
+        } else {
+            ["  trace:".to_string()]
+                .into_iter()
+                .chain(trace.iter().map(|step| format!("    - {step}")))
+                .collect()
+        };
+        lines.splice(range, new_trace);
+    }
+    Ok(lines)
+}
+
+/// The lines of a top-level key of a case, including a multi-line value.
+fn find_key(lines: &[String], key: &str) -> Option<Range<usize>> {
+    let start = lines.iter().enumerate().position(|(i, line)| {
+        let rest = if i == 0 {
+            line.strip_prefix("- ")
+        } else {
+            line.strip_prefix("  ")
+        };
+        rest.and_then(|r| r.strip_prefix(key))
+            .is_some_and(|r| r.starts_with(':'))
+    })?;
+    let mut end = start + 1;
+    for (i, line) in lines.iter().enumerate().skip(start + 1) {
+        if line.trim().is_empty() {
+            continue;
+        }
+        if !line.starts_with("   ") {
+            break;
+        }
+        end = i + 1;
+    }
+    Some(start..end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SUITE: &str = r#"# A comment about the file
+
+- name: Passes
+  before: _ > >
+  after: "> > >"
+
+# A comment about the next case
+- name: Wrong after
+  before: |
+    _ _ X
+  # Comment inside the case
+  after: |
+    > > > > >
+  not_reversible: true
+
+- name: Wrong errors
+  before: X
+  after: "*X > >"
+  expected_errors: [too_far_to_connect]
+"#;
+
+    #[test]
+    fn test_reports_only_failing_cases() {
+        let (failures, num_cases) = check_suite_text(SUITE).unwrap();
+        assert_eq!(num_cases, 3);
+        let names = failures.iter().map(|f| f.name.as_str()).collect_vec();
+        assert_eq!(names, ["Wrong after", "Wrong errors"]);
+
+        let diff = failures[0].diff.as_ref().unwrap();
+        assert_ne!(diff.expected, diff.actual);
+        let diff = failures[1].diff.as_ref().unwrap();
+        assert_eq!(diff.expected, diff.actual);
+        assert_eq!(diff.actual_errors, ["entity_in_the_way"]);
+    }
+
+    #[test]
+    fn test_bless_keeps_comments_and_flags() {
+        let (failures, _) = check_suite_text(SUITE).unwrap();
+        assert!(failures.iter().all(|f| f.blessed.is_ok()), "{failures:?}");
+
+        let blessed = bless_text(SUITE, &failures);
+        let expected = SUITE
+            .replace("    > > > > >", "    > >i X >o >")
+            .replace("[too_far_to_connect]", "[entity_in_the_way]");
+        assert_eq!(blessed, expected);
+        assert!(check_suite_text(&blessed).unwrap().0.is_empty());
+    }
+
+    #[test]
+    fn test_bless_records_trace() {
+        let suite = "- name: Trace\n  before: _ X _\n  after: \">i X >o >\"\n  trace: []\n";
+        let (failures, _) = check_suite_text(suite).unwrap();
+        let diff = failures[0].diff.as_ref().unwrap();
+        assert!(diff.expected_trace.is_empty());
+        assert_eq!(diff.actual_trace.len(), 3);
+
+        let blessed = bless_text(suite, &failures);
+        assert!(
+            blessed.ends_with(
+                "  after: \">i X >o >\"\n  trace:\n    - (1, 0) Belt + Obstacle -> None\n    - (2, 0) TraversingObstacle + Usable -> CreateUnderground\n    - (3, 0) ExtendableUnderground + Usable -> PlaceBelt\n"
+            ),
+            "{blessed}"
+        );
+        assert!(check_suite_text(&blessed).unwrap().0.is_empty());
+    }
+
+    #[test]
+    fn test_bless_adds_and_removes_expected_errors() {
+        let block = [
+            "- name: Test",
+            "  before: X",
+            "  after: \"X\"",
+            "  forward_back: true",
+        ];
+        let lines =
+            rewrite_fields(&block, "*X >", &["entity_in_the_way".to_string()], None).unwrap();
+        assert_eq!(
+            lines,
+            [
+                "- name: Test",
+                "  before: X",
+                "  after: \"*X >\"",
+                "  expected_errors: [entity_in_the_way]",
+                "  forward_back: true"
+            ]
+        );
+        let lines = lines.iter().map(String::as_str).collect_vec();
+        let lines = rewrite_fields(&lines, "> >", &[], None).unwrap();
+        assert_eq!(
+            lines,
+            [
+                "- name: Test",
+                "  before: X",
+                "  after: \"> >\"",
+                "  forward_back: true"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_render_grid_keeps_labels_and_shape() {
+        let (world, _) = crate::test_case::parse_world("> >i X >o >").unwrap();
+        let grid = render_grid("[a]_ _ _\n_ _ [b]_", &world, &[pos(1, 0)]).unwrap();
+        assert_eq!(grid, "[a]> *>i X    >o >\n_    _   [b]_");
+    }
+}
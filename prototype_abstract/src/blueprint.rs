@@ -0,0 +1,233 @@
+//! Worlds from Factorio blueprints: exchange strings (`0`, then base64 of
+//! zlib-compressed JSON) or their JSON.
+//!
+//! Belts, undergrounds, splitters and loader-likes (loaders and linked belts)
+//! of the built-in tiers are imported as such; every other entity blocks the
+//! tile at its position, whatever its real size (see
+//! [`BlueprintImport::other_entities`]). Tiles (e.g. concrete) are ignored. The
+//! world is moved so that its top left tile is at `(0, 0)`.
+
+use std::collections::BTreeSet;
+use std::io::Read;
+
+use anyhow::{Context, Result, bail};
+use base64::Engine;
+use serde::Deserialize;
+
+use crate::belts::{BELT_TIERS, Belt, BeltTier, LoaderLike, Splitter, UndergroundBelt};
+use crate::{BeltCollidable, CollidingEntityOrTile, Direction, TilePosition, WorldImpl, pos};
+
+/// A world imported from a blueprint.
+#[derive(Debug, Clone)]
+pub struct BlueprintImport {
+    pub world: WorldImpl,
+    /// The names of the entities imported as obstacles. Each blocks only the
+    /// tile at its centre, so the bigger ones leave gaps a drag may go
+    /// through.
+    pub other_entities: BTreeSet<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BlueprintJson {
+    blueprint: Option<Blueprint>,
+    blueprint_book: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Blueprint {
+    #[serde(default)]
+    entities: Vec<BlueprintEntity>,
+    #[serde(default)]
+    version: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct BlueprintEntity {
+    name: String,
+    position: BlueprintPosition,
+    #[serde(default)]
+    direction: u8,
+    /// `"input"` or `"output"`, for undergrounds and loader-likes.
+    #[serde(rename = "type")]
+    io_type: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BlueprintPosition {
+    x: f64,
+    y: f64,
+}
+
+/// A blueprint exchange string, as copied from the game.
+pub fn parse_blueprint_string(s: &str) -> Result<BlueprintImport> {
+    let s = s.trim();
+    let Some(data) = s.strip_prefix('0') else {
+        bail!("Not a blueprint string: it should start with a 0 (the format version)");
+    };
+    let compressed = base64::engine::general_purpose::STANDARD
+        .decode(data)
+        .context("Blueprint string is not valid base64")?;
+    let mut json = String::new();
+    flate2::read::ZlibDecoder::new(compressed.as_slice())
+        .read_to_string(&mut json)
+        .context("Blueprint string is not valid zlib data")?;
+    parse_blueprint_json(&json)
+}
+
+/// The JSON inside a blueprint string: `{"blueprint": {...}}`.
+pub fn parse_blueprint_json(json: &str) -> Result<BlueprintImport> {
+    let parsed: BlueprintJson = serde_json::from_str(json).context("Invalid blueprint JSON")?;
+    let blueprint = match parsed {
+        BlueprintJson {
+            blueprint: Some(blueprint),
+            ..
+        } => blueprint,
+        BlueprintJson {
+            blueprint_book: Some(_),
+            ..
+        } => bail!("Blueprint books are not supported; export a single blueprint"),
+        _ => bail!("No 'blueprint' in the JSON"),
+    };
+
+    // Factorio 2.0 has 16 directions, where 1.x had 8
+    let direction_steps = if blueprint.version >> 48 >= 2 { 4 } else { 2 };
+    let mut placed = Vec::new();
+    let mut other_entities = BTreeSet::new();
+    for entity in &blueprint.entities {
+        let imported = import_entity(entity, direction_steps)
+            .with_context(|| format!("Importing {} at {:?}", entity.name, entity.position))?;
+        if let [(_, BeltCollidable::CollidingEntityOrTile(_))] = imported[..] {
+            other_entities.insert(entity.name.clone());
+        }
+        placed.extend(imported);
+    }
+
+    let mut world = WorldImpl::new();
+    if let Some(min_x) = placed.iter().map(|(p, _)| p.x).min() {
+        let min_y = placed.iter().map(|(p, _)| p.y).min().unwrap();
+        for (p, entity) in placed {
+            world.build(pos(p.x - min_x, p.y - min_y), entity);
+        }
+    }
+    Ok(BlueprintImport {
+        world,
+        other_entities,
+    })
+}
+
+fn import_entity(
+    entity: &BlueprintEntity,
+    direction_steps: u8,
+) -> Result<Vec<(TilePosition, BeltCollidable)>> {
+    let (x, y) = (entity.position.x, entity.position.y);
+    let tile = |x: f64, y: f64| pos(x.floor() as i32, y.floor() as i32);
+    // Only asked of belt-likes: other entities (e.g. rails) may be diagonal
+    let direction = || -> Result<Direction> {
+        if !entity.direction.is_multiple_of(direction_steps) {
+            bail!("Direction {} is not a multiple of 90°", entity.direction);
+        }
+        Direction::from_ordinal(entity.direction / direction_steps)
+            .with_context(|| format!("Direction {} is out of range", entity.direction))
+    };
+    let is_input = || match entity.io_type.as_deref() {
+        Some("input") => Ok(true),
+        Some("output") => Ok(false),
+        other => bail!("Expected a type of input or output, not {other:?}"),
+    };
+
+    let name = entity.name.as_str();
+    Ok(if let Some(tier) = tier_of(name, "transport-belt")? {
+        vec![(tile(x, y), Belt::new(direction()?, tier).into())]
+    } else if let Some(tier) = tier_of(name, "underground-belt")? {
+        let ug = UndergroundBelt::new(direction()?, is_input()?, tier);
+        vec![(tile(x, y), ug.into())]
+    } else if let Some(tier) = tier_of(name, "splitter")? {
+        // Two tiles wide, centered between them
+        let direction = direction()?;
+        let side = direction.rotate_cw().to_vector().to_f64() * 0.5;
+        let splitter = Splitter::new(direction, tier);
+        vec![
+            (tile(x - side.x, y - side.y), splitter.clone().into()),
+            (tile(x + side.x, y + side.y), splitter.into()),
+        ]
+    } else if name == "linked-belt" || name.contains("loader") {
+        let tier = tier_of(name.trim_end_matches("-1x1"), "loader")?.unwrap_or(BELT_TIERS[0]);
+        let loader = LoaderLike::new(direction()?, is_input()?, tier);
+        vec![(tile(x, y), loader.into())]
+    } else {
+        vec![(tile(x, y), CollidingEntityOrTile.into())]
+    })
+}
+
+/// The tier of `name` if it is `kind` with a tier prefix (`fast-splitter`),
+/// `None` if it is not `kind`, and an error for tiers past the built-in ones.
+fn tier_of(name: &str, kind: &str) -> Result<Option<BeltTier>> {
+    let Some(prefix) = name.strip_suffix(kind) else {
+        return Ok(None);
+    };
+    Ok(Some(match prefix {
+        "" => BELT_TIERS[0],
+        "fast-" => BELT_TIERS[1],
+        "express-" => BELT_TIERS[2],
+        _ => bail!("Unsupported belt tier {:?}", prefix.trim_end_matches('-')),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_case::{parse_world, print_world};
+    use base64::Engine;
+    use std::io::Write;
+
+    const JSON: &str = r#"{"blueprint": {"version": 562949954076673, "entities": [
+        {"entity_number": 1, "name": "transport-belt", "position": {"x": 10.5, "y": 3.5}, "direction": 4},
+        {"entity_number": 2, "name": "fast-underground-belt", "position": {"x": 11.5, "y": 3.5}, "direction": 4, "type": "input"},
+        {"entity_number": 3, "name": "iron-chest", "position": {"x": 12.5, "y": 3.5}},
+        {"entity_number": 4, "name": "fast-underground-belt", "position": {"x": 13.5, "y": 3.5}, "direction": 4, "type": "output"},
+        {"entity_number": 5, "name": "splitter", "position": {"x": 14.5, "y": 4}, "direction": 4},
+        {"entity_number": 6, "name": "linked-belt", "position": {"x": 10.5, "y": 4.5}, "direction": 8, "type": "output"}
+    ]}}"#;
+
+    #[test]
+    fn test_parse_blueprint_json() {
+        let BlueprintImport {
+            world,
+            other_entities,
+        } = parse_blueprint_json(JSON).unwrap();
+        let (expected, _) = parse_world("> 2>i X 2>o >s\nvO _ _ _ >s").unwrap();
+        assert_eq!(
+            print_world(&world, world.bounds(), &[]),
+            print_world(&expected, expected.bounds(), &[])
+        );
+        assert_eq!(other_entities, BTreeSet::from(["iron-chest".to_string()]));
+    }
+
+    #[test]
+    fn test_diagonal_directions_only_matter_for_belts() {
+        let rail = r#"{"name": "curved-rail", "position": {"x": 9, "y": 2}, "direction": 5}"#;
+        let json = JSON.replace("\"entities\": [", &format!("\"entities\": [{rail}, "));
+        let import = parse_blueprint_json(&json).unwrap();
+        assert!(import.other_entities.contains("curved-rail"));
+
+        let diagonal_belt = JSON.replace("\"direction\": 8", "\"direction\": 6");
+        let err = parse_blueprint_json(&diagonal_belt).unwrap_err();
+        assert!(
+            format!("{err:#}").contains("not a multiple of 90°"),
+            "{err:#}"
+        );
+    }
+
+    #[test]
+    fn test_parse_blueprint_string() {
+        let mut encoder =
+            flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(JSON.as_bytes()).unwrap();
+        let encoded = base64::engine::general_purpose::STANDARD.encode(encoder.finish().unwrap());
+        let import = parse_blueprint_string(&format!("0{encoded}\n")).unwrap();
+        assert_eq!(import.world.bounds().size().width, 5);
+
+        assert!(parse_blueprint_string(&format!("1{encoded}")).is_err());
+        assert!(parse_blueprint_json(&JSON.replace("\"splitter\"", "\"turbo-splitter\"")).is_err());
+    }
+}
@@ -0,0 +1,438 @@
+//! Rule coverage: which decisions of the drag state machine a drag exercises.
+//!
+//! Every drag step records its drag end shape, the type of the next tile, and
+//! the resulting action and error; the tile classifier also records which
+//! branch decided the tile type. Recording is per thread, and only happens
+//! inside [`with_coverage`].
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use itertools::Itertools;
+use serde_yaml::Value;
+
+use crate::smart_belt::action::Error;
+use crate::test_case::SuiteCase;
+
+thread_local! {
+    static RECORDER: RefCell<Option<Coverage>> = const { RefCell::new(None) };
+}
+
+/// [`DragState`](crate::smart_belt::drag_state), without positions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum DragEndKind {
+    Belt,
+    IntegratedOutput,
+    ExtendableUnderground,
+    TraversingObstacle,
+    OverImpassableObstacle,
+    Error,
+    OverlappingOutputUnderground,
+    InBetweenUndergrounds,
+}
+
+impl DragEndKind {
+    pub const ALL: [DragEndKind; 8] = [
+        DragEndKind::Belt,
+        DragEndKind::IntegratedOutput,
+        DragEndKind::ExtendableUnderground,
+        DragEndKind::TraversingObstacle,
+        DragEndKind::OverImpassableObstacle,
+        DragEndKind::Error,
+        DragEndKind::OverlappingOutputUnderground,
+        DragEndKind::InBetweenUndergrounds,
+    ];
+
+    /// If steps from this shape classify the next tile.
+    pub fn classifies_tiles(self) -> bool {
+        !matches!(
+            self,
+            DragEndKind::OverlappingOutputUnderground | DragEndKind::InBetweenUndergrounds
+        )
+    }
+}
+
+/// [`TileType`](crate::smart_belt::tile_classification), without positions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum TileKind {
+    Usable,
+    Obstacle,
+    IntegratedSplitter,
+    IntegratedUnderground,
+    ImpassableObstacle,
+}
+
+impl TileKind {
+    pub const ALL: [TileKind; 5] = [
+        TileKind::Usable,
+        TileKind::Obstacle,
+        TileKind::IntegratedSplitter,
+        TileKind::IntegratedUnderground,
+        TileKind::ImpassableObstacle,
+    ];
+}
+
+/// [`Action`](crate::smart_belt::action::Action), without positions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ActionKind {
+    PlaceBelt,
+    CreateUnderground,
+    ExtendUnderground,
+    IntegrateInputUnderground,
+    IntegrateOutputUnderground,
+    IntegrateSplitter,
+    SetImpassable,
+    ClearEntity,
+    None,
+}
+
+/// A branch in the tile classifier that decides a tile type, or decides
+/// whether to integrate a belt segment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Branch {
+    EmptyTile,
+    NonBeltEntity,
+    CurvedBeltConnectedToIntegrated,
+    CurvedBeltOther,
+    StraightBeltObstacle,
+    StraightBeltForwards,
+    StraightBeltConnectedToIntegrated,
+    StraightBeltSegmentIntegrated,
+    StraightBeltSegmentNotIntegrated,
+    UndergroundObstacle,
+    PairedUndergroundEntered,
+    PairedUndergroundObstacle,
+    UnpairedUndergroundEnterable,
+    UnpairedUndergroundSameTier,
+    UnpairedUndergroundSegmentIntegrated,
+    UnpairedUndergroundObstacle,
+    SplitterConnectedToIntegrated,
+    SplitterConnectedWrongDirection,
+    SplitterNotEnterable,
+    SplitterSegmentIntegrated,
+    SplitterSegmentNotIntegrated,
+    LoaderConnectsInto,
+    LoaderObstacle,
+    SegmentNoUnderground,
+    SegmentSplitterExitBlocked,
+    SegmentCurvedBelt,
+    SegmentSplitterOrLoader,
+    SegmentSameTierUnderground,
+    SegmentUnpairedUnderground,
+    SegmentEnd,
+}
+
+impl Branch {
+    pub const ALL: [Branch; 30] = [
+        Branch::EmptyTile,
+        Branch::NonBeltEntity,
+        Branch::CurvedBeltConnectedToIntegrated,
+        Branch::CurvedBeltOther,
+        Branch::StraightBeltObstacle,
+        Branch::StraightBeltForwards,
+        Branch::StraightBeltConnectedToIntegrated,
+        Branch::StraightBeltSegmentIntegrated,
+        Branch::StraightBeltSegmentNotIntegrated,
+        Branch::UndergroundObstacle,
+        Branch::PairedUndergroundEntered,
+        Branch::PairedUndergroundObstacle,
+        Branch::UnpairedUndergroundEnterable,
+        Branch::UnpairedUndergroundSameTier,
+        Branch::UnpairedUndergroundSegmentIntegrated,
+        Branch::UnpairedUndergroundObstacle,
+        Branch::SplitterConnectedToIntegrated,
+        Branch::SplitterConnectedWrongDirection,
+        Branch::SplitterNotEnterable,
+        Branch::SplitterSegmentIntegrated,
+        Branch::SplitterSegmentNotIntegrated,
+        Branch::LoaderConnectsInto,
+        Branch::LoaderObstacle,
+        Branch::SegmentNoUnderground,
+        Branch::SegmentSplitterExitBlocked,
+        Branch::SegmentCurvedBelt,
+        Branch::SegmentSplitterOrLoader,
+        Branch::SegmentSameTierUnderground,
+        Branch::SegmentUnpairedUnderground,
+        Branch::SegmentEnd,
+    ];
+}
+
+/// One drag step, as a combination of decisions.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct StepRule {
+    pub drag_end: DragEndKind,
+    /// `None` for shapes that don't classify the next tile.
+    pub tile: Option<TileKind>,
+    pub action: ActionKind,
+    pub error: Option<Error>,
+}
+
+/// `DragEnd + Tile -> Action, Error`, omitting the parts that are `None`.
+impl fmt::Display for StepRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} -> {:?}",
+            format_state(self.drag_end, self.tile),
+            self.action
+        )?;
+        if let Some(error) = &self.error {
+            write!(f, ", {error:?}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Hit counts of step rules and classifier branches.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Coverage {
+    pub steps: BTreeMap<StepRule, usize>,
+    pub branches: BTreeMap<Branch, usize>,
+}
+
+impl Coverage {
+    pub fn merge(&mut self, other: &Coverage) {
+        for (rule, count) in &other.steps {
+            *self.steps.entry(rule.clone()).or_default() += count;
+        }
+        for (branch, count) in &other.branches {
+            *self.branches.entry(*branch).or_default() += count;
+        }
+    }
+
+    /// Number of step rules and branches hit here but not in `known`.
+    pub fn new_in(&self, known: &Coverage) -> usize {
+        let new_steps = self
+            .steps
+            .keys()
+            .filter(|r| !known.steps.contains_key(r))
+            .count();
+        let new_branches = self
+            .branches
+            .keys()
+            .filter(|b| !known.branches.contains_key(b))
+            .count();
+        new_steps + new_branches
+    }
+
+    pub fn reached_states(&self) -> impl Iterator<Item = (DragEndKind, Option<TileKind>)> + '_ {
+        self.steps.keys().map(|r| (r.drag_end, r.tile)).dedup()
+    }
+
+    /// Drag end shape and tile type pairs no step hit.
+    pub fn unreached_states(&self) -> Vec<(DragEndKind, Option<TileKind>)> {
+        let reached = self.reached_states().collect_vec();
+        all_states()
+            .filter(|state| !reached.contains(state))
+            .collect()
+    }
+
+    pub fn unreached_branches(&self) -> Vec<Branch> {
+        Branch::ALL
+            .into_iter()
+            .filter(|b| !self.branches.contains_key(b))
+            .collect()
+    }
+}
+
+/// Every drag end shape, with every tile type if it classifies tiles.
+pub fn all_states() -> impl Iterator<Item = (DragEndKind, Option<TileKind>)> {
+    DragEndKind::ALL.into_iter().flat_map(|drag_end| {
+        let tiles = if drag_end.classifies_tiles() {
+            TileKind::ALL.map(Some).to_vec()
+        } else {
+            vec![None]
+        };
+        tiles.into_iter().map(move |tile| (drag_end, tile))
+    })
+}
+
+/// Runs `f`, recording the coverage of all drags on this thread meanwhile.
+pub fn with_coverage<R>(f: impl FnOnce() -> R) -> (R, Coverage) {
+    let outer = RECORDER.with(|r| r.replace(Some(Coverage::default())));
+    let result = f();
+    let coverage = RECORDER.with(|r| r.replace(outer)).unwrap_or_default();
+    // A nested recording also counts towards the outer one
+    RECORDER.with(|r| {
+        if let Some(outer) = r.borrow_mut().as_mut() {
+            outer.merge(&coverage);
+        }
+    });
+    (result, coverage)
+}
+
+pub(crate) fn record_step(rule: StepRule) {
+    RECORDER.with(|r| {
+        if let Some(coverage) = r.borrow_mut().as_mut() {
+            *coverage.steps.entry(rule).or_default() += 1;
+        }
+    });
+}
+
+pub(crate) fn record_branch(branch: Branch) {
+    RECORDER.with(|r| {
+        if let Some(coverage) = r.borrow_mut().as_mut() {
+            *coverage.branches.entry(branch).or_default() += 1;
+        }
+    });
+}
+
+/// Records `branch` if `taken`, for one part of a condition: with `||`, only the
+/// first part that holds is recorded.
+pub(crate) fn record_if(taken: bool, branch: Branch) -> bool {
+    if taken {
+        record_branch(branch);
+    }
+    taken
+}
+
+/// The coverage of each case in a test suite.
+#[derive(Debug, Default)]
+pub struct CoverageReport {
+    /// `file: case name`, and what its variants hit.
+    pub cases: Vec<(String, Coverage)>,
+}
+
+impl CoverageReport {
+    pub fn total(&self) -> Coverage {
+        let mut total = Coverage::default();
+        for (_, coverage) in &self.cases {
+            total.merge(coverage);
+        }
+        total
+    }
+
+    fn num_cases_hitting(&self, rule: &StepRule) -> usize {
+        self.cases
+            .iter()
+            .filter(|(_, c)| c.steps.contains_key(rule))
+            .count()
+    }
+}
+
+impl fmt::Display for CoverageReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let total = self.total();
+        let num_states = all_states().count();
+        let unreached_states = total.unreached_states();
+        let unreached_branches = total.unreached_branches();
+
+        writeln!(f, "Rule coverage of {} cases", self.cases.len())?;
+        writeln!(
+            f,
+            "  States (drag end, tile): {}/{num_states} reached",
+            num_states - unreached_states.len()
+        )?;
+        writeln!(
+            f,
+            "  Classifier branches: {}/{} reached",
+            Branch::ALL.len() - unreached_branches.len(),
+            Branch::ALL.len()
+        )?;
+        writeln!(f, "  Step rules: {} distinct", total.steps.len())?;
+
+        writeln!(f, "\nUnreached states:")?;
+        for (drag_end, tile) in &unreached_states {
+            writeln!(f, "  {}", format_state(*drag_end, *tile))?;
+        }
+        writeln!(f, "\nUnreached branches:")?;
+        for branch in &unreached_branches {
+            writeln!(f, "  {branch:?}")?;
+        }
+
+        writeln!(f, "\nStep rules (cases, steps):")?;
+        for (rule, count) in &total.steps {
+            writeln!(f, "  {rule}: {}, {count}", self.num_cases_hitting(rule))?;
+        }
+        Ok(())
+    }
+}
+
+fn format_state(drag_end: DragEndKind, tile: Option<TileKind>) -> String {
+    match tile {
+        Some(tile) => format!("{drag_end:?} + {tile:?}"),
+        None => format!("{drag_end:?}"),
+    }
+}
+
+/// Runs every case in the `*.yaml` files in `dir`, in all variants, recording
+/// the coverage of each. Failing cases are still recorded.
+pub fn suite_coverage(dir: &Path) -> Result<CoverageReport> {
+    let mut report = CoverageReport::default();
+    for path in std::fs::read_dir(dir)?
+        .map(|entry| entry.map(|e| e.path()))
+        .filter_ok(|path| path.extension().is_some_and(|e| e == "yaml"))
+        .collect::<Result<Vec<_>, _>>()?
+        .into_iter()
+        .sorted()
+    {
+        let text = std::fs::read_to_string(&path)?;
+        let values: Vec<Value> =
+            serde_yaml::from_str(&text).with_context(|| format!("In {path:?}"))?;
+        let file_stem = path.file_stem().unwrap_or_default().to_string_lossy();
+        for value in values {
+            let name = value
+                .get("name")
+                .and_then(Value::as_str)
+                .unwrap_or("Unnamed")
+                .to_string();
+            let case =
+                SuiteCase::from_value(&value).with_context(|| format!("In {path:?}: {name}"))?;
+            let (_, coverage) = with_coverage(|| case.check_all_variants());
+            report
+                .cases
+                .push((format!("{file_stem}: {name}"), coverage));
+        }
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_case::{DragStep, parse_world, run_drag_steps};
+    use crate::{BELT_TIERS, Direction, pos};
+
+    fn drag_east(world: &str) -> Coverage {
+        let (mut world, _) = parse_world(world).unwrap();
+        let steps = [DragStep::MoveTo(pos(3, 0))];
+        let (errors, coverage) = with_coverage(|| {
+            run_drag_steps(
+                &mut world,
+                BELT_TIERS[0],
+                pos(0, 0),
+                Direction::East,
+                &steps,
+            )
+        });
+        errors.unwrap();
+        coverage
+    }
+
+    #[test]
+    fn test_records_steps_and_branches() {
+        let coverage = drag_east("_ _ X _");
+        let obstacle_step = StepRule {
+            drag_end: DragEndKind::Belt,
+            tile: Some(TileKind::Obstacle),
+            action: ActionKind::None,
+            error: None,
+        };
+        assert!(coverage.steps.contains_key(&obstacle_step));
+        assert_eq!(coverage.branches[&Branch::NonBeltEntity], 1);
+        assert!(coverage.branches.contains_key(&Branch::EmptyTile));
+        assert!(!coverage.branches.contains_key(&Branch::CurvedBeltOther));
+    }
+
+    #[test]
+    fn test_nested_recording_counts_towards_outer() {
+        let (inner, outer) = with_coverage(|| drag_east("_ _ X _"));
+        assert_eq!(inner, outer);
+        assert_eq!(inner.new_in(&outer), 0);
+        // Nothing is recorded outside with_coverage
+        RECORDER.with(|r| assert!(r.borrow().is_none()));
+    }
+}
@@ -1,23 +1,18 @@
 use crate::{
-    BELT_TIERS, Belt, BeltCollidable, BeltConnectable, BeltConnectableTrait, BeltTier,
-    CollidingEntityOrTile, Direction, ImpassableTile, LoaderLike, Splitter, TilePosition,
-    UndergroundBelt, WorldImpl, pos,
+    BELT_TIERS, Belt, BeltCollidable, BeltTier, BoundingBox, CollidingEntityOrTile, Direction,
+    ImpassableTile, LoaderLike, Ray, Splitter, TilePosition, TileVec, UndergroundBelt, WorldImpl,
+    coverage::{Coverage, with_coverage},
+    pos,
     smart_belt::{LineDrag, action::Error},
-    test_case::print_world,
+    spec_properties::{DragPath, PropertyViolation, check_drag_properties},
+    test_case::{DragStep, print_world},
 };
 use euclid::{Box2D, Size2D};
 use rand::{Rng, SeedableRng, rngs::StdRng};
-use std::collections::HashSet;
-
-/// Error type for fuzzer assertion failures
-#[derive(Debug, Clone)]
-pub struct FuzzError(pub String, pub Option<TilePosition>);
-
-impl std::fmt::Display for FuzzError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.0)
-    }
-}
+use rayon::prelude::*;
+use std::any::Any;
+use std::collections::{HashSet, VecDeque};
+use std::ops::Range;
 
 #[derive(Debug, Clone)]
 pub struct FuzzConfig {
@@ -40,6 +35,9 @@ impl FuzzTestCase {
     pub fn end_pos(&self) -> TilePosition {
         pos(self.max_x + 1, 1)
     }
+    pub fn belt_direction(&self) -> Direction {
+        BELT_DIRECTION
+    }
 }
 const BELT_DIRECTION: Direction = Direction::East;
 
@@ -49,9 +47,7 @@ pub struct FuzzResult {
     pub world_before: WorldImpl,
     pub world_after: WorldImpl,
     pub errors: HashSet<(TilePosition, Error)>,
-    pub tier: BeltTier,
-    pub max_x: i32,
-    pub furthest_placement: i32,
+    pub drag_path: DragPath,
 }
 
 /// Generate a random world with entities
@@ -148,20 +144,13 @@ pub fn run_fuzz_test(test_case: &FuzzTestCase) -> Result<FuzzResult, String> {
             BELT_DIRECTION,
         );
         drag.interpolate_to(&mut error_handler, end_pos);
-        drag.furthest_placement_pos()
+        pos(drag.furthest_placement_pos(), start_pos.y)
     }))
     .map_err(|panic_info| {
-        let panic_message = if let Some(s) = panic_info.downcast_ref::<&str>() {
-            s.to_string()
-        } else if let Some(s) = panic_info.downcast_ref::<String>() {
-            s.clone()
-        } else {
-            format!("{:?}", panic_info)
-        };
         let bounds = Box2D::from_size(Size2D::new(test_case.max_x, 3));
         format!(
             "interpolate_to panicked: {}\nWorld before:\n{}",
-            panic_message,
+            panic_message(panic_info.as_ref()),
             print_world(&world_before, bounds, &[])
         )
     })?;
@@ -169,207 +158,467 @@ pub fn run_fuzz_test(test_case: &FuzzTestCase) -> Result<FuzzResult, String> {
     Ok(FuzzResult {
         world_before,
         world_after,
-        max_x: test_case.max_x,
         errors: errors.into_iter().collect(),
-        tier: test_case.tier,
-        furthest_placement,
+        drag_path: DragPath::straight(
+            start_pos,
+            furthest_placement,
+            BELT_DIRECTION,
+            test_case.tier,
+        ),
     })
 }
 
-fn is_belt_connected_to_previous_tile(world: &WorldImpl, next_distance: i32) -> bool {
-    let (last_pos, cur_pos) = (pos(next_distance - 1, 1), pos(next_distance, 1));
+/// The message of a panic caught with `catch_unwind`.
+pub(crate) fn panic_message(panic_info: &(dyn Any + Send)) -> String {
+    if let Some(s) = panic_info.downcast_ref::<&str>() {
+        s.to_string()
+    } else if let Some(s) = panic_info.downcast_ref::<String>() {
+        s.clone()
+    } else {
+        format!("{:?}", panic_info)
+    }
+}
 
-    let connects_forward = world.output_direction_at(last_pos) == Some(BELT_DIRECTION)
-        && world.input_direction_at(cur_pos) == Some(BELT_DIRECTION);
-    if connects_forward {
-        return true;
+impl FuzzResult {
+    /// Checks the spec properties for this drag.
+    pub fn check(&self) -> Result<(), PropertyViolation> {
+        check_drag_properties(
+            &self.world_before,
+            &self.world_after,
+            &self.drag_path,
+            &self.errors,
+        )
+    }
+
+    pub fn print_before_after(&self, markers: &[TilePosition]) {
+        let bounds = self.world_before.bounds().union(&self.world_after.bounds());
+        eprintln!(
+            r#"
+    Before:
+
+{}
+
+    After:
+
+{}
+    "#,
+            print_world(&self.world_before, bounds, markers),
+            print_world(&self.world_after, bounds, markers)
+        );
     }
-    let opposite_direction = BELT_DIRECTION.opposite();
-    world.output_direction_at(last_pos) == Some(opposite_direction)
-        && world.input_direction_at(cur_pos) == Some(opposite_direction)
 }
 
-/// Scan the belt line from start to end, treating splitters as straight belts
-/// Also follows through underground belt pairs
-pub fn scan_belt_line(world: &WorldImpl) -> Vec<TilePosition> {
-    let mut scan_pos = pos(0, 1);
-    let mut result = Vec::new();
-    let mut iterations = 0;
-    const MAX_ITERATIONS: i32 = 100;
-    while let Some(belt_connectable) = world
-        .get(scan_pos)
-        .and_then(|e| BeltConnectable::try_from(e).ok())
-        && (scan_pos.x == 0 || is_belt_connected_to_previous_tile(world, scan_pos.x))
-        && iterations < MAX_ITERATIONS
+/// Picks seeds from `seeds` whose drags reach coverage not in `known`.
+///
+/// Greedy: repeatedly takes the seed adding the most new step rules and
+/// branches, until no seed adds anything.
+pub fn seeds_by_new_coverage(config: &FuzzConfig, seeds: Range<u64>, known: &Coverage) -> Vec<u64> {
+    let mut candidates: Vec<(u64, Coverage)> = seeds
+        .into_par_iter()
+        .map(|seed| {
+            let test_case = generate_test_case(seed, config);
+            let (_, coverage) = with_coverage(|| run_fuzz_test(&test_case));
+            (seed, coverage)
+        })
+        .collect();
+    let mut covered = known.clone();
+    let mut picked = Vec::new();
+    while let Some((index, _)) = candidates
+        .iter()
+        .enumerate()
+        .map(|(i, (seed, coverage))| (i, (coverage.new_in(&covered), std::cmp::Reverse(*seed))))
+        .filter(|(_, (new, _))| *new > 0)
+        // On ties, prefer the lowest seed
+        .max_by_key(|&(_, key)| key)
     {
-        iterations += 1;
-        match &belt_connectable {
-            BeltConnectable::Belt(belt) => {
-                if world.belt_is_curved_at(scan_pos, belt) {
-                    break;
-                }
-                result.push(scan_pos);
-            }
-            BeltConnectable::UndergroundBelt(ug) => {
-                result.push(scan_pos);
-                let Some((pair_pos, _)) = world.get_ug_pair(scan_pos, ug) else {
-                    break;
-                };
-                result.push(pair_pos);
-                scan_pos = pair_pos;
-            }
-            BeltConnectable::Splitter(_) => {
-                result.push(scan_pos);
-            }
-            BeltConnectable::LoaderLike(_) => {
-                break;
-            }
-        }
-        scan_pos.x += 1;
+        let (seed, coverage) = candidates.swap_remove(index);
+        covered.merge(&coverage);
+        picked.push(seed);
     }
+    picked
+}
 
-    result
+#[derive(Debug, Clone)]
+pub struct Fuzz2dConfig {
+    pub world_size: i32,
+    pub entity_density: f32, // 0.0 to 1.0
+    /// Maximum number of rotations in a single drag.
+    pub max_rotations: usize,
+    /// Number of cursor movement patterns (jumps, wiggles, ...) per straight segment.
+    pub moves_per_segment: usize,
 }
-/// Check if all belts in the line have the expected tier
-pub fn check_belt_line_tier(
-    world: &WorldImpl,
-    belt_line: &[TilePosition],
-    expected_tier: BeltTier,
-) -> Result<(), FuzzError> {
-    belt_line.iter().try_for_each(|&pos| {
-        if let Some(entity) = world.get(pos)
-            && let Ok(belt_connectable) = BeltConnectable::try_from(entity)
-            && !matches!(entity, BeltCollidable::LoaderLike(_))
-            && belt_connectable.tier() != expected_tier
-        {
-            Err(FuzzError(
-                format!(
-                    "Belt at position {:?} has tier {:?}, expected {:?}",
-                    pos,
-                    belt_connectable.tier(),
-                    expected_tier
-                ),
-                Some(pos),
-            ))
-        } else {
-            Ok(())
-        }
-    })
+
+/// A randomly generated drag in a 2D world, in any direction.
+/// The cursor trace is generated while dragging, as rotations depend on the drag's pivot.
+#[derive(Debug, Clone)]
+pub struct Fuzz2dTestCase {
+    pub world: WorldImpl,
+    pub start_pos: TilePosition,
+    pub belt_direction: Direction,
+    pub tier: BeltTier,
+    pub seed: u64,
 }
 
-pub fn check_non_integrated_belts_unchanged(
-    before: &WorldImpl,
-    after: &WorldImpl,
-    integrated_positions: &HashSet<TilePosition>,
-) -> Result<(), FuzzError> {
-    for (&pos, entity_before) in &before.entities {
-        let is_integrated = integrated_positions.contains(&pos);
-        if !is_integrated {
-            let after_ent = after.get(pos);
-            if after_ent.is_none() {
-                // limitation: we may replace some belts
-                continue;
-            }
-            if Some(entity_before) != after_ent {
-                return Err(FuzzError(
-                    format!(
-                        "Belt changed at position {:?}. Before: {:?}, After: {:?}",
-                        pos, entity_before, after_ent
-                    ),
-                    Some(pos),
-                ));
-            }
-            if let BeltCollidable::Belt(belt) = entity_before {
-                let before_in = before.belt_curved_input_direction(pos, belt.direction);
-                let after_in = after.belt_curved_input_direction(pos, belt.direction);
-                if before_in != after_in {
-                    return Err(FuzzError(
-                        format!(
-                            "Belt direction changed at position {:?} {:?}\nBefore in: {:?}\nAfter in {:?}",
-                            pos, belt, before_in, after_in
-                        ),
-                        Some(pos),
-                    ));
-                }
+/// How much of a 2D drag that didn't fail was checked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fuzz2dOutcome {
+    /// The spec properties held after every step.
+    Checked,
+    /// The drag stopped being a simple path (see `PathTracker::tiles`); only
+    /// the steps before that were checked.
+    Unchecked,
+}
+
+/// A 2D drag that violated a spec property (or panicked), with the cursor
+/// trace up to and including the failing step.
+#[derive(Debug)]
+pub struct Fuzz2dFailure {
+    pub test_case: Fuzz2dTestCase,
+    pub steps: Vec<DragStep>,
+    /// None if the drag panicked.
+    pub world_after: Option<WorldImpl>,
+    pub errors: HashSet<(TilePosition, Error)>,
+    pub violation: PropertyViolation,
+}
+
+/// Fill an NxN world with random entities anywhere.
+pub fn generate_random_world_2d<R: Rng>(rng: &mut R, config: &Fuzz2dConfig) -> WorldImpl {
+    let mut world = WorldImpl::with_capacity((config.world_size * config.world_size) as usize);
+    for y in 0..config.world_size {
+        for x in 0..config.world_size {
+            if rng.gen_bool(config.entity_density as f64) {
+                let _ = world.try_build(pos(x, y), generate_random_entity(rng));
             }
         }
     }
+    world
+}
 
-    Ok(())
+pub fn generate_test_case_2d<R: Rng>(
+    rng: &mut R,
+    seed: u64,
+    config: &Fuzz2dConfig,
+) -> Fuzz2dTestCase {
+    let mut world = generate_random_world_2d(rng, config);
+    let start_pos = pos(
+        rng.gen_range(0..config.world_size),
+        rng.gen_range(0..config.world_size),
+    );
+    // Like the straight fuzzer, start on an empty tile
+    world.entities.remove(&start_pos);
+    Fuzz2dTestCase {
+        world,
+        start_pos,
+        belt_direction: random_direction(rng),
+        tier: random_tier(rng),
+        seed,
+    }
 }
 
-impl FuzzResult {
-    /// Main invariant checking function
-    pub fn check(&self) -> Result<(), FuzzError> {
-        let belt_line = scan_belt_line(&self.world_after);
-        // Find the last successfully placed belt position
-        let last_placed_pos = if let Some(&last) = belt_line.last() {
-            last
+/// Generate and run a random 2D drag, checking the spec properties after every step.
+pub fn run_fuzz_2d_test(
+    seed: u64,
+    config: &Fuzz2dConfig,
+) -> Result<Fuzz2dOutcome, Box<Fuzz2dFailure>> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let test_case = generate_test_case_2d(&mut rng, seed, config);
+    let mut generator = CursorTraceGenerator::new(&mut rng, config, &test_case);
+    run_drag_checked(&test_case, &mut |drag| generator.next_step(drag))
+}
+
+/// Run a drag with a fixed cursor trace, checking the spec properties after every step.
+pub fn replay_drag_2d(
+    test_case: &Fuzz2dTestCase,
+    steps: &[DragStep],
+) -> Result<Fuzz2dOutcome, Box<Fuzz2dFailure>> {
+    let mut steps = steps.iter().copied();
+    run_drag_checked(test_case, &mut |_| steps.next())
+}
+
+/// Random cursor movement. Each straight segment moves along an axis from an origin:
+/// the start position for the first segment (in both directions),
+/// or the rotation pivot afterwards (only away from the pivot, so the drag stays a simple path).
+struct CursorTraceGenerator<'r> {
+    rng: &'r mut StdRng,
+    queue: VecDeque<DragStep>,
+    origin: TilePosition,
+    axis: TileVec,
+    min_offset: i32,
+    max_offset: i32,
+    offset: i32,
+    moves_left: usize,
+    rotations_left: usize,
+    moves_per_segment: usize,
+    span: i32,
+}
+
+impl<'r> CursorTraceGenerator<'r> {
+    fn new(rng: &'r mut StdRng, config: &Fuzz2dConfig, test_case: &Fuzz2dTestCase) -> Self {
+        let rotations_left = rng.gen_range(0..=config.max_rotations);
+        Self {
+            rng,
+            queue: VecDeque::new(),
+            origin: test_case.start_pos,
+            axis: test_case.belt_direction.to_vector(),
+            min_offset: -config.world_size,
+            max_offset: config.world_size,
+            offset: 0,
+            moves_left: config.moves_per_segment,
+            rotations_left,
+            moves_per_segment: config.moves_per_segment,
+            span: config.world_size,
+        }
+    }
+
+    fn next_step(&mut self, drag: &LineDrag) -> Option<DragStep> {
+        if self.queue.is_empty() && self.moves_left > 0 {
+            self.moves_left -= 1;
+            self.queue_moves();
+        }
+        if let Some(step) = self.queue.pop_front() {
+            return Some(step);
+        }
+        if self.rotations_left > 0 {
+            self.rotations_left -= 1;
+            return Some(self.rotate(drag));
+        }
+        None
+    }
+
+    fn rotate(&mut self, drag: &LineDrag) -> DragStep {
+        let (pivot, _) = drag.get_rotation_pivot();
+        let old_direction = drag.belt_direction();
+        let turn_direction = if self.rng.gen_bool(0.5) {
+            old_direction.rotate_cw()
         } else {
-            // No belts placed - this is fine if there was an error
-            if self.errors.is_empty() {
-                return Err(FuzzError(
-                    "No belts placed and no errors reported".to_string(),
-                    None,
-                ));
-            }
-            return Ok(());
+            old_direction.rotate_ccw()
         };
+        self.origin = pivot;
+        self.axis = turn_direction.to_vector();
+        self.min_offset = 0;
+        self.max_offset = self.span;
+        self.offset = self.rng.gen_range(1..=self.span);
+        self.moves_left = self.moves_per_segment;
+        DragStep::Rotate(self.cursor_at(self.offset))
+    }
 
-        let actual_end = last_placed_pos.x;
-        let expected_end = self.furthest_placement;
-
-        // Invariant 1: If there are no errors, there should be a continuous belt line from beginning to end
-        if self.errors.is_empty() && actual_end != expected_end {
-            return Err(FuzzError(
-                format!(
-                    "No errors but belt line ends at x={} instead of x={}",
-                    actual_end, expected_end
-                ),
-                Some(last_placed_pos),
-            ));
+    fn queue_moves(&mut self) {
+        let from = self.offset;
+        let target = self.rng.gen_range(self.min_offset..=self.max_offset);
+        let sign = (target - from).signum();
+        let offsets = match self.rng.gen_range(0..4) {
+            // Jump
+            0 => vec![target],
+            // Wiggle: 2 forward, 1 back
+            1 => {
+                let mut offsets = vec![];
+                let mut current = from;
+                while (target - current).abs() > 2 {
+                    offsets.push(current + 2 * sign);
+                    current += sign;
+                    offsets.push(current);
+                }
+                offsets.push(target);
+                offsets
+            }
+            // Mega wiggle: increasingly far, back to where we were each time
+            2 => (1..(target - from).abs())
+                .flat_map(|n| [from + n * sign, from])
+                .chain([target])
+                .collect(),
+            // Back and forth
+            _ => vec![target, from, target],
+        };
+        self.offset = target;
+        for offset in offsets {
+            let offset = offset.clamp(self.min_offset, self.max_offset);
+            self.queue
+                .push_back(DragStep::MoveTo(self.cursor_at(offset)));
         }
+    }
 
-        // Invariant 2: If the belt line is broken before the end, there must be at least one error
-        if actual_end < expected_end && self.errors.is_empty() {
-            return Err(FuzzError(
-                format!(
-                    "Belt line broken at x={} but no errors reported",
-                    actual_end,
-                ),
-                Some(last_placed_pos),
-            ));
-        }
+    fn cursor_at(&self, offset: i32) -> TilePosition {
+        self.origin + self.axis * offset
+    }
+}
+
+/// The flow-ordered path of a drag across rotations.
+/// `done` holds the path before the current straight segment; the current
+/// segment is added before or after it, depending on if we rotated backwards.
+/// The segment covers everything the drag has reached since the last rotation,
+/// as integrated entities stay integrated when dragging back.
+#[derive(Default)]
+struct PathTracker {
+    done: Vec<TilePosition>,
+    prepend: bool,
+    segment_extent: Option<(TilePosition, TilePosition)>,
+}
 
-        if self.errors.is_empty() {
-            // Invariant 3: All belts from the first successfully placed belt to the next must be the placement tier
-            check_belt_line_tier(&self.world_after, &belt_line, self.tier)?;
-            // Invariant 4: Non-integrated belts should remain unchanged
-            let integrated_positions: HashSet<TilePosition> = belt_line.iter().copied().collect();
-            check_non_integrated_belts_unchanged(
-                &self.world_before,
-                &self.world_after,
-                &integrated_positions,
-            )?;
+impl PathTracker {
+    /// Also covers up to `cursor` if given. Once there are errors, the drag may
+    /// have integrated entities past its placements before the line broke.
+    ///
+    /// None if the current segment doesn't join the previous ones at the pivot
+    /// (e.g. dragged back past it), so the drag is no longer a simple path.
+    fn tiles(
+        &mut self,
+        drag: &LineDrag,
+        cursor: Option<TilePosition>,
+    ) -> Option<Vec<TilePosition>> {
+        let (line_behind, line_ahead) = drag.line_extent();
+        let ray = Ray::new(line_behind, drag.belt_direction());
+        let (behind, ahead) = self.segment_extent.get_or_insert((line_behind, line_ahead));
+        let is_before = |a, b| ray.is_before(ray.ray_position(a), ray.ray_position(b));
+        for p in [line_behind, line_ahead]
+            .into_iter()
+            .chain(cursor.map(|c| ray.snap(c)))
+        {
+            if is_before(p, *behind) {
+                *behind = p;
+            }
+            if is_before(*ahead, p) {
+                *ahead = p;
+            }
         }
 
-        Ok(())
+        let segment = DragPath::from_corners(&[*behind, *ahead], drag.tier()).tiles;
+        if self.done.is_empty() {
+            return Some(segment);
+        }
+        if self.prepend {
+            (segment.last() == self.done.first())
+                .then(|| [&segment[..segment.len() - 1], &self.done].concat())
+        } else {
+            (segment.first() == self.done.last()).then(|| [&self.done, &segment[1..]].concat())
+        }
     }
+}
 
-    pub fn print_before_after(&self, markers: &[TilePosition]) {
-        let bounds = self.world_before.bounds().union(&self.world_after.bounds());
-        eprintln!(
-            r#"
-    Before:
+fn run_drag_checked(
+    test_case: &Fuzz2dTestCase,
+    next_step: &mut dyn FnMut(&LineDrag) -> Option<DragStep>,
+) -> Result<Fuzz2dOutcome, Box<Fuzz2dFailure>> {
+    let before = &test_case.world;
+    let mut world = before.clone();
+    let mut steps = Vec::new();
+    let mut errors = Vec::new();
 
-{}
+    let outcome = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        // False if the drag is no longer a simple path, and can't be checked
+        let check = |drag: &LineDrag,
+                     path: &mut PathTracker,
+                     cursor: TilePosition,
+                     errors: &[(TilePosition, Error)]| {
+            let cursor = (!errors.is_empty()).then_some(cursor);
+            let Some(tiles) = path.tiles(drag, cursor) else {
+                return Ok(false);
+            };
+            let drag_path = DragPath {
+                tiles,
+                tier: test_case.tier,
+            };
+            let errors = errors.iter().cloned().collect();
+            check_drag_properties(before, drag.world(), &drag_path, &errors).map(|_| true)
+        };
 
-    After:
+        let mut drag = LineDrag::start_drag(
+            &mut world,
+            &mut |p, e| errors.push((p, e)),
+            test_case.tier,
+            test_case.start_pos,
+            test_case.belt_direction,
+        );
+        let mut path = PathTracker::default();
+        let mut cursor = test_case.start_pos;
+        if !check(&drag, &mut path, cursor, &errors)? {
+            return Ok(Fuzz2dOutcome::Unchecked);
+        }
+        while let Some(step) = next_step(&drag) {
+            steps.push(step);
+            match step {
+                DragStep::MoveTo(target) => {
+                    cursor = target;
+                    drag.interpolate_to(&mut |p, e| errors.push((p, e)), target);
+                }
+                DragStep::Rotate(rotate_cursor) => {
+                    let Some(done) = path.tiles(&drag, None) else {
+                        return Ok(Fuzz2dOutcome::Unchecked);
+                    };
+                    let (_, backward) = drag.get_rotation_pivot();
+                    let (new_drag, rotated) =
+                        drag.rotate(&mut |p, e| errors.push((p, e)), rotate_cursor);
+                    drag = new_drag;
+                    cursor = rotate_cursor;
+                    if rotated {
+                        path = PathTracker {
+                            done,
+                            prepend: backward,
+                            segment_extent: None,
+                        };
+                    }
+                }
+            }
+            if !check(&drag, &mut path, cursor, &errors)? {
+                return Ok(Fuzz2dOutcome::Unchecked);
+            }
+        }
+        Ok(Fuzz2dOutcome::Checked)
+    }));
+
+    let (violation, world_after) = match outcome {
+        Ok(Ok(outcome)) => return Ok(outcome),
+        Ok(Err(violation)) => (violation, Some(world)),
+        Err(panic_info) => (
+            PropertyViolation(
+                format!("Drag panicked: {}", panic_message(panic_info.as_ref())),
+                None,
+            ),
+            None,
+        ),
+    };
+    Err(Box::new(Fuzz2dFailure {
+        test_case: test_case.clone(),
+        steps,
+        world_after,
+        errors: errors.into_iter().collect(),
+        violation,
+    }))
+}
 
-{}
-    "#,
-            print_world(&self.world_before, bounds, markers),
-            print_world(&self.world_after, bounds, markers)
+impl Fuzz2dFailure {
+    pub fn print(&self) {
+        let Fuzz2dTestCase {
+            world,
+            start_pos,
+            belt_direction,
+            tier,
+            seed,
+        } = &self.test_case;
+        let step_positions = self.steps.iter().map(|step| match *step {
+            DragStep::MoveTo(p) | DragStep::Rotate(p) => p,
+        });
+        let mut bounds = world.bounds();
+        for p in step_positions.chain([*start_pos]) {
+            bounds = bounds.union(&BoundingBox::new(p, p + euclid::vec2(1, 1)));
+        }
+        if let Some(after) = &self.world_after {
+            bounds = bounds.union(&after.bounds());
+        }
+        let markers: Vec<TilePosition> = self.violation.1.into_iter().collect();
+        eprintln!(
+            "❌ 2D test failed (seed: {}): {}\nStart: {:?} {:?} {:?}\nSteps: {:?}\nErrors: {:?}\n\n    Before:\n\n{}\n\n    After:\n\n{}\n",
+            seed,
+            self.violation,
+            start_pos,
+            belt_direction,
+            tier,
+            self.steps,
+            self.errors,
+            print_world(world, bounds, &[*start_pos]),
+            self.world_after
+                .as_ref()
+                .map(|after| print_world(after, bounds, &markers))
+                .unwrap_or_else(|| "<panicked>".to_string()),
         );
     }
 }
@@ -3,6 +3,7 @@ use std::cmp::Ordering;
 use euclid::{
     vec2, {Box2D, Point2D, Vector2D},
 };
+use serde::{Deserialize, Serialize};
 
 pub struct TileSpace;
 pub type TilePosition = Point2D<i32, TileSpace>;
@@ -15,7 +16,8 @@ pub fn pos(x: i32, y: i32) -> TilePosition {
 
 /// South is +y
 /// East is +x
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 #[repr(u8)]
 pub enum Direction {
     North = 0,
@@ -84,6 +86,17 @@ impl Direction {
         }
     }
 
+    /// Inverse of `to_vector`; None for anything other than a unit step.
+    pub const fn from_vector(vector: TileVec) -> Option<Direction> {
+        match (vector.x, vector.y) {
+            (0, -1) => Some(Direction::North),
+            (1, 0) => Some(Direction::East),
+            (0, 1) => Some(Direction::South),
+            (-1, 0) => Some(Direction::West),
+            _ => None,
+        }
+    }
+
     /// Sign of the direction vector's nonzero component on its axis.
     pub const fn axis_sign(self) -> i32 {
         match self {
@@ -281,6 +294,21 @@ mod tests {
         assert_eq!(Direction::from_ordinal(4), None);
     }
 
+    #[test]
+    fn test_direction_from_vector() {
+        for dir in [
+            Direction::North,
+            Direction::East,
+            Direction::South,
+            Direction::West,
+        ] {
+            assert_eq!(Direction::from_vector(dir.to_vector()), Some(dir));
+        }
+        assert_eq!(Direction::from_vector(vec2(0, 0)), None);
+        assert_eq!(Direction::from_vector(vec2(2, 0)), None);
+        assert_eq!(Direction::from_vector(vec2(1, 1)), None);
+    }
+
     #[test]
     fn test_ray_position() {
         let ray_north = Ray::new(pos(0, 0), Direction::North);
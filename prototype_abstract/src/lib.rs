@@ -1,9 +1,19 @@
 pub mod belts;
+pub mod bless;
+pub mod blueprint;
+pub mod coverage;
 pub mod entity;
 pub mod fuzzer;
 pub mod geometry;
+pub mod lint;
+pub mod model_check;
+pub mod shrinker;
 pub mod smart_belt;
+pub mod spec_properties;
+pub mod suite_test_names;
 pub mod test_case;
+pub mod test_entity;
+pub mod trace;
 pub mod world;
 
 pub use belts::*;
@@ -0,0 +1,406 @@
+//! Lints for the YAML test suite: mistakes that otherwise only show up as a
+//! single failing generated test, or not at all.
+//!
+//! - Cases that don't parse (e.g. `*` markers not matching `expected_errors`)
+//! - Cases that generate a test fn with the same name as another case's
+//!   (see [`sanitized_case_name`])
+//! - Drag cases that are the same drag as an earlier one, under one of
+//!   [`Transform::all_unique_transforms`] or flipped (as the reverse variants
+//!   run them), unless marked with `equivalent_to: <earlier case name>`
+//! - `equivalent_to` naming a case that isn't the same drag
+//! - Error markers off the drag row
+//! - `after_for_reverse` on a `not_reversible` case, where it's never used
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use euclid::vec2;
+use itertools::Itertools;
+use serde_yaml::Value;
+
+use crate::geometry::Ray;
+use crate::suite_test_names::{case_test_fns, sanitized_case_name, test_fn_name};
+use crate::test_case::{
+    DragTestCase, SuiteCase, TestCaseEntities, flip_test_case_unchecked, print_world,
+    transform_test_case,
+};
+use crate::{BoundingBox, TilePosition, Transform};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LintKind {
+    Malformed(String),
+    NameCollision {
+        test_fn: String,
+        other_case: String,
+    },
+    /// The same drag as `other_case`, under `[transform i]`, possibly flipped.
+    Equivalent {
+        other_file: String,
+        other_case: String,
+        transform_index: usize,
+        flipped: bool,
+    },
+    /// `equivalent_to` names a case that isn't the same (earlier) drag.
+    NotEquivalent(String),
+    MarkerOffDragRow(TilePosition),
+    UnusedAfterForReverse,
+}
+
+impl LintKind {
+    /// Errors break or hide generated tests; the rest are likely, but not
+    /// certainly, mistakes.
+    pub fn is_error(&self) -> bool {
+        matches!(
+            self,
+            LintKind::Malformed(_) | LintKind::NameCollision { .. }
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LintIssue {
+    pub file: String,
+    pub case: String,
+    pub kind: LintKind,
+}
+
+impl fmt::Display for LintIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = if self.kind.is_error() {
+            "error"
+        } else {
+            "warning"
+        };
+        write!(f, "{severity}: {}: {}: ", self.file, self.case)?;
+        match &self.kind {
+            LintKind::Malformed(error) => write!(f, "doesn't parse: {error}"),
+            LintKind::NameCollision {
+                test_fn,
+                other_case,
+            } => write!(f, "generates `{test_fn}`, as does '{other_case}'"),
+            LintKind::Equivalent {
+                other_file,
+                other_case,
+                transform_index,
+                flipped,
+            } => {
+                write!(f, "same drag as {other_file}: '{other_case}'")?;
+                if *transform_index != 0 {
+                    write!(f, " [transform {transform_index}]")?;
+                }
+                if *flipped {
+                    write!(f, " [flip]")?;
+                }
+                Ok(())
+            }
+            LintKind::NotEquivalent(other_case) => {
+                write!(
+                    f,
+                    "is 'equivalent_to' '{other_case}', but isn't the same drag"
+                )
+            }
+            LintKind::MarkerOffDragRow(p) => {
+                write!(f, "error marker at ({}, {}) is off the drag row", p.x, p.y)
+            }
+            LintKind::UnusedAfterForReverse => {
+                write!(f, "has 'after_for_reverse', but is 'not_reversible'")
+            }
+        }
+    }
+}
+
+/// Lints every `*.yaml` file in `dir`, in name order. Equivalent cases are
+/// also found across files.
+pub fn lint_suite(dir: &Path) -> Result<Vec<LintIssue>> {
+    let mut files = Vec::new();
+    for path in fs::read_dir(dir)?
+        .map(|entry| entry.map(|e| e.path()))
+        .filter_ok(|path| path.extension().is_some_and(|e| e == "yaml"))
+        .collect::<Result<Vec<_>, _>>()?
+        .into_iter()
+        .sorted()
+    {
+        let text = fs::read_to_string(&path)?;
+        let values: Vec<Value> =
+            serde_yaml::from_str(&text).with_context(|| format!("In {path:?}"))?;
+        let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+        files.push((file_name.to_string(), values));
+    }
+    Ok(lint_files(&files))
+}
+
+/// Lints `(file name, cases)` pairs.
+pub fn lint_files(files: &[(String, Vec<Value>)]) -> Vec<LintIssue> {
+    let mut issues = Vec::new();
+    let mut drag_cases = Vec::new();
+    for (file, values) in files {
+        issues.extend(name_collisions(file, values));
+        for value in values {
+            let name = case_name(value);
+            let issue = |kind| LintIssue {
+                file: file.clone(),
+                case: name.clone(),
+                kind,
+            };
+            match SuiteCase::from_value(value) {
+                Err(e) => issues.push(issue(LintKind::Malformed(format!("{e:#}")))),
+                Ok(SuiteCase::Drag(case)) => {
+                    if case.not_reversible && case.after_for_reverse.is_some() {
+                        issues.push(issue(LintKind::UnusedAfterForReverse));
+                    }
+                    issues.extend(
+                        markers_off_drag_row(&case.entities)
+                            .map(|p| issue(LintKind::MarkerOffDragRow(p))),
+                    );
+                    drag_cases.push((file.clone(), case));
+                }
+                Ok(SuiteCase::Steps(_) | SuiteCase::Scenario(_)) => {}
+            }
+        }
+    }
+    issues.extend(equivalent_cases(&drag_cases));
+    issues
+}
+
+fn case_name(value: &Value) -> String {
+    value
+        .get("name")
+        .and_then(Value::as_str)
+        .unwrap_or("Unnamed")
+        .to_string()
+}
+
+/// Generated test fns are per file (one `mod` each), so collisions are too.
+fn name_collisions(file: &str, values: &[Value]) -> Vec<LintIssue> {
+    let mut seen: HashMap<String, String> = HashMap::new();
+    let mut issues = Vec::new();
+    for (i, value) in values.iter().enumerate() {
+        let name = case_name(value);
+        let sanitized = sanitized_case_name(value, i);
+        for (_, suffix) in case_test_fns(value) {
+            let test_fn = test_fn_name(&sanitized, suffix);
+            match seen.get(&test_fn) {
+                Some(other_case) => issues.push(LintIssue {
+                    file: file.to_string(),
+                    case: name.clone(),
+                    kind: LintKind::NameCollision {
+                        test_fn,
+                        other_case: other_case.clone(),
+                    },
+                }),
+                None => {
+                    seen.insert(test_fn, name.clone());
+                }
+            }
+        }
+    }
+    issues
+}
+
+fn markers_off_drag_row(test: &TestCaseEntities) -> impl Iterator<Item = TilePosition> {
+    let ray = Ray::new(test.start_pos, test.belt_direction);
+    test.expected_errors
+        .iter()
+        .map(|(p, _)| *p)
+        .filter(move |p| ray.snap(*p) != *p)
+        .sorted_by_key(|p| (p.y, p.x))
+}
+
+/// Each drag case that is the same drag as an earlier one, other than the one
+/// it's marked `equivalent_to`.
+fn equivalent_cases(cases: &[(String, DragTestCase)]) -> Vec<LintIssue> {
+    // The first case (and how it was transformed) each drag key came from
+    let mut first_with_key: HashMap<String, (usize, usize, bool)> = HashMap::new();
+    for (i, (_, case)) in cases.iter().enumerate() {
+        for (transform_index, flipped, key) in drag_keys(case) {
+            first_with_key
+                .entry(key)
+                .or_insert((i, transform_index, flipped));
+        }
+    }
+
+    let mut issues = Vec::new();
+    for (i, (file, case)) in cases.iter().enumerate() {
+        let key = drag_key(&case.entities, case.forward_back);
+        let equivalent = first_with_key
+            .get(&key)
+            .filter(|(other, ..)| *other < i)
+            .map(|&(other, transform_index, flipped)| (&cases[other], transform_index, flipped));
+        let kind = match (equivalent, &case.equivalent_to) {
+            (Some(((_, other_case), ..)), Some(name)) if other_case.name == *name => continue,
+            (None, None) => continue,
+            (None, Some(name)) => LintKind::NotEquivalent(name.clone()),
+            (Some(((other_file, other_case), transform_index, flipped)), _) => {
+                LintKind::Equivalent {
+                    other_file: other_file.clone(),
+                    other_case: other_case.name.clone(),
+                    transform_index,
+                    flipped,
+                }
+            }
+        };
+        issues.push(LintIssue {
+            file: file.clone(),
+            case: case.name.clone(),
+            kind,
+        });
+    }
+    issues
+}
+
+/// Keys of the drags the suite runs for a case: under every transform, and
+/// flipped if the case is reversible.
+fn drag_keys(case: &DragTestCase) -> Vec<(usize, bool, String)> {
+    let flipped = (!case.not_reversible)
+        .then(|| flip_test_case_unchecked(&case.entities, case.after_for_reverse.as_ref()));
+    let mut keys = Vec::new();
+    for (transform_index, transform) in Transform::all_unique_transforms().iter().enumerate() {
+        for (is_flipped, entities) in [(false, Some(&case.entities)), (true, flipped.as_ref())] {
+            if let Some(entities) = entities {
+                let transformed = transform_test_case(entities, transform);
+                keys.push((
+                    transform_index,
+                    is_flipped,
+                    drag_key(&transformed, case.forward_back),
+                ));
+            }
+        }
+    }
+    keys
+}
+
+/// Everything that determines a drag's test, relative to its bounds; so
+/// equal keys are the same test, wherever in the grid it is.
+fn drag_key(test: &TestCaseEntities, forward_back: bool) -> String {
+    let mut points = vec![test.start_pos, test.end_pos];
+    if forward_back {
+        points.push(test.leftmost_pos);
+    }
+    let drag_bounds = BoundingBox::from_points(&points);
+    let drag_bounds = BoundingBox::new(drag_bounds.min, drag_bounds.max + vec2(1, 1));
+    let bounds = test
+        .before
+        .bounds()
+        .union(&test.after.bounds())
+        .union(&drag_bounds);
+    let relative = |p: TilePosition| p - bounds.min;
+    let errors = test
+        .expected_errors
+        .iter()
+        .map(|(p, e)| (relative(*p), e))
+        .sorted_by_key(|(p, e)| (p.y, p.x, (*e).clone()))
+        .collect_vec();
+    format!(
+        "{}\n--\n{}\n--\n{:?} {:?} {:?} {} {:?}\n{:?}",
+        print_world(&test.before, bounds, &[]),
+        print_world(&test.after, bounds, &[]),
+        relative(test.start_pos),
+        relative(test.end_pos),
+        test.belt_direction,
+        test.tier.tier_index(),
+        forward_back.then(|| relative(test.leftmost_pos)),
+        errors,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lint_text(text: &str) -> Vec<LintIssue> {
+        lint_files(&[("test.yaml".to_string(), serde_yaml::from_str(text).unwrap())])
+    }
+
+    #[test]
+    fn test_finds_equivalent_cases() {
+        let issues = lint_text(
+            r#"
+- name: East
+  before: _ X _
+  after: ">i X >o"
+- name: West
+  before: _ X _
+  after: "<o X <i"
+  start: [2, 0]
+  end: [0, 0]
+- name: Flipped
+  before: _ X _
+  after: "<o X <i"
+  start: [0, 0]
+  direction: west
+- name: Longer
+  before: _ X _ _
+  after: ">i X >o >"
+- name: Mirrored
+  before: _ X _
+  after: "<o X <i"
+  start: [2, 0]
+  end: [0, 0]
+  equivalent_to: East
+- name: Not mirrored
+  before: _ X _ _
+  after: "<o X <i <"
+  start: [3, 0]
+  end: [0, 0]
+  equivalent_to: Longer
+"#,
+        );
+        let equivalent = issues
+            .iter()
+            .map(|i| match &i.kind {
+                LintKind::Equivalent {
+                    other_case,
+                    flipped,
+                    ..
+                } => (i.case.as_str(), other_case.as_str(), Some(*flipped)),
+                LintKind::NotEquivalent(other_case) => (i.case.as_str(), other_case.as_str(), None),
+                kind => panic!("unexpected {kind:?}"),
+            })
+            .collect_vec();
+        assert_eq!(
+            equivalent,
+            [
+                ("West", "East", Some(false)),
+                ("Flipped", "East", Some(true)),
+                ("Not mirrored", "Longer", None),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_finds_malformed_and_colliding_cases() {
+        let issues = lint_text(
+            r#"
+- name: Missing error
+  before: X
+  after: "*X > >"
+- name: Obstacle!
+  before: _ X _
+  after: ">i X >o"
+  not_reversible: true
+  after_for_reverse: "<o X <i"
+- name: Obstacle?
+  before: _ _ X _
+  after: "> >i X >o"
+- name: Obstacle wiggle
+  before: _ _ _ X _
+  after: "> > >i X >o"
+"#,
+        );
+        let messages = issues.iter().map(|i| i.to_string()).collect_vec();
+        assert_eq!(
+            messages,
+            [
+                "error: test.yaml: Obstacle?: generates `test_obstacle_normal`, as does 'Obstacle!'",
+                "error: test.yaml: Obstacle?: generates `test_obstacle_wiggle`, as does 'Obstacle!'",
+                "error: test.yaml: Obstacle?: generates `test_obstacle_mega_wiggle`, as does 'Obstacle!'",
+                "error: test.yaml: Obstacle wiggle: generates `test_obstacle_wiggle_reverse`, as does 'Obstacle?'",
+                "error: test.yaml: Missing error: doesn't parse: Expected number of markers to match number of expected errors",
+                "warning: test.yaml: Obstacle!: has 'after_for_reverse', but is 'not_reversible'",
+            ]
+        );
+    }
+}
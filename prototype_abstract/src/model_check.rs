@@ -0,0 +1,342 @@
+//! Bounded exhaustive model checking: runs a drag over every world up to a
+//! given length, and checks the spec properties on each.
+//!
+//! Worlds use the same layout as the straight fuzzer: the drag goes east along
+//! row 1 from `(0, 1)`, over entities at `x = 1..=length`, with optional side
+//! rows above and below. Worlds that are a symmetry of an already checked world
+//! (via [`Transform::all_unique_transforms`]) are skipped.
+
+use rayon::prelude::*;
+
+use crate::belts::{BELT_TIERS, BeltTier};
+use crate::fuzzer::FuzzTestCase;
+use crate::shrinker::{fuzz_case_fails, to_yaml_test_case};
+use crate::{
+    Belt, BeltCollidable, CollidingEntityOrTile, Direction, ImpassableTile, LoaderLike, Splitter,
+    TilePosition, Transform, UndergroundBelt, WorldImpl, pos,
+};
+
+const DRAG_DIRECTION: Direction = Direction::East;
+const DRAG_ROW: i32 = 1;
+
+#[derive(Debug, Clone)]
+pub struct ModelCheckConfig {
+    /// Number of tiles on the drag row (not counting the start tile).
+    pub length: i32,
+    /// Also enumerate belts on the rows above and below the drag row.
+    pub side_rows: bool,
+    /// Tiers used for entities and for the drag itself.
+    pub tiers: Vec<BeltTier>,
+    /// Stop collecting counter-examples after this many (they are still counted).
+    pub max_counter_examples: usize,
+}
+
+impl ModelCheckConfig {
+    pub fn new(length: i32) -> Self {
+        Self {
+            length,
+            side_rows: false,
+            tiers: BELT_TIERS[..2].to_vec(),
+            max_counter_examples: 10,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ModelCheckReport {
+    pub config: ModelCheckConfig,
+    /// Number of worlds (including drag tiers) in the bound.
+    pub total_worlds: u64,
+    /// Number of worlds actually run, after skipping symmetric ones.
+    pub checked_worlds: u64,
+    pub num_counter_examples: u64,
+    pub counter_examples: Vec<FuzzTestCase>,
+}
+
+impl ModelCheckReport {
+    pub fn is_complete_and_correct(&self) -> bool {
+        self.num_counter_examples == 0
+    }
+
+    /// What this run shows, for the given bound only: other lengths need
+    /// their own run.
+    pub fn completeness_claim(&self) -> String {
+        let bound = format!(
+            "all {} worlds of exactly length {}{} over {} tiers ({} up to symmetry)",
+            self.total_worlds,
+            self.config.length,
+            if self.config.side_rows {
+                " with side rows"
+            } else {
+                ""
+            },
+            self.config.tiers.len(),
+            self.checked_worlds
+        );
+        if self.is_complete_and_correct() {
+            format!("The spec properties hold for {bound}. No other length was checked.")
+        } else {
+            format!(
+                "Found {} counter-examples in {bound}.",
+                self.num_counter_examples
+            )
+        }
+    }
+
+    /// The collected counter-examples as YAML test cases.
+    pub fn counter_examples_yaml(&self) -> String {
+        self.counter_examples
+            .iter()
+            .enumerate()
+            .map(|(i, test_case)| {
+                let name = format!("Model check counter-example {}", i + 1);
+                to_yaml_test_case(&name, test_case).unwrap_or_else(|e| {
+                    format!("# {name} panics:\n# {}\n", e.replace('\n', "\n# "))
+                })
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// The reduced alphabet for the drag row: empty, `X`, `#`, and belts,
+/// underground inputs/outputs, splitters and loaders in every direction and tier.
+fn drag_row_alphabet(tiers: &[BeltTier]) -> Vec<Option<BeltCollidable>> {
+    let mut alphabet = vec![
+        None,
+        Some(CollidingEntityOrTile.into()),
+        Some(ImpassableTile.into()),
+    ];
+    for &tier in tiers {
+        for direction in all_directions() {
+            alphabet.extend([
+                Some(Belt::new(direction, tier).into()),
+                Some(UndergroundBelt::new(direction, true, tier).into()),
+                Some(UndergroundBelt::new(direction, false, tier).into()),
+                Some(Splitter::new(direction, tier).into()),
+                Some(LoaderLike::new(direction, true, tier).into()),
+                Some(LoaderLike::new(direction, false, tier).into()),
+            ]);
+        }
+    }
+    alphabet
+}
+
+/// Side rows only matter for belt curvature, so they only hold belts.
+fn side_row_alphabet(tiers: &[BeltTier]) -> Vec<Option<BeltCollidable>> {
+    let mut alphabet = vec![None];
+    alphabet.extend(all_directions().map(|direction| Some(Belt::new(direction, tiers[0]).into())));
+    alphabet
+}
+
+fn all_directions() -> impl Iterator<Item = Direction> {
+    (0..4).filter_map(Direction::from_ordinal)
+}
+
+/// A world is a digit per cell, each an index into that cell's alphabet,
+/// followed by a digit for the drag tier.
+struct WorldSpace {
+    cells: Vec<TilePosition>,
+    alphabets: Vec<Vec<Option<BeltCollidable>>>,
+    tiers: Vec<BeltTier>,
+    /// For each symmetry that maps the drag onto itself (except the identity),
+    /// where each cell goes, and what each symbol becomes.
+    symmetries: Vec<(Vec<usize>, Vec<Vec<usize>>)>,
+}
+
+impl WorldSpace {
+    fn new(config: &ModelCheckConfig) -> Self {
+        let drag_alphabet = drag_row_alphabet(&config.tiers);
+        let side_alphabet = side_row_alphabet(&config.tiers);
+        let mut cells = Vec::new();
+        let mut alphabets = Vec::new();
+        for x in 1..=config.length {
+            cells.push(pos(x, DRAG_ROW));
+            alphabets.push(drag_alphabet.clone());
+            if config.side_rows {
+                for y in [DRAG_ROW - 1, DRAG_ROW + 1] {
+                    cells.push(pos(x, y));
+                    alphabets.push(side_alphabet.clone());
+                }
+            }
+        }
+
+        let mut space = Self {
+            cells,
+            alphabets,
+            tiers: config.tiers.clone(),
+            symmetries: Vec::new(),
+        };
+        space.symmetries = Transform::all_unique_transforms()
+            .into_iter()
+            .filter(|t| *t != Transform::identity())
+            .filter_map(|t| space.symmetry(&t))
+            .collect();
+        space
+    }
+
+    /// The cell and symbol maps for `transform`, if it maps every world in
+    /// this space to another world in it.
+    fn symmetry(&self, transform: &Transform) -> Option<(Vec<usize>, Vec<Vec<usize>>)> {
+        if transform.transform_direction(DRAG_DIRECTION) != DRAG_DIRECTION {
+            return None;
+        }
+        // Transforms are around the origin; move the drag start back in place
+        let start = pos(0, DRAG_ROW);
+        let offset = start - transform.transform_position(start);
+
+        let cell_map = self
+            .cells
+            .iter()
+            .map(|&cell| {
+                let target = transform.transform_position(cell) + offset;
+                self.cells.iter().position(|&c| c == target)
+            })
+            .collect::<Option<Vec<usize>>>()?;
+
+        let symbol_maps = self
+            .alphabets
+            .iter()
+            .enumerate()
+            .map(|(cell, alphabet)| {
+                let target_alphabet = &self.alphabets[cell_map[cell]];
+                alphabet
+                    .iter()
+                    .map(|symbol| {
+                        let transformed = symbol.as_ref().map(|e| transform.transform_entity(e));
+                        target_alphabet.iter().position(|s| *s == transformed)
+                    })
+                    .collect::<Option<Vec<usize>>>()
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        Some((cell_map, symbol_maps))
+    }
+
+    fn total(&self) -> u64 {
+        self.alphabets
+            .iter()
+            .map(|a| a.len() as u64)
+            .product::<u64>()
+            * self.tiers.len() as u64
+    }
+
+    fn digits(&self, mut index: u64) -> (Vec<usize>, usize) {
+        let digits = self
+            .alphabets
+            .iter()
+            .map(|alphabet| {
+                let digit = index % alphabet.len() as u64;
+                index /= alphabet.len() as u64;
+                digit as usize
+            })
+            .collect();
+        (digits, index as usize)
+    }
+
+    /// If no symmetry of this world sorts before it.
+    fn is_canonical(&self, digits: &[usize]) -> bool {
+        let mut transformed = vec![0; digits.len()];
+        self.symmetries.iter().all(|(cell_map, symbol_maps)| {
+            for (cell, &digit) in digits.iter().enumerate() {
+                transformed[cell_map[cell]] = symbol_maps[cell][digit];
+            }
+            // Digits are little-endian, so compare from the most significant
+            transformed.iter().rev().cmp(digits.iter().rev()) != std::cmp::Ordering::Less
+        })
+    }
+
+    fn test_case(&self, index: u64, digits: &[usize], tier: usize, length: i32) -> FuzzTestCase {
+        let mut world = WorldImpl::new();
+        for ((&cell, alphabet), &digit) in self.cells.iter().zip(&self.alphabets).zip(digits) {
+            if let Some(entity) = &alphabet[digit] {
+                world.build(cell, entity.clone());
+            }
+        }
+        FuzzTestCase {
+            world,
+            max_x: length,
+            tier: self.tiers[tier],
+            seed: index,
+        }
+    }
+}
+
+/// Runs a drag on every world in the bound given by `config`.
+pub fn model_check(config: &ModelCheckConfig) -> ModelCheckReport {
+    let space = WorldSpace::new(config);
+    let total_worlds = space.total();
+
+    let (checked_worlds, mut failures) = (0..total_worlds)
+        .into_par_iter()
+        .filter_map(|index| {
+            let (digits, tier) = space.digits(index);
+            space
+                .is_canonical(&digits)
+                .then(|| space.test_case(index, &digits, tier, config.length))
+        })
+        .map(|test_case| {
+            let failure = fuzz_case_fails(&test_case).then_some(test_case);
+            (1u64, failure.into_iter().collect::<Vec<_>>())
+        })
+        .reduce(
+            || (0, Vec::new()),
+            |(checked_a, mut failures_a), (checked_b, failures_b)| {
+                failures_a.extend(failures_b);
+                (checked_a + checked_b, failures_a)
+            },
+        );
+
+    failures.sort_by_key(|test_case| test_case.seed);
+    let num_counter_examples = failures.len() as u64;
+    failures.truncate(config.max_counter_examples);
+
+    ModelCheckReport {
+        config: config.clone(),
+        total_worlds,
+        checked_worlds,
+        num_counter_examples,
+        counter_examples: failures,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_alphabet_size() {
+        // empty, X, # and 6 kinds in 4 directions for 2 tiers
+        assert_eq!(drag_row_alphabet(&BELT_TIERS[..2]).len(), 3 + 6 * 4 * 2);
+    }
+
+    #[test]
+    fn test_only_mirror_symmetry_keeps_the_drag() {
+        let space = WorldSpace::new(&ModelCheckConfig {
+            side_rows: true,
+            ..ModelCheckConfig::new(2)
+        });
+        assert_eq!(space.symmetries.len(), 1);
+
+        // A belt going north above the row mirrors to one going south below it
+        let north_above = space.cells.iter().position(|&c| c == pos(1, 0)).unwrap();
+        let (cell_map, symbol_maps) = &space.symmetries[0];
+        assert_eq!(space.cells[cell_map[north_above]], pos(1, 2));
+        let north = 1 + Direction::North as usize;
+        let south = 1 + Direction::South as usize;
+        assert_eq!(symbol_maps[north_above][north], south);
+    }
+
+    #[test]
+    fn test_model_check_length_1() {
+        let report = model_check(&ModelCheckConfig::new(1));
+        assert_eq!(report.total_worlds, 51 * 2);
+        assert!(report.checked_worlds < report.total_worlds);
+        assert!(
+            report.is_complete_and_correct(),
+            "{}\n{}",
+            report.completeness_claim(),
+            report.counter_examples_yaml()
+        );
+    }
+}
@@ -0,0 +1,464 @@
+//! Minimises failing fuzzer test cases into something small enough to read,
+//! and prints them as YAML test cases for the test suite.
+//!
+//! Shrinking repeatedly tries, keeping any change that still fails:
+//! - removing whole columns, shortening the drag (straight drags),
+//! - removing single cursor steps (2D drags),
+//! - removing single entities,
+//! - replacing entities with simpler ones (belts, then `X`) and lowering tiers.
+
+use std::collections::HashSet;
+use std::panic::{AssertUnwindSafe, catch_unwind};
+
+use crate::belts::BELT_TIERS;
+use crate::fuzzer::{Fuzz2dTestCase, FuzzTestCase, panic_message, replay_drag_2d, run_fuzz_test};
+use crate::smart_belt::action::Error;
+use crate::test_case::{DragStep, align_columns, print_world, replay_drag_steps};
+use crate::{
+    Belt, BeltCollidable, BeltConnectable, BeltConnectableTrait, BeltTier, BoundingBox,
+    CollidingEntityOrTile, Direction, LoaderLike, Splitter, TilePosition, UndergroundBelt,
+    WorldImpl, pos,
+};
+
+/// If the drag panics or violates a spec property.
+pub fn fuzz_case_fails(test_case: &FuzzTestCase) -> bool {
+    !run_fuzz_test(test_case).is_ok_and(|result| result.check().is_ok())
+}
+
+/// Shrinks `test_case` while `still_fails` holds, until no single change makes it smaller.
+/// `still_fails` should hold for `test_case` itself.
+pub fn shrink_test_case(
+    test_case: &FuzzTestCase,
+    still_fails: impl Fn(&FuzzTestCase) -> bool,
+) -> FuzzTestCase {
+    let mut current = copy_test_case(test_case, test_case.world.clone());
+    loop {
+        let candidate = shrink_candidates(&current).find(|candidate| still_fails(candidate));
+        match candidate {
+            Some(candidate) => current = candidate,
+            None => return current,
+        }
+    }
+}
+
+/// All test cases one step simpler than `test_case`, roughly most-reducing first.
+fn shrink_candidates(test_case: &FuzzTestCase) -> impl Iterator<Item = FuzzTestCase> + '_ {
+    let removed_columns = (1..=test_case.max_x)
+        .rev()
+        .map(|x| remove_column(test_case, x));
+
+    let simpler_worlds =
+        simpler_worlds(&test_case.world).map(|world| copy_test_case(test_case, world));
+
+    let lowered_drag_tier = (test_case.tier != BELT_TIERS[0]).then(|| FuzzTestCase {
+        tier: BELT_TIERS[0],
+        ..copy_test_case(test_case, test_case.world.clone())
+    });
+
+    removed_columns
+        .chain(simpler_worlds)
+        .chain(lowered_drag_tier)
+}
+
+fn copy_test_case(test_case: &FuzzTestCase, world: WorldImpl) -> FuzzTestCase {
+    FuzzTestCase {
+        world,
+        max_x: test_case.max_x,
+        tier: test_case.tier,
+        seed: test_case.seed,
+    }
+}
+
+/// Removes column `x`, moving everything to the right of it one tile left.
+fn remove_column(test_case: &FuzzTestCase, x: i32) -> FuzzTestCase {
+    let mut world = WorldImpl::with_capacity(test_case.world.entities.len());
+    for (&p, entity) in &test_case.world.entities {
+        if p.x < x {
+            world.build(p, entity.clone());
+        } else if p.x > x {
+            world.build(pos(p.x - 1, p.y), entity.clone());
+        }
+    }
+    FuzzTestCase {
+        max_x: test_case.max_x - 1,
+        ..copy_test_case(test_case, world)
+    }
+}
+
+/// If the drag panics or violates a spec property at some step.
+pub fn fuzz_2d_case_fails(test_case: &Fuzz2dTestCase, steps: &[DragStep]) -> bool {
+    replay_drag_2d(test_case, steps).is_err()
+}
+
+/// Like [`shrink_test_case`], for a 2D drag and its cursor steps.
+pub fn shrink_2d_test_case(
+    test_case: &Fuzz2dTestCase,
+    steps: &[DragStep],
+    still_fails: impl Fn(&Fuzz2dTestCase, &[DragStep]) -> bool,
+) -> (Fuzz2dTestCase, Vec<DragStep>) {
+    let mut current = (test_case.clone(), steps.to_vec());
+    loop {
+        let candidate = shrink_2d_candidates(&current.0, &current.1)
+            .find(|(test_case, steps)| still_fails(test_case, steps));
+        match candidate {
+            Some(candidate) => current = candidate,
+            None => return current,
+        }
+    }
+}
+
+/// All 2D drags one step simpler than `test_case` with `steps`.
+fn shrink_2d_candidates<'a>(
+    test_case: &'a Fuzz2dTestCase,
+    steps: &'a [DragStep],
+) -> impl Iterator<Item = (Fuzz2dTestCase, Vec<DragStep>)> + 'a {
+    let removed_steps = (0..steps.len()).map(move |i| {
+        let mut steps = steps.to_vec();
+        steps.remove(i);
+        (test_case.clone(), steps)
+    });
+
+    let simpler_worlds = simpler_worlds(&test_case.world).map(move |world| {
+        let test_case = Fuzz2dTestCase {
+            world,
+            ..test_case.clone()
+        };
+        (test_case, steps.to_vec())
+    });
+
+    let lowered_drag_tier = (test_case.tier != BELT_TIERS[0]).then(|| {
+        let test_case = Fuzz2dTestCase {
+            tier: BELT_TIERS[0],
+            ..test_case.clone()
+        };
+        (test_case, steps.to_vec())
+    });
+
+    removed_steps.chain(simpler_worlds).chain(lowered_drag_tier)
+}
+
+/// `world` with one entity removed, or replaced with a simpler one.
+fn simpler_worlds(world: &WorldImpl) -> impl Iterator<Item = WorldImpl> + '_ {
+    let positions: Vec<TilePosition> = {
+        let mut positions: Vec<_> = world.entities.keys().copied().collect();
+        positions.sort_by_key(|p| (p.x, p.y));
+        positions
+    };
+
+    let removed_entities = positions.clone().into_iter().map(|p| {
+        let mut world = world.clone();
+        world.mine(p);
+        world
+    });
+
+    let simplified_entities = positions.into_iter().flat_map(move |p| {
+        let entity = world.get(p).unwrap();
+        simpler_entities(entity).into_iter().map(move |simpler| {
+            let mut world = world.clone();
+            world.build(p, simpler);
+            world
+        })
+    });
+
+    removed_entities.chain(simplified_entities)
+}
+
+/// Simpler replacements for an entity: a plain belt, the lowest tier, or an `X`.
+fn simpler_entities(entity: &BeltCollidable) -> Vec<BeltCollidable> {
+    let lowest_tier = BELT_TIERS[0];
+    let Ok(connectable) = BeltConnectable::try_from(entity) else {
+        return match entity {
+            BeltCollidable::ImpassableTile(_) => vec![CollidingEntityOrTile.into()],
+            _ => vec![],
+        };
+    };
+    let (direction, tier) = (connectable.direction(), connectable.tier());
+
+    let mut simpler = Vec::new();
+    if connectable.as_belt().is_none() {
+        simpler.push(Belt::new(direction, tier).into());
+    }
+    if tier != lowest_tier {
+        simpler.push(match connectable {
+            BeltConnectable::Belt(_) => Belt::new(direction, lowest_tier).into(),
+            BeltConnectable::UndergroundBelt(ug) => {
+                UndergroundBelt::new(direction, ug.is_input, lowest_tier).into()
+            }
+            BeltConnectable::Splitter(_) => Splitter::new(direction, lowest_tier).into(),
+            BeltConnectable::LoaderLike(loader) => {
+                LoaderLike::new(direction, loader.is_input, lowest_tier).into()
+            }
+        });
+    }
+    simpler.push(CollidingEntityOrTile.into());
+    simpler
+}
+
+/// A YAML test case for `test_suite/`, with the current output as `after`.
+///
+/// The start, end, direction and tier are all written out, so the case drags
+/// exactly as the fuzzer did rather than as inferred from the grids.
+pub fn to_yaml_test_case(name: &str, test_case: &FuzzTestCase) -> Result<String, String> {
+    let result = run_fuzz_test(test_case)?;
+    let before = &result.world_before;
+    let after = &result.world_after;
+    let start = test_case.start_pos();
+    let end = test_case.end_pos();
+
+    // Keep the origin, so positions in the YAML match the fuzzer's
+    let mut bounds = grid_bounds(before, after, [start, end]);
+    bounds.min = pos(0, 0);
+
+    let (markers, expected_errors) = expected_errors(&result.errors)?;
+    let mut yaml = format!("- name: {name}\n");
+    yaml.push_str(&yaml_block("before", &print_world(before, bounds, &[])));
+    yaml.push_str(&yaml_block("after", &print_world(after, bounds, &markers)));
+    yaml.push_str(&format!("  start: [{}, {}]\n", start.x, start.y));
+    yaml.push_str(&format!("  end: [{}, {}]\n", end.x, end.y));
+    yaml.push_str(&drag_keys(test_case.belt_direction(), test_case.tier)?);
+    yaml.push_str(&expected_errors);
+    Ok(yaml)
+}
+
+/// A YAML steps test case for `test_suite/`, with the current output as
+/// `after`. Positions are moved so that the grids start at `(0, 0)`.
+pub fn to_yaml_steps_test_case(
+    name: &str,
+    test_case: &Fuzz2dTestCase,
+    steps: &[DragStep],
+) -> Result<String, String> {
+    let before = &test_case.world;
+    let mut after = before.clone();
+    let (errors, rotated) = catch_unwind(AssertUnwindSafe(|| {
+        replay_drag_steps(
+            &mut after,
+            test_case.tier,
+            test_case.start_pos,
+            test_case.belt_direction,
+            steps,
+        )
+    }))
+    .map_err(|panic_info| format!("The drag panicked: {}", panic_message(panic_info.as_ref())))?;
+    if !rotated {
+        return Err(format!("A rotation failed in {steps:?}"));
+    }
+    let errors: HashSet<_> = errors.into_iter().collect();
+
+    let step_positions = steps.iter().map(|step| match *step {
+        DragStep::MoveTo(p) | DragStep::Rotate(p) => p,
+    });
+    let error_positions = errors.iter().map(|(p, _)| *p);
+    let bounds = grid_bounds(
+        before,
+        &after,
+        step_positions
+            .chain(error_positions)
+            .chain([test_case.start_pos]),
+    );
+    let origin = bounds.min.to_vector();
+    let coords = |p: TilePosition| format!("[{}, {}]", p.x - origin.x, p.y - origin.y);
+
+    let (markers, expected_errors) = expected_errors(&errors)?;
+    let mut yaml = format!("- name: {name}\n");
+    yaml.push_str(&yaml_block("before", &print_world(before, bounds, &[])));
+    yaml.push_str(&yaml_block("after", &print_world(&after, bounds, &markers)));
+    yaml.push_str(&format!("  start: {}\n", coords(test_case.start_pos)));
+    yaml.push_str(&drag_keys(test_case.belt_direction, test_case.tier)?);
+    yaml.push_str("  steps:\n");
+    for step in steps {
+        let (kind, p) = match *step {
+            DragStep::MoveTo(p) => ("move_to", p),
+            DragStep::Rotate(p) => ("rotate", p),
+        };
+        yaml.push_str(&format!("    - {kind}: {}\n", coords(p)));
+    }
+    yaml.push_str(&expected_errors);
+    Ok(yaml)
+}
+
+/// The bounds of both worlds, also covering `positions`.
+fn grid_bounds(
+    before: &WorldImpl,
+    after: &WorldImpl,
+    positions: impl IntoIterator<Item = TilePosition>,
+) -> BoundingBox {
+    positions
+        .into_iter()
+        .fold(before.bounds().union(&after.bounds()), |bounds, p| {
+            bounds.union(&BoundingBox::new(p, p + euclid::vec2(1, 1)))
+        })
+}
+
+/// The error markers for `after`, in reading order, and the matching
+/// `expected_errors` line (empty without errors).
+fn expected_errors(
+    errors: &HashSet<(TilePosition, Error)>,
+) -> Result<(Vec<TilePosition>, String), String> {
+    let mut errors: Vec<&(TilePosition, Error)> = errors.iter().collect();
+    errors.sort_by_key(|(p, e)| (p.y, p.x, format!("{e:?}")));
+    let markers = errors.iter().map(|(p, _)| *p).collect();
+    let error_names: Vec<String> = errors
+        .iter()
+        .map(|(_, e)| yaml_value(e))
+        .collect::<Result<_, _>>()?;
+    let line = if error_names.is_empty() {
+        String::new()
+    } else {
+        format!("  expected_errors: [{}]\n", error_names.join(", "))
+    };
+    Ok((markers, line))
+}
+
+fn drag_keys(direction: Direction, tier: BeltTier) -> Result<String, String> {
+    Ok(format!(
+        "  direction: {}\n  tier: {}\n",
+        yaml_value(&direction)?,
+        tier.tier_index() + 1
+    ))
+}
+
+fn yaml_value(value: &impl serde::Serialize) -> Result<String, String> {
+    serde_yaml::to_string(value)
+        .map(|s| s.trim().to_string())
+        .map_err(|e| e.to_string())
+}
+
+/// `grid` as a YAML block, with columns only as wide as their widest entity.
+fn yaml_block(key: &str, grid: &str) -> String {
+    let mut block = format!("  {key}: |\n");
+    for line in align_columns(grid).lines() {
+        block.push_str(&format!("    {line}\n"));
+    }
+    block
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::belts::RED_BELT;
+    use crate::test_case::{
+        DragTestCase, StepsTestCase, TestVariant, check_steps_test_case_all_transforms,
+        parse_world, run_test_case,
+    };
+
+    fn has_splitter(test_case: &FuzzTestCase) -> bool {
+        test_case
+            .world
+            .entities
+            .values()
+            .any(|e| matches!(e, BeltCollidable::Splitter(_)))
+    }
+
+    fn test_case(world: &str, tier: BeltTier) -> FuzzTestCase {
+        let (world, _) = parse_world(world).unwrap();
+        FuzzTestCase {
+            max_x: world.bounds().max.x - 1,
+            world,
+            tier,
+            seed: 0,
+        }
+    }
+
+    #[test]
+    fn test_shrink_keeps_only_what_fails() {
+        let original = test_case("_ 2v _ _ _ _\n_ X 3>i 2>s # 2<o\n_ _ _ _ 2^ _", RED_BELT);
+        assert!(has_splitter(&original));
+
+        let shrunk = shrink_test_case(&original, has_splitter);
+        let entities: Vec<_> = shrunk.world.entities.values().collect();
+        assert_eq!(
+            entities,
+            vec![&Splitter::new(Direction::East, BELT_TIERS[0]).into()]
+        );
+        assert_eq!(shrunk.max_x, 1);
+        assert_eq!(shrunk.tier, BELT_TIERS[0]);
+    }
+
+    #[test]
+    fn test_yaml_test_case_round_trips() {
+        let test_case = test_case("_ _ _ _\n_ > X 2v\n_ _ _ ^", RED_BELT);
+        let yaml = to_yaml_test_case("Round trip", &test_case).unwrap();
+
+        let mut cases: Vec<DragTestCase> = serde_yaml::from_str(&yaml).unwrap();
+        let case = cases.pop().unwrap();
+        let result = run_fuzz_test(&test_case).unwrap();
+        assert_eq!(case.name, "Round trip");
+        assert_eq!(case.entities.before, result.world_before);
+        assert_eq!(case.entities.after, result.world_after);
+        assert_eq!(case.entities.start_pos, test_case.start_pos());
+        assert_eq!(case.entities.end_pos, test_case.end_pos());
+        assert_eq!(case.entities.tier, RED_BELT);
+        assert_eq!(case.entities.expected_errors, result.errors);
+    }
+
+    #[test]
+    fn test_yaml_test_case_drags_as_the_fuzzer() {
+        // Nothing at the end of the drag row, so the end can't be inferred
+        let test_case = test_case("_ v _ _\n_ _ X _\n_ ^ _ _", RED_BELT);
+        let yaml = to_yaml_test_case("Drags as the fuzzer", &test_case).unwrap();
+
+        let case: Vec<DragTestCase> = serde_yaml::from_str(&yaml).unwrap();
+        let (world, errors) = run_test_case(&case[0].entities, TestVariant::Normal);
+        let result = run_fuzz_test(&test_case).unwrap();
+        assert_eq!(world, result.world_after);
+        assert_eq!(errors, result.errors);
+    }
+
+    /// A backwards rotation that undergrounds into the corner, leaving an
+    /// output underground there that can't turn (found by the 2D fuzzer).
+    fn broken_corner() -> (Fuzz2dTestCase, Vec<DragStep>) {
+        let mut world = WorldImpl::new();
+        world.build(pos(0, 6), CollidingEntityOrTile.into());
+        let test_case = Fuzz2dTestCase {
+            world,
+            start_pos: pos(8, 7),
+            belt_direction: Direction::East,
+            tier: BELT_TIERS[0],
+            seed: 0,
+        };
+        let steps = vec![DragStep::MoveTo(pos(0, 7)), DragStep::Rotate(pos(0, 0))];
+        (test_case, steps)
+    }
+
+    #[test]
+    fn test_shrink_2d_keeps_only_what_fails() {
+        let (mut original, mut steps) = broken_corner();
+        let (noise, _) = parse_world("_ _ _ 2^ _\n_ _ X _ 3>s\n_ _ _ _ _\n_ _ _ _ >").unwrap();
+        for (p, entity) in noise.entities {
+            original.world.build(p + euclid::vec2(3, 1), entity);
+        }
+        original.tier = RED_BELT;
+        steps.insert(0, DragStep::MoveTo(pos(10, 7)));
+        steps.insert(1, DragStep::MoveTo(pos(4, 7)));
+        steps.push(DragStep::MoveTo(pos(0, 3)));
+        assert!(fuzz_2d_case_fails(&original, &steps));
+
+        let (shrunk, shrunk_steps) = shrink_2d_test_case(&original, &steps, fuzz_2d_case_fails);
+        let (expected, expected_steps) = broken_corner();
+        assert_eq!(shrunk.world, expected.world);
+        assert_eq!(shrunk.tier, BELT_TIERS[0]);
+        assert_eq!(shrunk_steps, expected_steps);
+    }
+
+    #[test]
+    fn test_yaml_steps_test_case_reproduces_the_failure() {
+        let (test_case, steps) = broken_corner();
+        let failure = replay_drag_2d(&test_case, &steps).unwrap_err();
+        let yaml = to_yaml_steps_test_case("Broken corner", &test_case, &steps).unwrap();
+
+        let mut cases: Vec<StepsTestCase> = serde_yaml::from_str(&yaml).unwrap();
+        let case = cases.pop().unwrap();
+        check_steps_test_case_all_transforms(&case).unwrap();
+        assert_eq!(Some(&case.after), failure.world_after.as_ref());
+        assert_eq!(case.expected_errors, failure.errors);
+        let from_yaml = Fuzz2dTestCase {
+            world: case.before,
+            start_pos: case.start_pos,
+            belt_direction: case.belt_direction,
+            tier: case.tier,
+            seed: 0,
+        };
+        let yaml_failure = replay_drag_2d(&from_yaml, &case.steps).unwrap_err();
+        assert_eq!(yaml_failure.violation, failure.violation);
+    }
+}
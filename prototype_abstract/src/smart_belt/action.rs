@@ -1,8 +1,9 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 use super::drag_state::LastBuiltEntity;
 use super::{LineDrag, RaySense};
 use crate::belts::{Belt, BeltTier, UndergroundBelt};
+use crate::coverage::ActionKind;
 use crate::world::WorldImpl;
 use crate::{BeltCollidable, BeltConnectable, Direction, TilePosition};
 
@@ -27,7 +28,7 @@ pub enum Action {
     None,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize, Hash)]
 #[serde(rename_all = "snake_case")]
 pub enum Error {
     TooFarToConnect,
@@ -36,6 +37,22 @@ pub enum Error {
     BeltLineBroken,
 }
 
+impl Action {
+    pub fn kind(&self) -> ActionKind {
+        match self {
+            Action::PlaceBelt => ActionKind::PlaceBelt,
+            Action::CreateUnderground { .. } => ActionKind::CreateUnderground,
+            Action::ExtendUnderground { .. } => ActionKind::ExtendUnderground,
+            Action::IntegrateInputUnderground { .. } => ActionKind::IntegrateInputUnderground,
+            Action::IntegrateOutputUnderground => ActionKind::IntegrateOutputUnderground,
+            Action::IntegrateSplitter => ActionKind::IntegrateSplitter,
+            Action::SetImpassable(_) => ActionKind::SetImpassable,
+            Action::ClearEntity => ActionKind::ClearEntity,
+            Action::None => ActionKind::None,
+        }
+    }
+}
+
 impl<'a> LineDrag<'a> {
     pub fn apply_action(
         &mut self,
@@ -147,10 +164,7 @@ impl<'a> LineDrag<'a> {
             Action::IntegrateSplitter => {
                 let entity = self.world.upgrade_splitter(world_pos, self.tier);
                 let connectable = BeltConnectable::try_from(entity).unwrap();
-                self.set_last_built_entity(LastBuiltEntity::new(
-                    connectable,
-                    next_position,
-                ));
+                self.set_last_built_entity(LastBuiltEntity::new(connectable, next_position));
             }
             Action::SetImpassable(sense) => {
                 self.over_impassable = Some(sense);
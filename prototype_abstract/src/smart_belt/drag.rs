@@ -241,7 +241,8 @@ impl<'a> LineDrag<'a> {
         }
     }
 
-    pub(super) fn get_rotation_pivot(&self) -> (TilePosition, bool) {
+    /// The position the next rotation will pivot around, and if that is a backwards rotation.
+    pub fn get_rotation_pivot(&self) -> (TilePosition, bool) {
         let furthest_pos = self.furthest_placement_pos();
         (
             self.ray.get_position(furthest_pos),
@@ -249,6 +250,37 @@ impl<'a> LineDrag<'a> {
         )
     }
 
+    /// The furthest tiles behind and ahead, in the belt direction, that this
+    /// drag has placed or integrated an entity at.
+    /// Integrated entities (e.g. splitters) may be past the furthest placement.
+    pub fn line_extent(&self) -> (TilePosition, TilePosition) {
+        let (mut backward, mut forward) = (self.backward_placement, self.forward_placement);
+        if let Some(last_built) = &self.last_built_entity {
+            if self.ray.is_before(last_built.position, backward) {
+                backward = last_built.position;
+            }
+            if self.ray.is_before(forward, last_built.position) {
+                forward = last_built.position;
+            }
+        }
+        (
+            self.ray.get_position(backward),
+            self.ray.get_position(forward),
+        )
+    }
+
+    pub fn belt_direction(&self) -> Direction {
+        self.ray.direction
+    }
+
+    pub fn tier(&self) -> BeltTier {
+        self.tier
+    }
+
+    pub fn world(&self) -> &WorldImpl {
+        self.world
+    }
+
     pub fn furthest_placement_pos(&self) -> i32 {
         match self.furthest_placement_direction {
             RaySense::Forward => self.forward_placement,
@@ -1,5 +1,7 @@
 use crate::BeltCollidable;
 use crate::BeltConnectable;
+use crate::coverage::{self, DragEndKind, StepRule, TileKind};
+use crate::trace;
 use log::debug;
 
 use super::{Action, RaySense, SmartBeltWorldView, TileClassifier, TileType, action::Error};
@@ -158,7 +160,9 @@ pub(super) fn step(
 ) -> DragStepResult {
     let drag_end = get_drag_end_shape(last_built_entity, over_impassable, view);
     debug!("drag_end: {drag_end:?}");
-    match drag_end {
+    let drag_end_kind = drag_end.kind();
+    let mut tile = None;
+    let result = match drag_end {
         DragState::OverlappingOutputUnderground => {
             DragStepResult(Action::IntegrateOutputUnderground, None)
         }
@@ -172,6 +176,7 @@ pub(super) fn step(
             )
             .classify_next_tile();
             debug!("Tile type: {:?}", next_tile);
+            tile = Some(next_tile.kind());
             match next_tile {
                 TileType::Usable => drag_end.place_belt_or_underground(view),
                 TileType::IntegratedSplitter => DragStepResult(
@@ -185,6 +190,46 @@ pub(super) fn step(
                 TileType::ImpassableObstacle => drag_end.handle_impassable_obstacle(view),
             }
         }
+    };
+    record_step(drag_end_kind, tile, &result, view);
+    result
+}
+
+/// Reports a step to the coverage and trace recorders, if any are running.
+fn record_step(
+    drag_end: DragEndKind,
+    tile: Option<TileKind>,
+    result: &DragStepResult,
+    view: &SmartBeltWorldView,
+) {
+    let rule = StepRule {
+        drag_end,
+        tile,
+        action: result.0.kind(),
+        error: result.1.clone(),
+    };
+    trace::record_step(view.ray.get_position(view.next_position()), &rule);
+    coverage::record_step(rule);
+}
+
+impl DragState {
+    fn kind(&self) -> DragEndKind {
+        match self {
+            DragState::Extendable(ExtendableEnd::Belt) => DragEndKind::Belt,
+            DragState::Extendable(ExtendableEnd::IntegratedOutput) => DragEndKind::IntegratedOutput,
+            DragState::Extendable(ExtendableEnd::ExtendableUnderground { .. }) => {
+                DragEndKind::ExtendableUnderground
+            }
+            DragState::Extendable(ExtendableEnd::TraversingObstacle { .. }) => {
+                DragEndKind::TraversingObstacle
+            }
+            DragState::Extendable(ExtendableEnd::OverImpassableObstacle { .. }) => {
+                DragEndKind::OverImpassableObstacle
+            }
+            DragState::Extendable(ExtendableEnd::Error) => DragEndKind::Error,
+            DragState::OverlappingOutputUnderground => DragEndKind::OverlappingOutputUnderground,
+            DragState::InBetweenUndergrounds => DragEndKind::InBetweenUndergrounds,
+        }
     }
 }
 
@@ -1,4 +1,5 @@
 use crate::belts::{Belt, BeltTier, LoaderLike, Splitter, UndergroundBelt};
+use crate::coverage::{Branch, TileKind, record_branch, record_if};
 use crate::{BeltCollidable, BeltConnectable, Direction};
 use std::fmt::Debug;
 
@@ -19,6 +20,18 @@ pub(super) enum TileType {
     ImpassableObstacle,
 }
 
+impl TileType {
+    pub(super) fn kind(&self) -> TileKind {
+        match self {
+            TileType::Usable => TileKind::Usable,
+            TileType::Obstacle => TileKind::Obstacle,
+            TileType::IntegratedSplitter => TileKind::IntegratedSplitter,
+            TileType::IntegratedUnderground { .. } => TileKind::IntegratedUnderground,
+            TileType::ImpassableObstacle => TileKind::ImpassableObstacle,
+        }
+    }
+}
+
 pub(super) struct TileClassifier<'a> {
     view: &'a SmartBeltWorldView<'a>,
     can_enter_next_tile: bool,
@@ -78,9 +91,13 @@ impl<'a> TileClassifier<'a> {
                 Ok(BeltConnectable::UndergroundBelt(ug)) => self.classify_underground(&ug),
                 Ok(BeltConnectable::Splitter(splitter)) => self.classify_splitter(&splitter),
                 Ok(BeltConnectable::LoaderLike(loader)) => self.classify_loader(&loader),
-                Err(_) => TileType::Obstacle,
+                Err(_) => {
+                    record_branch(Branch::NonBeltEntity);
+                    TileType::Obstacle
+                }
             }
         } else {
+            record_branch(Branch::EmptyTile);
             TileType::Usable
         }
     }
@@ -98,9 +115,11 @@ impl<'a> TileClassifier<'a> {
             // If we are connected to the previous belt, we must try to
             // integrate it. However, we aren't allowed to rotate existing belt;
             // so this is impassable (ends the belt segment)
+            record_branch(Branch::CurvedBeltConnectedToIntegrated);
             TileType::ImpassableObstacle
         } else {
             // Other curved belts are normal obstacles.
+            record_branch(Branch::CurvedBeltOther);
             TileType::Obstacle
         }
     }
@@ -109,11 +128,18 @@ impl<'a> TileClassifier<'a> {
         if self.is_perpendicular(belt.direction) || self.is_connected_to_previous_obstacle_belt() {
             // - Perpendicular belts are obstacles
             // - Belts connected to obstacles are obstacles
+            record_branch(Branch::StraightBeltObstacle);
             TileType::Obstacle
-        } else if (belt.direction == self.belt_direction() && self.can_enter_next_tile)
-            || self.is_connected_to_previous_integrated_belt()
-            || self.should_integrate_belt_segment(false, false)
-        {
+        } else if record_if(
+            belt.direction == self.belt_direction() && self.can_enter_next_tile,
+            Branch::StraightBeltForwards,
+        ) || record_if(
+            self.is_connected_to_previous_integrated_belt(),
+            Branch::StraightBeltConnectedToIntegrated,
+        ) || record_if(
+            self.should_integrate_belt_segment(false, false),
+            Branch::StraightBeltSegmentIntegrated,
+        ) {
             // - A forwards belt means we're running "directly" into a new belt
             //   segment. _always_ use this, even if it leads to a dead end later.
             // - If we've integrated the last (backwards) belt, then integrate
@@ -121,6 +147,7 @@ impl<'a> TileClassifier<'a> {
             // - Otherwise, check the belt segment. We might want to underground over it.
             TileType::Usable
         } else {
+            record_branch(Branch::StraightBeltSegmentNotIntegrated);
             TileType::Obstacle
         }
     }
@@ -129,6 +156,7 @@ impl<'a> TileClassifier<'a> {
         if self.is_perpendicular(ug.direction) || self.is_connected_to_previous_obstacle_belt() {
             // - Perpendicular undergrounds are obstacles.
             // - Undergrounds connected to obstacles are obstacles.
+            record_branch(Branch::UndergroundObstacle);
             TileType::Obstacle
         } else if let Some(output_pos) = self.view.get_ug_pair_pos(self.next_position(), ug) {
             self.classify_paired_underground_belt(ug, output_pos)
@@ -141,8 +169,10 @@ impl<'a> TileClassifier<'a> {
         if self.ug_is_enterable(ug) && self.can_enter_next_tile {
             // Enter, if it has the correct shape, and we can enter it (not traversing an obstacle)
             // Note, underground upgrade checking is handled elsewhere
+            record_branch(Branch::PairedUndergroundEntered);
             TileType::IntegratedUnderground { output_pos }
         } else {
+            record_branch(Branch::PairedUndergroundObstacle);
             TileType::Obstacle
         }
     }
@@ -153,15 +183,20 @@ impl<'a> TileClassifier<'a> {
             // If the unpaired underground is enterable, it is always usable (fast-replaced with belt).
             // We don't need to check if last_state.can_enter_next_tile, since this tile might be replaced with an output underground.
             // Note: this also covers the "is_connected_to_previous_integrated_belt" case
+            record_branch(Branch::UnpairedUndergroundEnterable);
             TileType::Usable
-        } else if ug.tier == self.tier() || {
+        } else if record_if(ug.tier == self.tier(), Branch::UnpairedUndergroundSameTier) || {
             // We're running into the back of an unpaired underground.
             // Check the belt segment, we might want to underground over it.
             // Additional check: we can't ug over another ug of the same tier, so always integrate that.
-            self.should_integrate_belt_segment(ug.direction == self.belt_direction(), false)
+            record_if(
+                self.should_integrate_belt_segment(ug.direction == self.belt_direction(), false),
+                Branch::UnpairedUndergroundSegmentIntegrated,
+            )
         } {
             TileType::Usable
         } else {
+            record_branch(Branch::UnpairedUndergroundObstacle);
             TileType::Obstacle
         }
     }
@@ -177,19 +212,24 @@ impl<'a> TileClassifier<'a> {
         if self.is_connected_to_previous_integrated_belt() {
             // If we are connected to the previous belt, we must try to integrate this splitter.
             if splitter_direction_matches {
+                record_branch(Branch::SplitterConnectedToIntegrated);
                 TileType::IntegratedSplitter
             } else {
                 // Wrong direction -- we're about to break the belt segment
+                record_branch(Branch::SplitterConnectedWrongDirection);
                 TileType::ImpassableObstacle
             }
         } else if !(splitter_direction_matches && self.can_enter_next_tile) {
             // Un-enterable splitters are obstacles
+            record_branch(Branch::SplitterNotEnterable);
             TileType::Obstacle
         } else if self.should_integrate_belt_segment(true, true) {
             // We are entering a splitter that previously didn't have an input.
             // Check the belt segment, we might want to underground over it.
+            record_branch(Branch::SplitterSegmentIntegrated);
             TileType::IntegratedSplitter
         } else {
+            record_branch(Branch::SplitterSegmentNotIntegrated);
             TileType::Obstacle
         }
     }
@@ -198,8 +238,10 @@ impl<'a> TileClassifier<'a> {
         // If we directly run into the loader, it ends the belt segment (so is impassable)
         // Otherwise, it's an obstacle
         if self.belt_connects_into_loader(loader) {
+            record_branch(Branch::LoaderConnectsInto);
             TileType::ImpassableObstacle
         } else {
+            record_branch(Branch::LoaderObstacle);
             TileType::Obstacle
         }
     }
@@ -266,6 +308,7 @@ impl<'a> TileClassifier<'a> {
     ) -> bool {
         let Some(max_underground_position) = self.max_underground_position() else {
             // If we can't create an underground, integrate it.
+            record_branch(Branch::SegmentNoUnderground);
             return true;
         };
 
@@ -287,10 +330,12 @@ impl<'a> TileClassifier<'a> {
                 && let Some(entity) = self.view.get_entity(scan_pos)
                 && self.is_trivial_obstacle(entity, scan_pos)
             {
+                record_branch(Branch::SegmentSplitterExitBlocked);
                 return false;
             }
         }
 
+        let mut segment_end = Branch::SegmentEnd;
         while scan_pos * step_sign < max_underground_position * step_sign
             && let Some(belt_connectable) = self.view.get_belt_connectable(scan_pos)
             && self.view.is_belt_connected_to_previous_tile(scan_pos)
@@ -298,24 +343,29 @@ impl<'a> TileClassifier<'a> {
             match belt_connectable {
                 BeltConnectable::Belt(belt) => {
                     if self.view.belt_was_curved(scan_pos, &belt) {
+                        record_branch(Branch::SegmentCurvedBelt);
                         return false;
                     }
                 }
                 BeltConnectable::UndergroundBelt(ug) => {
                     if ug.tier == self.tier() {
+                        segment_end = Branch::SegmentSameTierUnderground;
                         break;
                     }
                     let Some(pair_pos) = self.view.get_ug_pair_pos(scan_pos, &ug) else {
+                        segment_end = Branch::SegmentUnpairedUnderground;
                         break;
                     };
                     scan_pos = pair_pos;
                 }
                 BeltConnectable::Splitter(_) | BeltConnectable::LoaderLike(_) => {
+                    record_branch(Branch::SegmentSplitterOrLoader);
                     return segment_belt_direction_matches;
                 }
             }
             scan_pos += step_sign;
         }
+        record_branch(segment_end);
         true
     }
 
@@ -0,0 +1,495 @@
+//! Checks for the spec's desired properties, which must hold for _any_ drag:
+//! - Continuity: without errors, the belt line is unbroken from start to end.
+//! - Completeness: if the belt line is broken, an error was reported.
+//! - Non-interference: non-integrated belts are unaffected, including their curvature.
+//!
+//! These only look at the world before and after a drag, so they work for any
+//! direction, backwards drags, and rotated drags alike.
+
+use std::collections::HashSet;
+
+use euclid::vec2;
+use itertools::Itertools;
+
+use crate::belts::BeltTier;
+use crate::smart_belt::action::Error;
+use crate::{
+    BeltCollidable, BeltConnectable, BeltConnectableTrait, Direction, Ray, TilePosition, WorldImpl,
+};
+
+/// A violated property, with the position it was detected at (if any).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PropertyViolation(pub String, pub Option<TilePosition>);
+
+impl std::fmt::Display for PropertyViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// The tiles a drag should have made a belt line over, in the order items flow along it.
+///
+/// For backwards drags this is the reverse of the cursor order. For rotated
+/// drags, the corner tile appears once, where the belt turns.
+#[derive(Debug, Clone)]
+pub struct DragPath {
+    pub tiles: Vec<TilePosition>,
+    pub tier: BeltTier,
+}
+
+impl DragPath {
+    /// A single straight drag from `start_pos` to `end_pos`, placing belts facing `belt_direction`.
+    /// `end_pos` may be behind `start_pos`, for backwards drags.
+    pub fn straight(
+        start_pos: TilePosition,
+        end_pos: TilePosition,
+        belt_direction: Direction,
+        tier: BeltTier,
+    ) -> Self {
+        let ray = Ray::new(start_pos, belt_direction);
+        assert_eq!(
+            ray.snap(end_pos),
+            end_pos,
+            "end_pos must be on the same line as start_pos in belt_direction"
+        );
+        let (first, last) = if ray.is_before(ray.ray_position(end_pos), ray.ray_position(start_pos))
+        {
+            (end_pos, start_pos)
+        } else {
+            (start_pos, end_pos)
+        };
+        Self::from_corners(&[first, last], tier)
+    }
+
+    /// A path going through each of `corners` in flow order.
+    /// Consecutive corners must be in the same row or column.
+    pub fn from_corners(corners: &[TilePosition], tier: BeltTier) -> Self {
+        let mut tiles: Vec<TilePosition> = corners.first().copied().into_iter().collect();
+        for (&from, &to) in corners.iter().tuple_windows() {
+            let diff = to - from;
+            assert!(
+                diff.x == 0 || diff.y == 0,
+                "Corners {from:?} and {to:?} are not in a straight line"
+            );
+            let step = vec2(diff.x.signum(), diff.y.signum());
+            let mut current = from;
+            while current != to {
+                current += step;
+                tiles.push(current);
+            }
+        }
+        Self { tiles, tier }
+    }
+
+    /// Direction items flow in, when entering the tile at `index` from the previous tile.
+    fn flow_direction_into(&self, index: usize) -> Option<Direction> {
+        let prev = index.checked_sub(1)?;
+        Direction::from_vector(self.tiles[index] - self.tiles[prev])
+    }
+
+    /// The tile the end of the belt line outputs into, just past the path.
+    /// Uses the entity at the end of the path if there is one, as a single
+    /// tile path has no direction of its own.
+    fn head_output_tile(&self, world: &WorldImpl) -> Option<TilePosition> {
+        let index = self.tiles.len().checked_sub(1)?;
+        let direction = world
+            .output_direction_at(self.tiles[index])
+            .or_else(|| self.flow_direction_into(index))?;
+        Some(self.tiles[index] + direction.to_vector())
+    }
+}
+
+/// Result of following a belt line along a drag path.
+#[derive(Debug, Clone, Default)]
+pub struct BeltLineTrace {
+    /// Positions of the entities that form the belt line, in flow order.
+    pub integrated: Vec<TilePosition>,
+    /// Where the belt line stops following the path, if it does not reach the end.
+    pub broken_at: Option<TilePosition>,
+}
+
+/// Follows the belt line in `world` along `path`, passing through underground pairs.
+///
+/// The first tile may have any input; every other entity must be belt-connected
+/// to the previous one, in the direction of the path. A loader always ends the line.
+pub fn trace_belt_line(world: &WorldImpl, path: &DragPath) -> BeltLineTrace {
+    let tiles = &path.tiles;
+    let mut trace = BeltLineTrace::default();
+    let mut index = 0;
+    while index < tiles.len() {
+        let position = tiles[index];
+        let entity = world.get_belt(position);
+        let connected = path.flow_direction_into(index).is_none_or(|direction| {
+            world.output_direction_at(tiles[index - 1]) == Some(direction)
+                && entity.as_ref().is_some_and(|e| accepts_input(e, direction))
+        });
+        let (Some(entity), true) = (entity, connected) else {
+            trace.broken_at = Some(position);
+            break;
+        };
+        trace.integrated.push(position);
+        match entity {
+            BeltConnectable::UndergroundBelt(ug) if ug.is_input => {
+                let pair_index = world.get_ug_pair(position, &ug).and_then(|(pair_pos, _)| {
+                    let offset = tiles[index..].iter().position(|&p| p == pair_pos)?;
+                    let straight = pair_pos - position == ug.direction.to_vector() * offset as i32;
+                    straight.then_some(index + offset)
+                });
+                let Some(pair_index) = pair_index else {
+                    // A drag may end at an input, its pair then being past the path
+                    if index == tiles.len() - 1
+                        && let Some((pair_pos, _)) = world.get_ug_pair(position, &ug)
+                    {
+                        trace.integrated.push(pair_pos);
+                    } else {
+                        trace.broken_at = Some(position);
+                    }
+                    break;
+                };
+                trace.integrated.push(tiles[pair_index]);
+                index = pair_index;
+            }
+            BeltConnectable::LoaderLike(_) => {
+                trace.integrated.pop();
+                trace.broken_at = Some(position);
+                break;
+            }
+            _ => {}
+        }
+        index += 1;
+    }
+    trace
+}
+
+/// If items going `direction` can flow into `entity`.
+/// Belts and input undergrounds can also be side-loaded.
+fn accepts_input(entity: &BeltConnectable, direction: Direction) -> bool {
+    match entity {
+        BeltConnectable::Belt(belt) => direction != belt.direction.opposite(),
+        BeltConnectable::UndergroundBelt(ug) if ug.is_input => direction != ug.direction.opposite(),
+        _ => entity.has_input_going(direction),
+    }
+}
+
+/// Checks the spec properties for a single drag.
+///
+/// Without errors, all properties are checked. With errors, the belt line may
+/// legitimately be broken anywhere on the path, so only entities off the path
+/// are checked to be unaffected.
+pub fn check_drag_properties(
+    before: &WorldImpl,
+    after: &WorldImpl,
+    drag_path: &DragPath,
+    errors: &HashSet<(TilePosition, Error)>,
+) -> Result<(), PropertyViolation> {
+    let trace = trace_belt_line(after, drag_path);
+
+    if errors.is_empty() {
+        // Continuity and completeness: a broken belt line must come with an error
+        if let Some(broken_at) = trace.broken_at {
+            return Err(PropertyViolation(
+                format!(
+                    "No errors, but belt line is broken at {:?} (path {:?} to {:?})",
+                    broken_at,
+                    drag_path.tiles.first(),
+                    drag_path.tiles.last()
+                ),
+                Some(broken_at),
+            ));
+        }
+        check_belt_line_tier(after, &trace.integrated, drag_path.tier)?;
+    }
+
+    let integrated: HashSet<TilePosition> = trace.integrated.into_iter().collect();
+    check_non_interference(before, after, drag_path, &integrated, errors)
+}
+
+/// All entities in the belt line have the drag tier, except loaders.
+pub fn check_belt_line_tier(
+    world: &WorldImpl,
+    belt_line: &[TilePosition],
+    expected_tier: BeltTier,
+) -> Result<(), PropertyViolation> {
+    belt_line.iter().try_for_each(|&pos| {
+        if let Some(entity) = world.get(pos)
+            && let Ok(belt_connectable) = BeltConnectable::try_from(entity)
+            && !matches!(entity, BeltCollidable::LoaderLike(_))
+            && belt_connectable.tier() != expected_tier
+        {
+            Err(PropertyViolation(
+                format!(
+                    "Belt at position {:?} has tier {:?}, expected {:?}",
+                    pos,
+                    belt_connectable.tier(),
+                    expected_tier
+                ),
+                Some(pos),
+            ))
+        } else {
+            Ok(())
+        }
+    })
+}
+
+/// Entities not integrated into the belt line are unchanged, and belts keep their curvature.
+///
+/// If there are `errors`, every tile on the path is treated as possibly
+/// integrated, as the belt line is then unknowable.
+///
+/// Exceptions:
+/// - Entities on the path may be removed (we may replace some belts).
+/// - Belts may lose an input, when the drag replaces the entity feeding them.
+/// - The tile the end of the belt line outputs into may become curved; that is
+///   the player's intent when dragging up to it.
+/// - The tile an entity with an error outputs into may become curved; the
+///   error already tells the player the line is broken there.
+fn check_non_interference(
+    before: &WorldImpl,
+    after: &WorldImpl,
+    drag_path: &DragPath,
+    integrated: &HashSet<TilePosition>,
+    errors: &HashSet<(TilePosition, Error)>,
+) -> Result<(), PropertyViolation> {
+    let path_tiles_only_integrated = !errors.is_empty();
+    let path_tiles: HashSet<TilePosition> = drag_path.tiles.iter().copied().collect();
+    let mut may_curve: HashSet<TilePosition> =
+        drag_path.head_output_tile(after).into_iter().collect();
+    may_curve.extend(errors.iter().filter_map(|&(tile, _)| {
+        after
+            .output_direction_at(tile)
+            .map(|direction| tile + direction.to_vector())
+    }));
+
+    for (&pos, entity_before) in &before.entities {
+        if integrated.contains(&pos) || (path_tiles_only_integrated && path_tiles.contains(&pos)) {
+            continue;
+        }
+        let Some(entity_after) = after.get(pos) else {
+            if path_tiles.contains(&pos) {
+                continue;
+            }
+            return Err(PropertyViolation(
+                format!("Entity removed outside the drag path at {pos:?}: {entity_before:?}"),
+                Some(pos),
+            ));
+        };
+        if entity_before != entity_after {
+            return Err(PropertyViolation(
+                format!(
+                    "Entity changed at position {:?}. Before: {:?}, After: {:?}",
+                    pos, entity_before, entity_after
+                ),
+                Some(pos),
+            ));
+        }
+        if let BeltCollidable::Belt(belt) = entity_before
+            && !may_curve.contains(&pos)
+        {
+            let before_in = before.belt_curved_input_direction(pos, belt.direction);
+            let after_in = after.belt_curved_input_direction(pos, belt.direction);
+            // Losing an input is fine: that is a replaced entity on the path.
+            let gained_input = (0..4).filter_map(Direction::from_ordinal).any(|direction| {
+                let feeds = |world: &WorldImpl| {
+                    world.output_direction_at(pos - direction.to_vector()) == Some(direction)
+                };
+                feeds(after) && !feeds(before)
+            });
+            if before_in != after_in && gained_input {
+                return Err(PropertyViolation(
+                    format!(
+                        "Belt curvature changed at position {:?} {:?}\nBefore in: {:?}\nAfter in: {:?}",
+                        pos, belt, before_in, after_in
+                    ),
+                    Some(pos),
+                ));
+            }
+        }
+    }
+
+    if let Some((&pos, entity)) = after
+        .entities
+        .iter()
+        .find(|(pos, _)| !before.entities.contains_key(pos) && !path_tiles.contains(pos))
+    {
+        return Err(PropertyViolation(
+            format!("Entity built outside the drag path at {pos:?}: {entity:?}"),
+            Some(pos),
+        ));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::belts::{RED_BELT, YELLOW_BELT};
+    use crate::smart_belt::LineDrag;
+    use crate::test_case::parse_world;
+    use crate::{Transform, pos};
+
+    fn run_drag(
+        before: &WorldImpl,
+        start: TilePosition,
+        direction: Direction,
+        cursor: &[TilePosition],
+        rotate_at: Option<usize>,
+    ) -> (WorldImpl, HashSet<(TilePosition, Error)>) {
+        let mut after = before.clone();
+        let mut errors = HashSet::new();
+        {
+            let mut error_handler = |p, e| {
+                errors.insert((p, e));
+            };
+            let mut drag = LineDrag::start_drag(
+                &mut after,
+                &mut error_handler,
+                YELLOW_BELT,
+                start,
+                direction,
+            );
+            for (i, &target) in cursor.iter().enumerate() {
+                if rotate_at == Some(i) {
+                    let (new_drag, rotated) = drag.rotate(&mut error_handler, target);
+                    assert!(rotated);
+                    drag = new_drag;
+                } else {
+                    drag.interpolate_to(&mut error_handler, target);
+                }
+            }
+        }
+        (after, errors)
+    }
+
+    #[test]
+    fn test_drag_path_straight_is_in_flow_order() {
+        let forward = DragPath::straight(pos(0, 0), pos(2, 0), Direction::East, YELLOW_BELT);
+        assert_eq!(forward.tiles, vec![pos(0, 0), pos(1, 0), pos(2, 0)]);
+
+        let backward = DragPath::straight(pos(0, 0), pos(0, 2), Direction::North, YELLOW_BELT);
+        assert_eq!(backward.tiles, vec![pos(0, 2), pos(0, 1), pos(0, 0)]);
+        assert_eq!(
+            backward.head_output_tile(&WorldImpl::new()),
+            Some(pos(0, -1))
+        );
+    }
+
+    #[test]
+    fn test_drag_path_from_corners() {
+        let path = DragPath::from_corners(&[pos(0, 0), pos(2, 0), pos(2, 2)], YELLOW_BELT);
+        assert_eq!(
+            path.tiles,
+            vec![pos(0, 0), pos(1, 0), pos(2, 0), pos(2, 1), pos(2, 2)]
+        );
+    }
+
+    #[test]
+    fn test_straight_drag_all_transforms() {
+        let (before, _) = parse_world("_ X _ < _ v _\n_ _ _ _ _ > _").unwrap();
+        for transform in Transform::all_unique_transforms() {
+            let before = before.transform_world(&transform);
+            let start = transform.transform_position(pos(0, 0));
+            let end = transform.transform_position(pos(7, 0));
+            let direction = transform.transform_direction(Direction::East);
+            let (after, errors) = run_drag(&before, start, direction, &[end], None);
+            assert!(errors.is_empty(), "{transform:?}: {errors:?}");
+
+            let path = DragPath::straight(start, end, direction, YELLOW_BELT);
+            check_drag_properties(&before, &after, &path, &errors)
+                .unwrap_or_else(|e| panic!("{transform:?}: {e}"));
+        }
+    }
+
+    #[test]
+    fn test_backwards_drag() {
+        let (before, _) = parse_world("_ _ X _ _").unwrap();
+        let (after, errors) = run_drag(&before, pos(4, 0), Direction::East, &[pos(0, 0)], None);
+        let path = DragPath::straight(pos(4, 0), pos(0, 0), Direction::East, YELLOW_BELT);
+        check_drag_properties(&before, &after, &path, &errors).unwrap();
+    }
+
+    #[test]
+    fn test_rotated_drag() {
+        let before = WorldImpl::new();
+        let (after, errors) = run_drag(
+            &before,
+            pos(0, 0),
+            Direction::East,
+            &[pos(2, 0), pos(2, 1), pos(2, 2)],
+            Some(1),
+        );
+        let path = DragPath::from_corners(&[pos(0, 0), pos(2, 0), pos(2, 2)], YELLOW_BELT);
+        check_drag_properties(&before, &after, &path, &errors).unwrap();
+    }
+
+    #[test]
+    fn test_broken_line_without_error_is_violation() {
+        let before = WorldImpl::new();
+        let (after, _) = parse_world("> > < >").unwrap();
+        let path = DragPath::straight(pos(0, 0), pos(3, 0), Direction::East, YELLOW_BELT);
+        let err = check_drag_properties(&before, &after, &path, &HashSet::new()).unwrap_err();
+        assert_eq!(err.1, Some(pos(2, 0)));
+
+        let errors = [(pos(2, 0), Error::EntityInTheWay)].into();
+        check_drag_properties(&before, &after, &path, &errors).unwrap();
+    }
+
+    #[test]
+    fn test_wrong_tier_is_violation() {
+        let (before, _) = parse_world("_ 2> _").unwrap();
+        let (after, _) = parse_world("> 2> >").unwrap();
+        let path = DragPath::straight(pos(0, 0), pos(2, 0), Direction::East, YELLOW_BELT);
+        let err = check_drag_properties(&before, &after, &path, &HashSet::new()).unwrap_err();
+        assert_eq!(err.1, Some(pos(1, 0)));
+
+        let red_path = DragPath {
+            tier: RED_BELT,
+            ..path
+        };
+        assert!(check_drag_properties(&before, &after, &red_path, &HashSet::new()).is_err());
+    }
+
+    #[test]
+    fn test_curvature_change_is_violation_even_with_errors() {
+        // The belt above the path gets side-loaded by a (wrongly) rotated belt
+        let (before, _) = parse_world("_ >\n_ _ _").unwrap();
+        let (after, _) = parse_world("_ >\n> ^ *>").unwrap();
+        let path = DragPath::straight(pos(0, 1), pos(2, 1), Direction::East, YELLOW_BELT);
+        let errors = [(pos(2, 1), Error::BeltLineBroken)].into();
+        let err = check_drag_properties(&before, &after, &path, &errors).unwrap_err();
+        assert_eq!(err.1, Some(pos(1, 0)));
+    }
+
+    #[test]
+    fn test_entity_with_error_may_curve_what_it_outputs_into() {
+        let (before, _) = parse_world("_ _ _\n_ > _").unwrap();
+        let (after, _) = parse_world("> v\n_ > _").unwrap();
+        let path = DragPath::straight(pos(0, 0), pos(2, 0), Direction::East, YELLOW_BELT);
+        let errors = [(pos(1, 0), Error::EntityInTheWay)].into();
+        check_drag_properties(&before, &after, &path, &errors).unwrap();
+
+        let errors = [(pos(0, 0), Error::EntityInTheWay)].into();
+        let err = check_drag_properties(&before, &after, &path, &errors).unwrap_err();
+        assert_eq!(err.1, Some(pos(1, 1)));
+    }
+
+    #[test]
+    fn test_losing_an_input_is_not_violation() {
+        // The belt feeding the one below the path is replaced
+        let (before, _) = parse_world("_ v _\n_ > _").unwrap();
+        let (after, _) = parse_world("> > >\n_ > _").unwrap();
+        let path = DragPath::straight(pos(0, 0), pos(2, 0), Direction::East, YELLOW_BELT);
+        check_drag_properties(&before, &after, &path, &HashSet::new()).unwrap();
+    }
+
+    #[test]
+    fn test_drag_may_end_at_underground_input() {
+        let (before, _) = parse_world("_ _ X >o").unwrap();
+        let (after, _) = parse_world("> >i X >o").unwrap();
+        let path = DragPath::straight(pos(0, 0), pos(1, 0), Direction::East, YELLOW_BELT);
+        let trace = trace_belt_line(&after, &path);
+        assert_eq!(trace.broken_at, None);
+        assert_eq!(trace.integrated, vec![pos(0, 0), pos(1, 0), pos(3, 0)]);
+        check_drag_properties(&before, &after, &path, &HashSet::new()).unwrap();
+    }
+}
@@ -0,0 +1,75 @@
+//! The test functions `build.rs` generates for each case in `test_suite/`.
+//! Also compiled into `build.rs` (via `#[path]`), so it only depends on
+//! `serde` and `serde_yaml`.
+
+use serde::Deserialize;
+use serde_yaml::Value;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+struct TestCaseFlags {
+    #[serde(default)]
+    not_reversible: bool,
+    #[serde(default)]
+    forward_back: bool,
+}
+
+/// Lowercase, with each run of non-alphanumeric characters replaced by one `_`.
+/// Unnamed cases are numbered from 1 by their position in the file.
+pub fn sanitized_case_name(case: &Value, index: usize) -> String {
+    let Some(name) = case.get("name").and_then(|v| v.as_str()) else {
+        return format!("{:03}", index + 1);
+    };
+    let sanitized_name = name
+        .to_lowercase()
+        .chars()
+        .fold(String::new(), |mut acc, c| {
+            if c.is_alphanumeric() {
+                acc.push(c);
+            } else if !acc.ends_with('_') {
+                acc.push('_');
+            }
+            acc
+        });
+    sanitized_name.trim_end_matches('_').to_string()
+}
+
+/// `(runner fn in tests/suite.rs, test fn name suffix)` for each generated test.
+pub fn case_test_fns(case: &Value) -> Vec<(&'static str, &'static str)> {
+    let flags: TestCaseFlags = serde_yaml::from_value(case.clone()).unwrap_or(TestCaseFlags {
+        not_reversible: false,
+        forward_back: false,
+    });
+
+    let mut test_fns = vec![];
+    if case.get("drags").is_some() {
+        test_fns.push(("run_scenario_test_case", "_normal"));
+    } else if case.get("steps").is_some() {
+        // Steps spell out the whole drag, so there are no variants
+        test_fns.push(("run_steps_test_case", "_normal"));
+    } else if flags.forward_back {
+        // For forward_back tests, generate ForwardBack variants
+        test_fns.push(("run_test_case_forward_back", "_normal"));
+        if !flags.not_reversible {
+            test_fns.push(("run_test_case_forward_back_reverse", "_reverse"));
+        }
+    } else {
+        // For normal tests, generate normal and wiggle variants
+        test_fns.push(("run_test_case", "_normal"));
+        if !flags.not_reversible {
+            test_fns.push(("run_test_case_reverse", "_reverse"));
+        }
+        test_fns.push(("run_test_case_wiggle", "_wiggle"));
+        test_fns.push(("run_test_case_mega_wiggle", "_mega_wiggle"));
+        if !flags.not_reversible {
+            test_fns.push(("run_test_case_wiggle_reverse", "_wiggle_reverse"));
+            test_fns.push(("run_test_case_mega_wiggle_reverse", "_mega_wiggle_reverse"));
+        }
+    }
+    test_fns
+}
+
+/// The generated test fn name for a case, given a suffix from [`case_test_fns`].
+pub fn test_fn_name(case_name: &str, suffix: &str) -> String {
+    format!("test_{case_name}{suffix}")
+}
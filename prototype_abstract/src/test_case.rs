@@ -1,5 +1,6 @@
+use std::cell::RefCell;
 use std::cmp::max;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 use crate::BoundingBox;
 use crate::belts::{BELT_TIERS, Belt, BeltTier, LoaderLike, Splitter, UndergroundBelt};
@@ -9,6 +10,7 @@ use crate::{
     BeltCollidable, BeltConnectable, BeltConnectableTrait, Direction, TilePosition, TileVec,
     Transform, WorldImpl, pos,
     smart_belt::{LineDrag, action, action::Error},
+    trace::{TraceStep, check_trace, parse_trace, with_trace},
 };
 use anyhow::{Context, Result, bail};
 use euclid::vec2;
@@ -23,6 +25,13 @@ pub enum TestVariant {
     ForwardBack,
 }
 
+/// A single cursor action during a drag.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DragStep {
+    MoveTo(TilePosition),
+    Rotate(TilePosition),
+}
+
 #[derive(Debug, Clone)]
 pub struct DragTestCase {
     pub name: String,
@@ -30,6 +39,11 @@ pub struct DragTestCase {
     pub after_for_reverse: Option<WorldImpl>,
     pub not_reversible: bool,
     pub forward_back: bool,
+    /// Checked against the plain (not reversed or wiggled) drag.
+    pub trace: Option<Vec<TraceStep>>,
+    /// The name of an earlier case this is intentionally the same drag as
+    /// (e.g. a mirror of it), so [`crate::lint`] doesn't warn about it.
+    pub equivalent_to: Option<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -80,53 +94,13 @@ fn check_test_case(
     };
 
     if result != *expected_world || !errors_match {
-        let bounds = test.bounds();
-        let mut error_message = format!(
-            r#"
-Before:
-
-{}
-
-Expected:
-
-{}
-
-Got:
-
-{}
-
-"#,
-            print_world(&test.before, bounds, &[]),
-            print_world(
-                expected_world,
-                bounds,
-                &expected_errors
-                    .iter()
-                    .map(|f| f.0)
-                    .collect::<Vec<TilePosition>>()
-            ),
-            print_world(
-                &result,
-                bounds,
-                &actual_errors
-                    .iter()
-                    .map(|f| f.0)
-                    .collect::<Vec<TilePosition>>()
-            )
-        );
-        if actual_errors != *expected_errors {
-            error_message.push_str(&format!(
-                r#"
-Expected errors:
-{:?}
-Got errors:
-{:?}
-"#,
-                expected_errors, actual_errors
-            ));
-        }
-
-        bail!(error_message);
+        bail!(DragMismatch {
+            before: test.before.clone(),
+            expected: expected_world.clone(),
+            actual: result,
+            expected_errors: expected_errors.clone(),
+            actual_errors,
+        });
     }
 
     Ok(())
@@ -162,13 +136,27 @@ pub fn check_test_case_all_transforms(
             (false, TestVariant::ForwardBack) => format!("[transform {}] [forward_back]", i),
             (false, TestVariant::Normal) => format!("[transform {}]", i),
         };
-        check_test_case(&test_to_check, false, test_variant).with_context(|| test_name)?;
+        let (result, trace) = with_trace(|| check_test_case(&test_to_check, false, test_variant));
+        result.with_context(|| test_name.clone())?;
+        if let Some(expected_trace) = &test.trace
+            && !reverse
+            && test_variant == TestVariant::Normal
+        {
+            let expected_trace = expected_trace
+                .iter()
+                .map(|s| s.transform(transform))
+                .collect_vec();
+            check_trace(&expected_trace, &trace).with_context(|| test_name)?;
+        }
     }
 
     Ok(())
 }
 
-fn transform_test_case(test: &TestCaseEntities, transform: &Transform) -> TestCaseEntities {
+pub(crate) fn transform_test_case(
+    test: &TestCaseEntities,
+    transform: &Transform,
+) -> TestCaseEntities {
     TestCaseEntities {
         before: test.before.transform_world(transform),
         after: test.after.transform_world(transform),
@@ -185,7 +173,7 @@ fn transform_test_case(test: &TestCaseEntities, transform: &Transform) -> TestCa
     }
 }
 
-fn flip_test_case_unchecked(
+pub(crate) fn flip_test_case_unchecked(
     test: &TestCaseEntities,
     after_for_reverse: Option<&WorldImpl>,
 ) -> TestCaseEntities {
@@ -215,14 +203,13 @@ fn flip_test_case(
         })?;
     Ok(flipped)
 }
-fn run_test_case(
+pub(crate) fn run_test_case(
     test: &TestCaseEntities,
     test_variant: TestVariant,
 ) -> (WorldImpl, HashSet<(TilePosition, Error)>) {
     eprintln!("Starting test case\n");
 
     let TestCaseEntities {
-        leftmost_pos,
         start_pos,
         belt_direction,
         end_pos,
@@ -237,38 +224,15 @@ fn run_test_case(
         "end_pos must be on the same line as start_pos in drag_direction"
     );
 
+    let steps = test
+        .variant_steps(test_variant)
+        .expect("Checked to be in line above");
     let mut result = test.before.clone();
-    let mut errors = Vec::new();
-    {
-        let mut error_handler = |pos, err| {
-            errors.push((pos, err));
-        };
-        let mut drag = LineDrag::start_drag(
-            &mut result,
-            &mut error_handler,
-            tier,
-            start_pos,
-            belt_direction,
-        );
-
-        match test_variant {
-            TestVariant::Normal => {
-                drag.interpolate_to(&mut error_handler, end_pos);
-            }
-            TestVariant::ForwardBack => {
-                run_forward_back(&mut drag, &mut error_handler, leftmost_pos, end_pos);
-            }
-            TestVariant::Wiggle => {
-                run_wiggle(&mut drag, &mut error_handler, start_pos, end_pos, &ray);
-            }
-            TestVariant::MegaWiggle => {
-                run_mega_wiggle(&mut drag, &mut error_handler, start_pos, end_pos, &ray);
-            }
-        }
-    }
+    let errors = run_drag_steps(&mut result, tier, start_pos, belt_direction, &steps)
+        .expect("Drag variants have no rotations");
 
     eprintln!();
-    (result, errors.into_iter().collect())
+    (result, errors)
 }
 
 fn toward_end_step(start_pos: TilePosition, end_pos: TilePosition, ray: &Ray) -> TileVec {
@@ -280,63 +244,14 @@ fn toward_end_step(start_pos: TilePosition, end_pos: TilePosition, ray: &Ray) ->
     }
 }
 
-fn run_wiggle(
-    drag: &mut LineDrag<'_>,
-    error_handler: &mut dyn FnMut(TilePosition, Error),
-    start_pos: TilePosition,
-    end_pos: TilePosition,
-    ray: &Ray,
-) {
-    let end_ray = ray.ray_position(end_pos);
-    let step = toward_end_step(start_pos, end_pos, ray);
-    let mut current_pos = start_pos;
-
-    while (ray.ray_position(current_pos) - end_ray).unsigned_abs() > 2 {
-        let forward_2 = current_pos + step * 2;
-        drag.interpolate_to(error_handler, forward_2);
-        let back_1 = current_pos + step;
-        drag.interpolate_to(error_handler, back_1);
-        current_pos = back_1
-    }
-    if ray.ray_position(current_pos) != end_ray {
-        drag.interpolate_to(error_handler, end_pos);
-    }
-}
-
-fn run_mega_wiggle(
-    drag: &mut LineDrag<'_>,
-    error_handler: &mut dyn FnMut(TilePosition, Error),
-    start_pos: TilePosition,
-    end_pos: TilePosition,
-    ray: &Ray,
-) {
-    let start_ray = ray.ray_position(start_pos);
-    let end_ray = ray.ray_position(end_pos);
-    let drag_distance = start_ray.abs_diff(end_ray);
-    let step = toward_end_step(start_pos, end_pos, ray);
-
-    for n in 1..drag_distance as i32 {
-        let forward_n = start_pos + step * n;
-        drag.interpolate_to(error_handler, forward_n);
-        drag.interpolate_to(error_handler, start_pos);
-    }
-    drag.interpolate_to(error_handler, end_pos);
-}
-
-fn run_forward_back(
-    drag: &mut LineDrag<'_>,
-    error_handler: &mut dyn FnMut(TilePosition, Error),
-    leftmost_pos: TilePosition,
-    end_pos: TilePosition,
-) {
-    drag.interpolate_to(error_handler, end_pos);
-    drag.interpolate_to(error_handler, leftmost_pos);
-}
-
 impl WorldImpl {
     fn max_x(&self) -> i32 {
         self.entities.keys().map(|pos| pos.x).max().unwrap_or(0)
     }
+
+    fn max_y(&self) -> i32 {
+        self.entities.keys().map(|pos| pos.y).max().unwrap_or(0)
+    }
 }
 
 #[derive(Deserialize)]
@@ -353,57 +268,136 @@ struct TestCaseSerde {
     not_reversible: bool,
     #[serde(default)]
     forward_back: bool,
+    start: Option<PositionRef>,
+    end: Option<PositionRef>,
+    direction: Option<Direction>,
+    /// 1-based, as in the grid
+    tier: Option<usize>,
+    trace: Option<Vec<String>>,
+    equivalent_to: Option<String>,
 }
-fn get_entities(serde_case: &TestCaseSerde) -> Result<TestCaseEntities, String> {
-    let (before, before_markers) = parse_world(&serde_case.before)
-        .map_err(|e| format!("Failed to parse 'before' entities: {}", e))?;
-
-    let (after, after_markers) = parse_world(&serde_case.after)
-        .map_err(|e| format!("Failed to parse 'after' entities: {}", e))?;
 
-    let serde_expected_errors = serde_case.expected_errors.clone();
+/// Anything not given explicitly is inferred from the grids: the drag starts at
+/// the `*` marker in `before`, or else the first entity in the left column,
+/// and ends at the right edge of that row. The direction and tier are those of
+/// the first belt-like entity in `after` on the way from the start to the end.
+fn get_entities(serde_case: &TestCaseSerde) -> Result<TestCaseEntities> {
+    let before =
+        parse_labeled_world(&serde_case.before).context("Failed to parse 'before' entities")?;
+    let after =
+        parse_labeled_world(&serde_case.after).context("Failed to parse 'after' entities")?;
+    let labels = merge_labels([&before, &after])?;
+
+    let grid_size: TileVec = vec2(
+        max(before.size.x, after.size.x),
+        max(before.size.y, after.size.y),
+    );
+    let resolve_in_grid = |key: &str, position: &PositionRef| {
+        let p = position.resolve(&labels)?;
+        if p.x < 0 || p.y < 0 || p.x >= grid_size.x || p.y >= grid_size.y {
+            bail!("'{key}' {p:?} is outside the grid");
+        }
+        Ok(p)
+    };
 
-    if after_markers.len() != serde_expected_errors.len() {
-        return Err("Expected number of markers to match number of expected errors".to_string());
+    if after.markers.len() != serde_case.expected_errors.len() {
+        bail!("Expected number of markers to match number of expected errors");
     }
 
-    let expected_errors = after_markers
-        .into_iter()
-        .zip(serde_expected_errors)
+    let expected_errors = after
+        .markers
+        .iter()
+        .copied()
+        .zip(serde_case.expected_errors.iter().cloned())
         .collect();
 
-    let start_pos = if !before_markers.is_empty() {
-        if before_markers.len() > 1 {
-            return Err("Expected exactly one marker for drag start position".to_string());
+    if before.markers.len() > 1 {
+        bail!("Expected exactly one marker for drag start position");
+    }
+    let start_marker = before.markers.first().copied();
+    let start_pos = match &serde_case.start {
+        Some(start) => {
+            let start = resolve_in_grid("start", start)?;
+            if let Some(marker) = start_marker
+                && marker != start
+            {
+                bail!("'start' {start:?} contradicts the marker at {marker:?} in 'before'");
+            }
+            start
         }
-        before_markers[0]
-    } else {
-        *after
-            .entities
-            .keys()
-            .find(|p| p.x == 0)
-            .expect("No first position found")
+        None => match start_marker {
+            Some(marker) => marker,
+            None => *after
+                .world
+                .entities
+                .keys()
+                .find(|p| p.x == 0)
+                .context("No first position found")?,
+        },
     };
 
-    let first_ent = after
-        .entities
-        .iter()
-        .filter(|(p, _)| p.y == start_pos.y && p.x >= start_pos.x)
-        .sorted_by_key(|(p, _)| p.x)
-        .find_map(|(_, ent)| BeltConnectable::try_from(ent).ok())
-        .expect("No belt found in drag row");
-    let tier = first_ent.tier();
+    let max_x = max(before.world.max_x(), after.world.max_x());
+    let max_y = max(before.world.max_y(), after.world.max_y());
+    let end_pos = match &serde_case.end {
+        Some(end) => resolve_in_grid("end", end)?,
+        None if serde_case.direction.is_some_and(|d| d.axis() == Axis::Y) => {
+            pos(start_pos.x, max_y)
+        }
+        None => pos(max_x, start_pos.y),
+    };
+    if end_pos.x != start_pos.x && end_pos.y != start_pos.y {
+        bail!("'end' {end_pos:?} is not in a straight line from the start {start_pos:?}");
+    }
+
+    let step = vec2(
+        (end_pos.x - start_pos.x).signum(),
+        (end_pos.y - start_pos.y).signum(),
+    );
+    let distance = (end_pos.x - start_pos.x).abs() + (end_pos.y - start_pos.y).abs();
+    let first_ent = (0..=distance)
+        .filter_map(|i| after.world.get(start_pos + step * i))
+        .find_map(|ent| BeltConnectable::try_from(ent).ok());
+
+    let direction = match serde_case.direction {
+        Some(direction) => direction,
+        None => first_ent
+            .as_ref()
+            .context("No belt found in drag row to infer 'direction' from")?
+            .direction(),
+    };
+    let tier = match serde_case.tier {
+        Some(tier) => parse_tier(tier)?,
+        None => first_ent
+            .as_ref()
+            .context("No belt found in drag row to infer 'tier' from")?
+            .tier(),
+    };
 
-    let max_x = max(before.max_x(), after.max_x());
-    let end_pos = pos(max_x, start_pos.y);
+    let ray = Ray::new(start_pos, direction);
+    if ray.snap(end_pos) != end_pos {
+        bail!("'end' {end_pos:?} is not in line with the start {start_pos:?} going {direction:?}");
+    }
 
-    let leftmost_pos = pos(0, start_pos.y);
+    // The first click places a belt going in the drag direction, with the drag tier
+    if let Some(BeltCollidable::Belt(belt)) = after.world.get(start_pos)
+        && before.world.get(start_pos) != after.world.get(start_pos)
+        && (belt.direction != direction || belt.tier != tier)
+    {
+        bail!(
+            "The belt placed at the start {start_pos:?} contradicts direction {direction:?} and tier {}",
+            tier.tier_index() + 1
+        );
+    }
 
-    let direction = first_ent.direction();
+    // The far edge of the grid behind the start
+    let leftmost_pos = match direction.axis() {
+        Axis::X => pos(if end_pos.x < start_pos.x { max_x } else { 0 }, start_pos.y),
+        Axis::Y => pos(start_pos.x, if end_pos.y < start_pos.y { max_y } else { 0 }),
+    };
 
     Ok(TestCaseEntities {
-        before,
-        after,
+        before: before.world,
+        after: after.world,
         tier,
         leftmost_pos,
         start_pos,
@@ -413,6 +407,22 @@ fn get_entities(serde_case: &TestCaseSerde) -> Result<TestCaseEntities, String>
     })
 }
 
+/// Labels from all grids; a label in several grids must be at the same position.
+fn merge_labels<'a>(
+    grids: impl IntoIterator<Item = &'a LabeledWorldParse>,
+) -> Result<HashMap<String, TilePosition>> {
+    let mut labels = HashMap::new();
+    for (label, &position) in grids.into_iter().flat_map(|grid| &grid.labels) {
+        if labels
+            .insert(label.clone(), position)
+            .is_some_and(|p| p != position)
+        {
+            bail!("Marker {label:?} is at different positions in different grids");
+        }
+    }
+    Ok(labels)
+}
+
 impl<'de> Deserialize<'de> for DragTestCase {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
@@ -424,7 +434,8 @@ impl<'de> Deserialize<'de> for DragTestCase {
         let not_reversible = serde_case.not_reversible;
         let forward_back = serde_case.forward_back;
 
-        let entities = get_entities(&serde_case).map_err(serde::de::Error::custom)?;
+        let entities =
+            get_entities(&serde_case).map_err(|e| serde::de::Error::custom(format!("{e:#}")))?;
         let after_for_reverse = serde_case
             .after_for_reverse
             .map(|s| {
@@ -433,6 +444,12 @@ impl<'de> Deserialize<'de> for DragTestCase {
             })
             .transpose()
             .map_err(|e: anyhow::Error| serde::de::Error::custom(e.to_string()))?;
+        let trace = serde_case
+            .trace
+            .as_deref()
+            .map(parse_trace)
+            .transpose()
+            .map_err(|e| serde::de::Error::custom(format!("{e:#}")))?;
 
         Ok(DragTestCase {
             name,
@@ -440,10 +457,674 @@ impl<'de> Deserialize<'de> for DragTestCase {
             after_for_reverse,
             not_reversible,
             forward_back,
+            trace,
+            equivalent_to: serde_case.equivalent_to,
+        })
+    }
+}
+
+/// A position in a test case: `[x, y]` grid coordinates, or a marker label.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(untagged)]
+pub enum PositionRef {
+    Coords([i32; 2]),
+    Label(String),
+}
+
+impl PositionRef {
+    pub fn resolve(&self, labels: &HashMap<String, TilePosition>) -> Result<TilePosition> {
+        match self {
+            PositionRef::Coords([x, y]) => Ok(pos(*x, *y)),
+            PositionRef::Label(label) => labels
+                .get(label)
+                .copied()
+                .with_context(|| format!("No marker labelled {label:?}")),
+        }
+    }
+}
+
+/// A cursor step as written in test cases and drag scripts, e.g.
+/// `- move_to: [x, y]`; see [`resolve_steps`].
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TestStepSerde {
+    MoveTo(PositionRef),
+    Rotate(PositionRef),
+    /// Forward two tiles, back one, until reaching the position.
+    Wiggle(PositionRef),
+}
+
+/// A drag made of explicit steps, possibly with rotations.
+/// Unlike [`DragTestCase`], nothing is inferred from the grids.
+#[derive(Debug, Clone)]
+pub struct StepsTestCase {
+    pub name: String,
+    pub before: WorldImpl,
+    pub after: WorldImpl,
+    pub start_pos: TilePosition,
+    pub belt_direction: Direction,
+    pub tier: BeltTier,
+    pub steps: Vec<DragStep>,
+    pub expected_errors: HashSet<(TilePosition, Error)>,
+    pub trace: Option<Vec<TraceStep>>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[serde(deny_unknown_fields)]
+struct StepsTestCaseSerde {
+    name: Option<String>,
+    before: String,
+    after: String,
+    start: PositionRef,
+    direction: Direction,
+    #[serde(default = "default_tier")]
+    tier: usize,
+    /// Written as `- move_to: [x, y]` rather than with YAML tags
+    #[serde(with = "serde_yaml::with::singleton_map_recursive")]
+    steps: Vec<TestStepSerde>,
+    #[serde(default)]
+    expected_errors: Vec<action::Error>,
+    trace: Option<Vec<String>>,
+}
+
+fn get_steps_test_case(serde_case: StepsTestCaseSerde) -> Result<StepsTestCase> {
+    let before = parse_labeled_world(&serde_case.before).context("Failed to parse 'before'")?;
+    let after = parse_labeled_world(&serde_case.after).context("Failed to parse 'after'")?;
+
+    let labels = merge_labels([&before, &after])?;
+
+    let expected_errors = errors_at_markers(&after, serde_case.expected_errors)?;
+    let start_pos = serde_case.start.resolve(&labels)?;
+    let steps = resolve_steps(start_pos, &serde_case.steps, &labels)?;
+
+    Ok(StepsTestCase {
+        name: serde_case.name.unwrap_or_else(|| "Unnamed".to_string()),
+        before: before.world,
+        after: after.world,
+        start_pos,
+        belt_direction: serde_case.direction,
+        tier: parse_tier(serde_case.tier)?,
+        steps,
+        expected_errors,
+        trace: serde_case.trace.as_deref().map(parse_trace).transpose()?,
+    })
+}
+
+/// Pairs the `*` markers in `after` with `expected_errors`, in reading order.
+fn errors_at_markers(
+    after: &LabeledWorldParse,
+    expected_errors: Vec<Error>,
+) -> Result<HashSet<(TilePosition, Error)>> {
+    if after.markers.len() != expected_errors.len() {
+        bail!("Expected number of markers to match number of expected errors");
+    }
+    Ok(after.markers.iter().copied().zip(expected_errors).collect())
+}
+
+/// Resolves labels and expands wiggles, with the cursor starting at
+/// `start_pos`.
+pub fn resolve_steps(
+    start_pos: TilePosition,
+    steps: &[TestStepSerde],
+    labels: &HashMap<String, TilePosition>,
+) -> Result<Vec<DragStep>> {
+    let mut cursor = start_pos;
+    let mut resolved = Vec::new();
+    for step in steps {
+        match step {
+            TestStepSerde::MoveTo(target) => {
+                cursor = target.resolve(labels)?;
+                resolved.push(DragStep::MoveTo(cursor));
+            }
+            TestStepSerde::Rotate(target) => {
+                cursor = target.resolve(labels)?;
+                resolved.push(DragStep::Rotate(cursor));
+            }
+            TestStepSerde::Wiggle(target) => {
+                let target = target.resolve(labels)?;
+                resolved.extend(wiggle_steps(cursor, target)?);
+                cursor = target;
+            }
+        }
+    }
+    Ok(resolved)
+}
+
+/// The cursor moves of the wiggle variant (see [`TestCaseEntities::variant_steps`]):
+/// two tiles forward and one back, until the end is reached.
+fn wiggle_steps(from: TilePosition, to: TilePosition) -> Result<Vec<DragStep>> {
+    let diff = to - from;
+    if diff.x != 0 && diff.y != 0 {
+        bail!("Cannot wiggle from {from:?} to {to:?}: not in a straight line");
+    }
+    let step = vec2(diff.x.signum(), diff.y.signum());
+    let distance = diff.x.abs() + diff.y.abs();
+
+    let mut steps = Vec::new();
+    let mut current = 0;
+    while distance - current > 2 {
+        steps.push(DragStep::MoveTo(from + step * (current + 2)));
+        steps.push(DragStep::MoveTo(from + step * (current + 1)));
+        current += 1;
+    }
+    if current != distance {
+        steps.push(DragStep::MoveTo(to));
+    }
+    Ok(steps)
+}
+
+impl<'de> Deserialize<'de> for StepsTestCase {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let serde_case = StepsTestCaseSerde::deserialize(deserializer)?;
+        get_steps_test_case(serde_case).map_err(|e| serde::de::Error::custom(format!("{e:#}")))
+    }
+}
+
+/// Runs `steps` as a single drag. Fails if a rotation doesn't happen.
+pub fn run_drag_steps(
+    world: &mut WorldImpl,
+    tier: BeltTier,
+    start_pos: TilePosition,
+    belt_direction: Direction,
+    steps: &[DragStep],
+) -> Result<HashSet<(TilePosition, Error)>> {
+    let (errors, rotated) = replay_drag_steps(world, tier, start_pos, belt_direction, steps);
+    if !rotated {
+        bail!("A rotation failed in {:?}", steps);
+    }
+    Ok(errors.into_iter().collect())
+}
+
+/// Runs `steps` as a single drag, returning its errors in the order it
+/// reported them, and false if it stopped at a rotation that failed.
+///
+/// For drags driven step by step from outside (e.g. over an FFI), which
+/// cannot hold on to a [`LineDrag`] as it borrows its world: they keep the
+/// world from before the drag and its steps, and replay them after every
+/// move. The replay is deterministic, so a replay reports the errors of the
+/// one before it first; only the rest are new.
+pub fn replay_drag_steps(
+    world: &mut WorldImpl,
+    tier: BeltTier,
+    start_pos: TilePosition,
+    belt_direction: Direction,
+    steps: &[DragStep],
+) -> (Vec<(TilePosition, Error)>, bool) {
+    let mut errors = Vec::new();
+    let mut error_handler = |pos, err| errors.push((pos, err));
+    let mut drag = LineDrag::start_drag(world, &mut error_handler, tier, start_pos, belt_direction);
+    for step in steps {
+        match *step {
+            DragStep::MoveTo(target) => {
+                drag.interpolate_to(&mut error_handler, target);
+            }
+            DragStep::Rotate(cursor) => {
+                let (new_drag, rotated) = drag.rotate(&mut error_handler, cursor);
+                if !rotated {
+                    return (errors, false);
+                }
+                drag = new_drag;
+            }
+        }
+    }
+    (errors, true)
+}
+
+/// The world after a cursor move of a drag, e.g. to animate it.
+#[derive(Debug, Clone)]
+pub struct DragFrame {
+    pub world: WorldImpl,
+    pub cursor: TilePosition,
+    /// The latest error the drag has reported, if any.
+    pub last_error: Option<(TilePosition, Error)>,
+}
+
+/// Like [`run_drag_steps`], but moves the cursor one tile at a time as a real
+/// cursor does (diagonally when off the drag line), capturing the world after
+/// the first click and after every move.
+pub fn record_drag_frames(
+    world: &WorldImpl,
+    tier: BeltTier,
+    start_pos: TilePosition,
+    belt_direction: Direction,
+    steps: &[DragStep],
+) -> Result<Vec<DragFrame>> {
+    let mut world = world.clone();
+    let last_error = RefCell::new(None);
+    let mut error_handler = |pos, err| *last_error.borrow_mut() = Some((pos, err));
+    let mut drag = LineDrag::start_drag(
+        &mut world,
+        &mut error_handler,
+        tier,
+        start_pos,
+        belt_direction,
+    );
+    let frame = |drag: &LineDrag<'_>, cursor| DragFrame {
+        world: drag.world().clone(),
+        cursor,
+        last_error: last_error.borrow().clone(),
+    };
+
+    let mut cursor = start_pos;
+    let mut frames = vec![frame(&drag, cursor)];
+    for step in steps {
+        match *step {
+            DragStep::MoveTo(target) => {
+                while cursor != target {
+                    let diff = target - cursor;
+                    cursor += vec2(diff.x.signum(), diff.y.signum());
+                    drag.interpolate_to(&mut error_handler, cursor);
+                    frames.push(frame(&drag, cursor));
+                }
+            }
+            DragStep::Rotate(target) => {
+                let (new_drag, rotated) = drag.rotate(&mut error_handler, target);
+                if !rotated {
+                    bail!("Rotation failed at cursor {:?}", target);
+                }
+                drag = new_drag;
+                cursor = target;
+                frames.push(frame(&drag, cursor));
+            }
+        }
+    }
+    Ok(frames)
+}
+
+/// Several drags applied one after another, starting from `before`.
+#[derive(Debug, Clone)]
+pub struct ScenarioTestCase {
+    pub name: String,
+    pub before: WorldImpl,
+    pub drags: Vec<ScenarioDrag>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ScenarioDrag {
+    pub start_pos: TilePosition,
+    pub belt_direction: Direction,
+    pub tier: BeltTier,
+    pub steps: Vec<DragStep>,
+    /// The world and errors expected after this drag, if checked.
+    pub expected: Option<(WorldImpl, HashSet<(TilePosition, Error)>)>,
+}
+
+/// The final `after` is that of the last drag.
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[serde(deny_unknown_fields)]
+struct ScenarioTestCaseSerde {
+    name: Option<String>,
+    before: String,
+    drags: Vec<ScenarioDragSerde>,
+    after: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[serde(deny_unknown_fields)]
+struct ScenarioDragSerde {
+    start: PositionRef,
+    direction: Direction,
+    /// 1-based, as in the grid
+    #[serde(default = "default_tier")]
+    tier: usize,
+    /// Shorthand for a single `move_to` step
+    end: Option<PositionRef>,
+    #[serde(default, with = "serde_yaml::with::singleton_map_recursive")]
+    steps: Vec<TestStepSerde>,
+    /// Intermediate state; not allowed on the last drag
+    after: Option<String>,
+    /// At the `*` markers in this drag's `after`
+    #[serde(default)]
+    expected_errors: Vec<action::Error>,
+}
+
+/// The tier for a `tier` key of a test case.
+fn parse_tier(tier: usize) -> Result<BeltTier> {
+    BeltTier::from_number(tier)
+        .with_context(|| format!("'tier' {tier} is not between 1 and {}", BELT_TIERS.len()))
+}
+
+fn default_tier() -> usize {
+    1
+}
+
+fn get_scenario_test_case(serde_case: ScenarioTestCaseSerde) -> Result<ScenarioTestCase> {
+    let before = parse_labeled_world(&serde_case.before).context("Failed to parse 'before'")?;
+    let final_after = parse_labeled_world(&serde_case.after).context("Failed to parse 'after'")?;
+    let num_drags = serde_case.drags.len();
+    if num_drags == 0 {
+        bail!("A scenario needs at least one drag");
+    }
+
+    let mut afters = Vec::new();
+    for (i, drag) in serde_case.drags.iter().enumerate() {
+        let after = match &drag.after {
+            Some(_) if i == num_drags - 1 => {
+                bail!("The last drag's 'after' is the scenario's 'after'")
+            }
+            Some(after) => Some(
+                parse_labeled_world(after)
+                    .with_context(|| format!("Failed to parse 'after' of drag {}", i + 1))?,
+            ),
+            None if i == num_drags - 1 => Some(final_after.clone()),
+            None => None,
+        };
+        afters.push(after);
+    }
+
+    let labels = merge_labels([&before].into_iter().chain(afters.iter().flatten()))?;
+
+    let drags = serde_case
+        .drags
+        .into_iter()
+        .zip(afters)
+        .enumerate()
+        .map(|(i, (drag, after))| {
+            get_scenario_drag(drag, after, &labels).with_context(|| format!("In drag {}", i + 1))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(ScenarioTestCase {
+        name: serde_case.name.unwrap_or_else(|| "Unnamed".to_string()),
+        before: before.world,
+        drags,
+    })
+}
+
+fn get_scenario_drag(
+    drag: ScenarioDragSerde,
+    after: Option<LabeledWorldParse>,
+    labels: &HashMap<String, TilePosition>,
+) -> Result<ScenarioDrag> {
+    let start_pos = drag.start.resolve(labels)?;
+    let mut steps = resolve_steps(start_pos, &drag.steps, labels)?;
+    match (drag.end, steps.is_empty()) {
+        (Some(end), true) => steps.push(DragStep::MoveTo(end.resolve(labels)?)),
+        (None, false) => {}
+        _ => bail!("Expected exactly one of 'end' and 'steps'"),
+    }
+    let tier = parse_tier(drag.tier)?;
+    let expected = match after {
+        Some(after) => {
+            let errors = errors_at_markers(&after, drag.expected_errors)?;
+            Some((after.world, errors))
+        }
+        None if drag.expected_errors.is_empty() => None,
+        None => bail!("'expected_errors' needs an 'after' to mark them in"),
+    };
+    Ok(ScenarioDrag {
+        start_pos,
+        belt_direction: drag.direction,
+        tier,
+        steps,
+        expected,
+    })
+}
+
+impl<'de> Deserialize<'de> for ScenarioTestCase {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let serde_case = ScenarioTestCaseSerde::deserialize(deserializer)?;
+        get_scenario_test_case(serde_case).map_err(|e| serde::de::Error::custom(format!("{e:#}")))
+    }
+}
+
+pub fn check_scenario_test_case_all_transforms(test: &ScenarioTestCase) -> Result<()> {
+    for (i, transform) in Transform::all_unique_transforms().iter().enumerate() {
+        let mut world = test.before.transform_world(transform);
+        for (j, drag) in test.drags.iter().enumerate() {
+            let context = || format!("[transform {}] [drag {}]", i, j + 1);
+            let before = world.clone();
+            let actual_errors = run_drag_steps(
+                &mut world,
+                drag.tier,
+                transform.transform_position(drag.start_pos),
+                transform.transform_direction(drag.belt_direction),
+                &transform_steps(&drag.steps, transform),
+            )
+            .with_context(context)?;
+            if let Some((expected_world, expected_errors)) = &drag.expected {
+                check_drag_result(
+                    &before,
+                    &expected_world.transform_world(transform),
+                    &transform_errors(expected_errors, transform),
+                    &world,
+                    &actual_errors,
+                )
+                .with_context(context)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Any kind of case in the YAML test suite.
+pub enum SuiteCase {
+    Drag(DragTestCase),
+    Steps(StepsTestCase),
+    Scenario(ScenarioTestCase),
+}
+
+impl SuiteCase {
+    /// Parses a case the way `build.rs` decides which runner to use.
+    pub fn from_value(value: &serde_yaml::Value) -> Result<Self> {
+        Ok(if value.get("drags").is_some() {
+            SuiteCase::Scenario(serde_yaml::from_value(value.clone())?)
+        } else if value.get("steps").is_some() {
+            SuiteCase::Steps(serde_yaml::from_value(value.clone())?)
+        } else {
+            SuiteCase::Drag(serde_yaml::from_value(value.clone())?)
+        })
+    }
+
+    /// Checks every variant `build.rs` generates a test for.
+    pub fn check_all_variants(&self) -> Result<()> {
+        match self {
+            SuiteCase::Drag(test_case) => {
+                for (reverse, variant) in test_case.variants() {
+                    check_test_case_all_transforms(test_case, reverse, variant)?;
+                }
+                Ok(())
+            }
+            SuiteCase::Steps(test_case) => check_steps_test_case_all_transforms(test_case),
+            SuiteCase::Scenario(test_case) => check_scenario_test_case_all_transforms(test_case),
+        }
+    }
+}
+
+impl TestCaseEntities {
+    /// The cursor moves the suite's tests make for `variant`, as steps.
+    pub fn variant_steps(&self, variant: TestVariant) -> Result<Vec<DragStep>> {
+        Ok(match variant {
+            TestVariant::Normal => vec![DragStep::MoveTo(self.end_pos)],
+            TestVariant::ForwardBack => vec![
+                DragStep::MoveTo(self.end_pos),
+                DragStep::MoveTo(self.leftmost_pos),
+            ],
+            TestVariant::Wiggle => wiggle_steps(self.start_pos, self.end_pos)?,
+            TestVariant::MegaWiggle => {
+                let ray = Ray::new(self.start_pos, self.belt_direction);
+                let step = toward_end_step(self.start_pos, self.end_pos, &ray);
+                let distance = ray
+                    .ray_position(self.start_pos)
+                    .abs_diff(ray.ray_position(self.end_pos));
+                (1..distance as i32)
+                    .flat_map(|n| {
+                        [
+                            DragStep::MoveTo(self.start_pos + step * n),
+                            DragStep::MoveTo(self.start_pos),
+                        ]
+                    })
+                    .chain([DragStep::MoveTo(self.end_pos)])
+                    .collect()
+            }
         })
     }
 }
 
+impl DragTestCase {
+    /// The `(reverse, variant)` pairs `build.rs` generates tests for.
+    pub fn variants(&self) -> Vec<(bool, TestVariant)> {
+        let variants = if self.forward_back {
+            vec![TestVariant::ForwardBack]
+        } else {
+            vec![
+                TestVariant::Normal,
+                TestVariant::Wiggle,
+                TestVariant::MegaWiggle,
+            ]
+        };
+        variants
+            .into_iter()
+            .flat_map(|variant| [(false, variant), (true, variant)])
+            .filter(|&(reverse, _)| !reverse || !self.not_reversible)
+            .collect()
+    }
+}
+
+fn transform_steps(steps: &[DragStep], transform: &Transform) -> Vec<DragStep> {
+    steps
+        .iter()
+        .map(|step| match *step {
+            DragStep::MoveTo(p) => DragStep::MoveTo(transform.transform_position(p)),
+            DragStep::Rotate(p) => DragStep::Rotate(transform.transform_position(p)),
+        })
+        .collect()
+}
+
+fn transform_errors(
+    errors: &HashSet<(TilePosition, Error)>,
+    transform: &Transform,
+) -> HashSet<(TilePosition, Error)> {
+    errors
+        .iter()
+        .map(|(p, e)| (transform.transform_position(*p), e.clone()))
+        .collect()
+}
+
+/// Fails with a printout of all worlds if the result isn't as expected.
+fn check_drag_result(
+    before: &WorldImpl,
+    expected_world: &WorldImpl,
+    expected_errors: &HashSet<(TilePosition, Error)>,
+    result: &WorldImpl,
+    actual_errors: &HashSet<(TilePosition, Error)>,
+) -> Result<()> {
+    if result == expected_world && actual_errors == expected_errors {
+        return Ok(());
+    }
+    bail!(DragMismatch {
+        before: before.clone(),
+        expected: expected_world.clone(),
+        actual: result.clone(),
+        expected_errors: expected_errors.clone(),
+        actual_errors: actual_errors.clone(),
+    });
+}
+
+/// A drag whose result doesn't match the test case. Checks fail with this,
+/// wrapped in the failing transform and variant as context; so it can be
+/// recovered with `anyhow::Error::downcast_ref`, e.g. to render it.
+#[derive(Debug, Clone)]
+pub struct DragMismatch {
+    pub before: WorldImpl,
+    pub expected: WorldImpl,
+    pub actual: WorldImpl,
+    pub expected_errors: HashSet<(TilePosition, Error)>,
+    pub actual_errors: HashSet<(TilePosition, Error)>,
+}
+
+impl DragMismatch {
+    pub fn bounds(&self) -> BoundingBox {
+        self.before
+            .bounds()
+            .union(&self.expected.bounds())
+            .union(&self.actual.bounds())
+    }
+}
+
+impl std::fmt::Display for DragMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let bounds = self.bounds();
+        let error_positions =
+            |errors: &HashSet<(TilePosition, Error)>| errors.iter().map(|e| e.0).collect_vec();
+        write!(
+            f,
+            r#"
+Before:
+
+{}
+
+Expected:
+
+{}
+
+Got:
+
+{}
+"#,
+            print_world(&self.before, bounds, &[]),
+            print_world(
+                &self.expected,
+                bounds,
+                &error_positions(&self.expected_errors)
+            ),
+            print_world(&self.actual, bounds, &error_positions(&self.actual_errors))
+        )?;
+        if self.actual_errors != self.expected_errors {
+            write!(
+                f,
+                "\nExpected errors:\n{:?}\nGot errors:\n{:?}\n",
+                self.expected_errors, self.actual_errors
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for DragMismatch {}
+
+pub fn check_steps_test_case_all_transforms(test: &StepsTestCase) -> Result<()> {
+    for (i, transform) in Transform::all_unique_transforms().iter().enumerate() {
+        let before = test.before.transform_world(transform);
+        let mut result = before.clone();
+        let (actual_errors, trace) = with_trace(|| {
+            run_drag_steps(
+                &mut result,
+                test.tier,
+                transform.transform_position(test.start_pos),
+                transform.transform_direction(test.belt_direction),
+                &transform_steps(&test.steps, transform),
+            )
+        });
+        let actual_errors = actual_errors.with_context(|| format!("[transform {}]", i))?;
+        check_drag_result(
+            &before,
+            &test.after.transform_world(transform),
+            &transform_errors(&test.expected_errors, transform),
+            &result,
+            &actual_errors,
+        )
+        .with_context(|| format!("[transform {}]", i))?;
+        if let Some(expected_trace) = &test.trace {
+            let expected_trace = expected_trace
+                .iter()
+                .map(|s| s.transform(transform))
+                .collect_vec();
+            check_trace(&expected_trace, &trace).with_context(|| format!("[transform {}]", i))?;
+        }
+    }
+    Ok(())
+}
+
 /**
 Format: [tier][direction][type]
 - tier: 1-indexed belt tier (default 1)
@@ -476,10 +1157,7 @@ fn parse_word(input: &str) -> Result<Option<BeltCollidable>> {
     })
     .unwrap_or(1) as usize;
 
-    let tier = *tier_int
-        .checked_sub(1)
-        .and_then(|i| BELT_TIERS.get(i))
-        .context("")?;
+    let tier = BeltTier::from_number(tier_int).context("")?;
 
     let direction = match chars.next() {
         Some('<') => Direction::West,
@@ -502,15 +1180,50 @@ fn parse_word(input: &str) -> Result<Option<BeltCollidable>> {
 pub type WorldParse = (WorldImpl, Vec<TilePosition>);
 
 pub fn parse_world(input: &str) -> Result<WorldParse> {
+    let parse = parse_labeled_world(input)?;
+    Ok((parse.world, parse.markers))
+}
+
+/// A parsed grid, with its markers.
+#[derive(Debug, Clone)]
+pub struct LabeledWorldParse {
+    pub world: WorldImpl,
+    /// Positions of `*` markers, in reading order.
+    pub markers: Vec<TilePosition>,
+    /// Positions of `[label]` markers.
+    pub labels: HashMap<String, TilePosition>,
+    /// Number of columns (of the widest row) and rows in the grid.
+    pub size: TileVec,
+}
+
+/// Like [`parse_world`], but words may also be prefixed with `[label]` to name
+/// their position, e.g. `[a]_` or `[pivot]2>`.
+pub fn parse_labeled_world(input: &str) -> Result<LabeledWorldParse> {
     let mut world = WorldImpl::new();
     let mut markers = Vec::new();
+    let mut labels = HashMap::new();
+    let mut size = vec2(0, 0);
     for (y, line) in input.lines().enumerate() {
+        size.y = y as i32 + 1;
         let words = line.split_whitespace();
         for (x, mut word) in words.enumerate() {
+            size.x = max(size.x, x as i32 + 1);
             let pos = TilePosition::new(x as i32, y as i32);
-            while word.starts_with('*') {
-                markers.push(pos);
-                word = &word[1..];
+            loop {
+                if let Some(rest) = word.strip_prefix('*') {
+                    markers.push(pos);
+                    word = rest;
+                } else if let Some(rest) = word.strip_prefix('[') {
+                    let (label, rest) = rest
+                        .split_once(']')
+                        .with_context(|| format!("Unclosed marker label in {word:?}"))?;
+                    if labels.insert(label.to_string(), pos).is_some() {
+                        bail!("Duplicate marker label {label:?}");
+                    }
+                    word = rest;
+                } else {
+                    break;
+                }
             }
 
             if let Some(entity) = parse_word(word)? {
@@ -518,7 +1231,12 @@ pub fn parse_world(input: &str) -> Result<WorldParse> {
             }
         }
     }
-    Ok((world, markers))
+    Ok(LabeledWorldParse {
+        world,
+        markers,
+        labels,
+        size,
+    })
 }
 
 fn get_dir_char(direction: Direction) -> char {
@@ -530,7 +1248,8 @@ fn get_dir_char(direction: Direction) -> char {
     }
 }
 
-fn print_entity(entity: &BeltCollidable) -> String {
+/// How `entity` is written in grids, e.g. `2>i`.
+pub fn print_entity(entity: &BeltCollidable) -> String {
     match entity {
         BeltCollidable::Belt(Belt { direction, tier }) => {
             let tier_num = tier.tier_index() + 1;
@@ -598,15 +1317,10 @@ pub fn print_world(world: &WorldImpl, bounds: BoundingBox, markers: &[TilePositi
                 result.push(' ');
             }
             let pos = pos(x, y);
-            if let Some(entity) = world.get(pos) {
-                let mut entity_str = print_entity(entity);
-                if markers.contains(&pos) {
-                    entity_str.insert(0, '*');
-                }
-                result.push_str(&format!("{:<4}", entity_str));
-            } else {
-                result.push_str("_   ");
-            }
+            let entity_str = world.get(pos).map_or("_".to_string(), print_entity);
+            let marker_count = markers.iter().filter(|&&m| m == pos).count();
+            let marked_str = "*".repeat(marker_count) + &entity_str;
+            result.push_str(&format!("{:<4}", marked_str));
         }
         // Trim trailing whitespace from the line
         while result.ends_with(' ') {
@@ -616,10 +1330,41 @@ pub fn print_world(world: &WorldImpl, bounds: BoundingBox, markers: &[TilePositi
 
     result
 }
+
+/// Re-spaces a printed grid so each column is only as wide as its widest word.
+pub fn align_columns(grid: &str) -> String {
+    let rows: Vec<Vec<&str>> = grid
+        .lines()
+        .map(|l| l.split_whitespace().collect())
+        .collect();
+    let num_columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+    let widths: Vec<usize> = (0..num_columns)
+        .map(|x| {
+            rows.iter()
+                .filter_map(|r| r.get(x))
+                .map(|w| w.len())
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    rows.iter()
+        .map(|row| {
+            row.iter()
+                .zip(&widths)
+                .map(|(word, width)| format!("{word:<width$}"))
+                .join(" ")
+                .trim_end()
+                .to_string()
+        })
+        .join("\n")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    use crate::RED_BELT;
     use crate::entity::*;
 
     #[test]
@@ -791,4 +1536,165 @@ after: "2>\t^\tX"
         let (back_to_world, _) = parse_world(&output).expect("Failed to parse world");
         assert_eq!(back_to_world, world);
     }
+
+    #[test]
+    fn test_parse_labeled_world() {
+        let parsed = parse_labeled_world("[a]_ *[b]>\n_ [c]*X").unwrap();
+        assert_eq!(parsed.labels["a"], pos(0, 0));
+        assert_eq!(parsed.labels["b"], pos(1, 0));
+        assert_eq!(parsed.labels["c"], pos(1, 1));
+        assert_eq!(parsed.markers, vec![pos(1, 0), pos(1, 1)]);
+        assert_eq!(parsed.world.entities.len(), 2);
+
+        assert!(parse_labeled_world("[a_ >").is_err());
+        assert!(parse_labeled_world("[a]_ [a]>").is_err());
+    }
+
+    #[test]
+    fn test_parse_steps() {
+        let yaml = r#"
+before: "[start]_ _ _ _ [end]_"
+after: "> > > > >"
+start: start
+direction: east
+steps:
+  - wiggle: end
+  - rotate: [4, 1]
+"#;
+        let test_case: StepsTestCase = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(test_case.start_pos, pos(0, 0));
+        let moves = [2, 1, 3, 2, 4].map(|x| DragStep::MoveTo(pos(x, 0)));
+        assert_eq!(
+            test_case.steps,
+            moves
+                .into_iter()
+                .chain([DragStep::Rotate(pos(4, 1))])
+                .collect_vec()
+        );
+
+        assert_eq!(test_case.tier, BELT_TIERS[0]);
+
+        let red = yaml.replace("direction: east", "direction: east\ntier: 2");
+        let test_case: StepsTestCase = serde_yaml::from_str(&red).unwrap();
+        assert_eq!(test_case.tier, RED_BELT);
+        let bad_tier = yaml.replace("direction: east", "direction: east\ntier: 4");
+        assert!(serde_yaml::from_str::<StepsTestCase>(&bad_tier).is_err());
+
+        let unknown_label = yaml.replace("start: start", "start: nowhere");
+        assert!(serde_yaml::from_str::<StepsTestCase>(&unknown_label).is_err());
+    }
+
+    #[test]
+    fn test_explicit_drag_keys_are_validated() {
+        let parse = |yaml: &str| serde_yaml::from_str::<DragTestCase>(yaml);
+        let base = "before: \"*_ _ [end]_\"\nafter: \"> > >\"\n";
+
+        let err = parse(&format!("{base}end: end\ntier: 2")).unwrap_err();
+        assert!(err.to_string().contains("contradicts"), "{err}");
+        let err = parse(&format!("{base}start: [1, 0]")).unwrap_err();
+        assert!(err.to_string().contains("marker"), "{err}");
+        let err = parse(&format!("{base}end: [5, 0]")).unwrap_err();
+        assert!(err.to_string().contains("outside the grid"), "{err}");
+        let err = parse(&format!("{base}end: end\ndirection: north")).unwrap_err();
+        assert!(err.to_string().contains("not in line"), "{err}");
+
+        let test_case = parse(&format!("{base}end: end\ndirection: east")).unwrap();
+        assert_eq!(test_case.entities.end_pos, pos(2, 0));
+        assert_eq!(test_case.entities.tier, BELT_TIERS[0]);
+    }
+
+    #[test]
+    fn test_scenario_checks_every_drag() {
+        let yaml = r#"
+before: "[a]_ _ [b]_"
+drags:
+  - start: a
+    direction: east
+    end: b
+    after: "> > >"
+  - start: b
+    direction: west
+    end: a
+after: "< < <"
+"#;
+        let scenario: ScenarioTestCase = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(scenario.drags.len(), 2);
+        check_scenario_test_case_all_transforms(&scenario).unwrap();
+
+        let wrong_intermediate = yaml.replace("after: \"> > >\"", "after: \"< < <\"");
+        let scenario: ScenarioTestCase = serde_yaml::from_str(&wrong_intermediate).unwrap();
+        let err = check_scenario_test_case_all_transforms(&scenario).unwrap_err();
+        assert!(format!("{err:#}").contains("[drag 1]"), "{err:#}");
+
+        let end_and_steps = yaml.replace("end: a", "end: a\n    steps: [move_to: a]");
+        assert!(serde_yaml::from_str::<ScenarioTestCase>(&end_and_steps).is_err());
+    }
+
+    #[test]
+    fn test_replay_drag_steps_stops_at_failed_rotation() {
+        let (before, _) = parse_world("_ _ X X X X X _ _ _\n_ _ _ _ _ _ _ _ _ _").unwrap();
+        let run = |steps: &[DragStep]| {
+            let mut world = before.clone();
+            replay_drag_steps(&mut world, BELT_TIERS[0], pos(0, 0), Direction::East, steps)
+        };
+        let too_far = (pos(7, 0), Error::TooFarToConnect);
+
+        let moves = [DragStep::MoveTo(pos(8, 0)), DragStep::MoveTo(pos(9, 0))];
+        assert_eq!(run(&moves), (vec![too_far.clone()], true));
+        // On the drag line, so no rotation
+        let on_line = [moves[0], DragStep::Rotate(pos(9, 0)), moves[1]];
+        assert_eq!(run(&on_line), (vec![too_far.clone()], false));
+        assert!(
+            run_drag_steps(
+                &mut before.clone(),
+                BELT_TIERS[0],
+                pos(0, 0),
+                Direction::East,
+                &on_line
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_drag_frames_match_variant_runs() {
+        let test_case: DragTestCase =
+            serde_yaml::from_str("before: _ X _ _ _\nafter: \"> >i X >o >\"").unwrap();
+        let test = &test_case.entities;
+        for variant in [
+            TestVariant::Normal,
+            TestVariant::Wiggle,
+            TestVariant::MegaWiggle,
+        ] {
+            let steps = test.variant_steps(variant).unwrap();
+            let frames = record_drag_frames(
+                &test.before,
+                test.tier,
+                test.start_pos,
+                test.belt_direction,
+                &steps,
+            )
+            .unwrap();
+            let (expected, _) = run_test_case(test, variant);
+            let last = frames.last().unwrap();
+            assert_eq!(last.world, expected, "{variant:?}");
+            assert_eq!(last.cursor, test.end_pos);
+            assert!(last.last_error.is_none());
+        }
+
+        let steps = test.variant_steps(TestVariant::Wiggle).unwrap();
+        let frames = record_drag_frames(
+            &test.before,
+            test.tier,
+            test.start_pos,
+            test.belt_direction,
+            &steps,
+        )
+        .unwrap();
+        // The first click, then forward 2 and back 1 until the end: 2, 1, 3, 2, 4
+        assert_eq!(
+            frames.iter().map(|f| f.cursor.x).collect_vec(),
+            [0, 1, 2, 1, 2, 3, 2, 3, 4]
+        );
+    }
 }
@@ -0,0 +1,193 @@
+//! Entities in the shape the mod's harness uses (`TestEntity` in
+//! `prototype_mod/common/test_entity.ts`), for tools that drive the engine
+//! from outside Rust: directions are `0` to `3` (north, east, south, west),
+//! and tiers are 1-based, as in grids.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    BELT_TIERS, Belt, BeltCollidable, BeltConnectable, BeltConnectableTrait, BeltTier,
+    CollidingEntityOrTile, Direction, ImpassableTile, LoaderLike, Splitter, UndergroundBelt,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IoType {
+    Input,
+    Output,
+}
+
+impl IoType {
+    pub fn is_input(self) -> bool {
+        self == IoType::Input
+    }
+}
+
+impl From<bool> for IoType {
+    fn from(is_input: bool) -> Self {
+        if is_input {
+            IoType::Input
+        } else {
+            IoType::Output
+        }
+    }
+}
+
+/// Only the entities the engine models; the mod's ghosts and trees have no
+/// counterpart here.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum TestEntity {
+    Belt {
+        direction: u8,
+        #[serde(default = "first_tier")]
+        tier: usize,
+    },
+    UndergroundBelt {
+        direction: u8,
+        #[serde(default = "first_tier")]
+        tier: usize,
+        #[serde(rename = "ioType")]
+        io_type: IoType,
+    },
+    Splitter {
+        direction: u8,
+        #[serde(default = "first_tier")]
+        tier: usize,
+    },
+    Loader {
+        direction: u8,
+        #[serde(default = "first_tier")]
+        tier: usize,
+        #[serde(rename = "ioType")]
+        io_type: IoType,
+    },
+    Obstacle,
+    Impassable,
+}
+
+fn first_tier() -> usize {
+    1
+}
+
+impl TestEntity {
+    /// Fails if the direction or tier is out of range.
+    pub fn to_entity(&self) -> Result<BeltCollidable> {
+        Ok(match *self {
+            TestEntity::Belt { direction, tier } => {
+                Belt::new(parse_direction(direction)?, parse_tier(tier)?).into()
+            }
+            TestEntity::UndergroundBelt {
+                direction,
+                tier,
+                io_type,
+            } => UndergroundBelt::new(
+                parse_direction(direction)?,
+                io_type.is_input(),
+                parse_tier(tier)?,
+            )
+            .into(),
+            TestEntity::Splitter { direction, tier } => {
+                Splitter::new(parse_direction(direction)?, parse_tier(tier)?).into()
+            }
+            TestEntity::Loader {
+                direction,
+                tier,
+                io_type,
+            } => LoaderLike::new(
+                parse_direction(direction)?,
+                io_type.is_input(),
+                parse_tier(tier)?,
+            )
+            .into(),
+            TestEntity::Obstacle => CollidingEntityOrTile.into(),
+            TestEntity::Impassable => ImpassableTile.into(),
+        })
+    }
+}
+
+impl From<&BeltCollidable> for TestEntity {
+    fn from(entity: &BeltCollidable) -> Self {
+        let belt = BeltConnectable::try_from(entity).ok();
+        let direction = belt.as_ref().map_or(0, |b| b.direction() as u8);
+        let tier = belt.as_ref().map_or(1, |b| b.tier().tier_index() + 1);
+        match entity {
+            BeltCollidable::Belt(_) => TestEntity::Belt { direction, tier },
+            BeltCollidable::UndergroundBelt(ug) => TestEntity::UndergroundBelt {
+                direction,
+                tier,
+                io_type: ug.is_input.into(),
+            },
+            BeltCollidable::Splitter(_) => TestEntity::Splitter { direction, tier },
+            BeltCollidable::LoaderLike(loader) => TestEntity::Loader {
+                direction,
+                tier,
+                io_type: loader.is_input.into(),
+            },
+            BeltCollidable::CollidingEntityOrTile(_) => TestEntity::Obstacle,
+            BeltCollidable::ImpassableTile(_) => TestEntity::Impassable,
+        }
+    }
+}
+
+/// `0` to `3`: north, east, south, west.
+pub fn parse_direction(direction: u8) -> Result<Direction> {
+    Direction::from_ordinal(direction)
+        .with_context(|| format!("Direction must be 0 to 3, got {direction}"))
+}
+
+/// 1-based, as in grids.
+pub fn parse_tier(tier: usize) -> Result<BeltTier> {
+    BeltTier::from_number(tier)
+        .with_context(|| format!("Tier must be 1 to {}, got {tier}", BELT_TIERS.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RED_BELT;
+    use serde_json::json;
+
+    #[test]
+    fn test_entity_round_trip() {
+        let json =
+            json!({ "kind": "underground-belt", "direction": 3, "tier": 2, "ioType": "input" });
+        let entity: TestEntity = serde_json::from_value(json.clone()).unwrap();
+        let built = entity.to_entity().unwrap();
+        assert_eq!(
+            built,
+            UndergroundBelt::new(Direction::West, true, RED_BELT).into()
+        );
+        assert_eq!(
+            serde_json::to_value(TestEntity::from(&built)).unwrap(),
+            json
+        );
+
+        let obstacle: TestEntity = serde_json::from_value(json!({ "kind": "obstacle" })).unwrap();
+        assert_eq!(obstacle.to_entity().unwrap(), CollidingEntityOrTile.into());
+    }
+
+    #[test]
+    fn test_entity_checks_ranges() {
+        let belt = |direction, tier| TestEntity::Belt { direction, tier }.to_entity();
+        assert_eq!(
+            belt(1, 1).unwrap(),
+            Belt::new(Direction::East, BELT_TIERS[0]).into()
+        );
+        let err = belt(4, 1).unwrap_err();
+        assert_eq!(err.to_string(), "Direction must be 0 to 3, got 4");
+        let err = belt(0, 0).unwrap_err();
+        assert_eq!(err.to_string(), "Tier must be 1 to 3, got 0");
+        // Tier defaults to the first, as in grids
+        let default_tier: TestEntity =
+            serde_json::from_value(json!({ "kind": "belt", "direction": 0 })).unwrap();
+        assert_eq!(
+            default_tier,
+            TestEntity::Belt {
+                direction: 0,
+                tier: 1
+            }
+        );
+    }
+}
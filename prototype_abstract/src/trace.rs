@@ -0,0 +1,165 @@
+//! Golden action traces: the decisions a drag made, step by step.
+//!
+//! Comparing only the final world hides _how_ it was reached (e.g. extending
+//! an underground then mining it, versus creating it once). A trace has one
+//! line per drag step:
+//!
+//! ```text
+//! (x, y) DragEnd + Tile -> Action, Error
+//! ```
+//!
+//! where `(x, y)` is the tile stepped onto, and the rest is the step's
+//! [`StepRule`]. The format is plain text so traces from the TypeScript port
+//! can be diffed against these directly.
+
+use std::cell::RefCell;
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::{Context, Result, bail};
+use itertools::Itertools;
+
+use crate::coverage::StepRule;
+use crate::{TilePosition, Transform, pos};
+
+thread_local! {
+    static RECORDER: RefCell<Option<Vec<TraceStep>>> = const { RefCell::new(None) };
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceStep {
+    pub position: TilePosition,
+    /// The [`StepRule`], as displayed.
+    pub rule: String,
+}
+
+impl TraceStep {
+    pub fn transform(&self, transform: &Transform) -> Self {
+        Self {
+            position: transform.transform_position(self.position),
+            rule: self.rule.clone(),
+        }
+    }
+}
+
+impl fmt::Display for TraceStep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "({}, {}) {}",
+            self.position.x, self.position.y, self.rule
+        )
+    }
+}
+
+impl FromStr for TraceStep {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (position, rule) = s
+            .trim()
+            .strip_prefix('(')
+            .and_then(|s| s.split_once(')'))
+            .context("Trace step must start with '(x, y)'")?;
+        let (x, y) = position
+            .split(',')
+            .map(|c| c.trim().parse::<i32>())
+            .collect_tuple()
+            .context("Trace step must start with '(x, y)'")?;
+        Ok(Self {
+            position: pos(x?, y?),
+            rule: rule.split_whitespace().join(" "),
+        })
+    }
+}
+
+/// Runs `f`, recording the steps of all drags on this thread meanwhile.
+pub fn with_trace<R>(f: impl FnOnce() -> R) -> (R, Vec<TraceStep>) {
+    let outer = RECORDER.with(|r| r.replace(Some(Vec::new())));
+    let result = f();
+    let trace = RECORDER.with(|r| r.replace(outer)).unwrap_or_default();
+    (result, trace)
+}
+
+pub(crate) fn record_step(position: TilePosition, rule: &StepRule) {
+    RECORDER.with(|r| {
+        if let Some(trace) = r.borrow_mut().as_mut() {
+            trace.push(TraceStep {
+                position,
+                rule: rule.to_string(),
+            });
+        }
+    });
+}
+
+pub fn parse_trace(lines: &[String]) -> Result<Vec<TraceStep>> {
+    lines
+        .iter()
+        .enumerate()
+        .map(|(i, line)| line.parse().with_context(|| format!("In trace step {i}")))
+        .collect()
+}
+
+/// Fails with the first step at which `actual` diverges from `expected`.
+pub fn check_trace(expected: &[TraceStep], actual: &[TraceStep]) -> Result<()> {
+    let Some(i) = (0..expected.len().max(actual.len())).find(|&i| expected.get(i) != actual.get(i))
+    else {
+        return Ok(());
+    };
+    let show =
+        |step: Option<&TraceStep>| step.map_or("(end of trace)".to_string(), |s| s.to_string());
+    bail!(
+        "Trace diverges at step {i}:\n  expected: {}\n  got:      {}\nGot trace:\n  {}",
+        show(expected.get(i)),
+        show(actual.get(i)),
+        actual.iter().join("\n  ")
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_case::{DragStep, parse_world, run_drag_steps};
+    use crate::{BELT_TIERS, Direction};
+
+    #[test]
+    fn test_trace_round_trips() {
+        let step: TraceStep = "( 3,-1)  TraversingObstacle + Usable ->  CreateUnderground"
+            .parse()
+            .unwrap();
+        assert_eq!(step.position, pos(3, -1));
+        assert_eq!(
+            step.to_string(),
+            "(3, -1) TraversingObstacle + Usable -> CreateUnderground"
+        );
+        assert!("3, -1 Belt".parse::<TraceStep>().is_err());
+    }
+
+    #[test]
+    fn test_check_trace_reports_divergent_step() {
+        let (mut world, _) = parse_world("_ X _").unwrap();
+        let steps = [DragStep::MoveTo(pos(2, 0))];
+        let (errors, trace) = with_trace(|| {
+            run_drag_steps(
+                &mut world,
+                BELT_TIERS[0],
+                pos(0, 0),
+                Direction::East,
+                &steps,
+            )
+        });
+        errors.unwrap();
+        let expected = parse_trace(&[
+            "(1, 0) Belt + Obstacle -> None".to_string(),
+            "(2, 0) TraversingObstacle + Usable -> CreateUnderground".to_string(),
+        ])
+        .unwrap();
+        check_trace(&expected, &trace).unwrap();
+
+        let err = check_trace(&expected[..1], &trace).unwrap_err();
+        assert!(
+            err.to_string().starts_with("Trace diverges at step 1:"),
+            "{err}"
+        );
+    }
+}
@@ -0,0 +1,39 @@
+//! Runs every case in `test_suite/` and prints a diff for each failing one.
+//! To rewrite failing cases' `after`/`expected_errors` with the current output:
+//!
+//! ```sh
+//! BLESS=1 cargo test -p prototype_abstract --test bless -- --ignored --nocapture
+//! ```
+
+use std::path::Path;
+
+use prototype_abstract::bless::bless_suite;
+
+mod common;
+
+#[test]
+#[ignore]
+fn bless_test_suite() {
+    common::init_logger();
+    let write = std::env::var_os("BLESS").is_some();
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("../test_suite");
+
+    let reports = bless_suite(&dir, write).unwrap();
+    for report in reports.iter().filter(|r| !r.failures.is_empty()) {
+        println!("{report}");
+    }
+
+    let failed: usize = reports.iter().map(|r| r.failures.len()).sum();
+    let blessed: usize = reports
+        .iter()
+        .filter(|r| r.written)
+        .map(|r| r.num_blessed())
+        .sum();
+    println!("   Failed: {failed}");
+    if write {
+        println!("   Blessed: {blessed}");
+        assert_eq!(failed, blessed, "Some failing cases cannot be blessed");
+    } else {
+        assert_eq!(failed, 0, "Failed; run with BLESS=1 to update the cases");
+    }
+}
@@ -0,0 +1,35 @@
+//! Prints which drag states, classifier branches and step rules the test
+//! suite reaches:
+//!
+//! ```sh
+//! cargo test -p prototype_abstract --test coverage -- --ignored --nocapture
+//! ```
+//!
+//! With `FUZZ_SEEDS=<n>`, also lists fuzzer seeds below `n` that reach
+//! anything the suite doesn't.
+
+use std::path::Path;
+
+use prototype_abstract::coverage::suite_coverage;
+use prototype_abstract::fuzzer::{FuzzConfig, seeds_by_new_coverage};
+
+mod common;
+
+#[test]
+#[ignore]
+fn test_suite_coverage() {
+    common::init_logger();
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("../test_suite");
+    let report = suite_coverage(&dir).unwrap();
+    println!("{report}");
+
+    let Some(num_seeds) = std::env::var("FUZZ_SEEDS").ok() else {
+        return;
+    };
+    let config = FuzzConfig {
+        world_width: 15,
+        entity_density: 0.6,
+    };
+    let seeds = seeds_by_new_coverage(&config, 0..num_seeds.parse().unwrap(), &report.total());
+    println!("Seeds reaching new coverage: {seeds:?}");
+}
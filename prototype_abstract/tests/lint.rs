@@ -0,0 +1,17 @@
+//! Lints the cases in `test_suite/`; see [`prototype_abstract::lint`].
+//! Warnings are printed (run with `--nocapture`), errors fail the test.
+
+use std::path::Path;
+
+use prototype_abstract::lint::lint_suite;
+
+#[test]
+fn lint_test_suite() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("../test_suite");
+    let issues = lint_suite(&dir).unwrap();
+    for issue in &issues {
+        println!("{issue}");
+    }
+    let num_errors = issues.iter().filter(|i| i.kind.is_error()).count();
+    assert_eq!(num_errors, 0, "Test suite has lint errors");
+}
@@ -0,0 +1,54 @@
+//! Model checks of the drag row, one length per test. Only length 2 runs in a
+//! plain `cargo test`, and length 3 in release builds; the longer runs and
+//! side rows are ignored, to be run with:
+//!
+//! ```sh
+//! cargo test --release -p prototype_abstract --test model_check_test -- --include-ignored
+//! ```
+
+use prototype_abstract::model_check::{ModelCheckConfig, model_check};
+
+mod common;
+
+#[test]
+fn model_check_length_2() {
+    common::init_logger();
+    check(&ModelCheckConfig::new(2));
+}
+
+#[test]
+#[cfg_attr(debug_assertions, ignore = "slow in debug builds")]
+fn model_check_length_3() {
+    common::init_logger();
+    check(&ModelCheckConfig::new(3));
+}
+
+#[test]
+#[ignore]
+fn model_check_length_4() {
+    common::init_logger();
+    check(&ModelCheckConfig::new(4));
+}
+
+#[test]
+#[ignore]
+fn model_check_length_2_side_rows() {
+    common::init_logger();
+    check(&ModelCheckConfig {
+        side_rows: true,
+        ..ModelCheckConfig::new(2)
+    });
+}
+
+fn check(config: &ModelCheckConfig) {
+    let report = model_check(config);
+    println!("{}", report.completeness_claim());
+    if !report.is_complete_and_correct() {
+        eprintln!("{}", report.counter_examples_yaml());
+    }
+    assert!(
+        report.is_complete_and_correct(),
+        "{}",
+        report.completeness_claim()
+    );
+}
@@ -1,4 +1,8 @@
-use prototype_abstract::test_case::{DragTestCase, TestVariant, check_test_case_all_transforms};
+use prototype_abstract::test_case::{
+    DragTestCase, ScenarioTestCase, StepsTestCase, TestVariant,
+    check_scenario_test_case_all_transforms, check_steps_test_case_all_transforms,
+    check_test_case_all_transforms,
+};
 
 mod common;
 
@@ -11,6 +15,22 @@ pub fn run_test_case(content: &str) {
     check_test_case_all_transforms(&test_case, false, TestVariant::Normal).unwrap();
 }
 
+pub fn run_steps_test_case(content: &str) {
+    common::init_logger();
+    let test_case: StepsTestCase =
+        serde_yaml::from_str(content).expect("Failed to parse test case YAML");
+
+    check_steps_test_case_all_transforms(&test_case).unwrap();
+}
+
+pub fn run_scenario_test_case(content: &str) {
+    common::init_logger();
+    let test_case: ScenarioTestCase =
+        serde_yaml::from_str(content).expect("Failed to parse test case YAML");
+
+    check_scenario_test_case_all_transforms(&test_case).unwrap();
+}
+
 pub fn run_test_case_reverse(content: &str) {
     common::init_logger();
     let test_case: DragTestCase =
@@ -112,6 +112,20 @@ function entitiesToJson(entities: EntityWithPos[]): string {
   return `[\n        ${items.join(",\n        ")},\n      ]`
 }
 
+/**
+ * Why a case is run only by the Rust tests, if it is: `runDragTest` does a
+ * single straight drag. Checked in the same order as `SuiteCase::from_value`.
+ */
+function rustOnlyReason(testCase: TestCaseYaml): string | undefined {
+  if ("drags" in testCase) {
+    return "scenario cases (several drags) are Rust-only"
+  }
+  if ("steps" in testCase) {
+    return "steps cases (cursor steps and rotations) are Rust-only"
+  }
+  return undefined
+}
+
 function generateTestFile(
   fileStem: string,
   testCases: TestCaseYaml[],
@@ -123,6 +137,12 @@ function generateTestFile(
     const testName = testCase.name || `test_${String(i + 1).padStart(3, "0")}`
     const sanitizedName = sanitizeTestName(testName)
 
+    const rustOnly = rustOnlyReason(testCase)
+    if (rustOnly) {
+      console.log(`  Skipping test "${testName}": ${rustOnly}`)
+      continue
+    }
+
     const yamlContent = yaml.dump(testCase)
     let parsed: DragTestCase
     try {
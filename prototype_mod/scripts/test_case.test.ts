@@ -0,0 +1,60 @@
+import { describe, expect, test } from "bun:test"
+import { BELT_TIERS } from "../common/belt_tiers"
+import { Direction, pos } from "../common/geometry"
+import { parseTestCase, parseWorld } from "./test_case"
+
+describe("parseTestCase", () => {
+  test("infers the drag from the grids", () => {
+    const { entities } = parseTestCase(`before: _ X _\nafter: ">i X >o"`)
+    expect(entities.startPos).toEqual(pos(0, 0))
+    expect(entities.endPos).toEqual(pos(2, 0))
+    expect(entities.beltDirection).toBe(Direction.East)
+    expect(entities.tier).toBe(BELT_TIERS[0]!)
+  })
+
+  test("explicit keys override the inferred drag", () => {
+    const { entities } = parseTestCase(
+      `before: _ _ _\nafter: "2< 2< 2<"\nstart: [2, 0]\nend: [0, 0]\ndirection: west\ntier: 2`,
+    )
+    expect(entities.startPos).toEqual(pos(2, 0))
+    expect(entities.endPos).toEqual(pos(0, 0))
+    expect(entities.beltDirection).toBe(Direction.West)
+    expect(entities.tier).toBe(BELT_TIERS[1]!)
+    expect(entities.leftmostPos).toEqual(pos(2, 0))
+  })
+
+  test("positions can be labels", () => {
+    const { entities } = parseTestCase(
+      `before: "_ _ [end]_ _ >"\nafter: "> > > _ >"\nend: end`,
+    )
+    expect(entities.endPos).toEqual(pos(2, 0))
+  })
+
+  test("rejects contradicting keys", () => {
+    expect(() =>
+      parseTestCase(`before: _ _\nafter: "> >"\nend: nowhere`),
+    ).toThrow("No marker labelled nowhere")
+    expect(() =>
+      parseTestCase(`before: _ _\nafter: "> >"\ndirection: west`),
+    ).toThrow("contradicts")
+    expect(() =>
+      parseTestCase(
+        `before: _ _\nafter: "> >"\nend: [1, 0]\ndirection: north`,
+      ),
+    ).toThrow("not in line")
+    expect(() =>
+      parseTestCase(`before: "_ _\\n_ _"\nafter: "> >"\nend: [1, 1]`),
+    ).toThrow("not in a straight line")
+  })
+})
+
+describe("parseWorld", () => {
+  test("reads labels and markers", () => {
+    const { entities, markers, labels, size } = parseWorld("[a]*> _\n_ [b]_")
+    expect(entities.length).toBe(1)
+    expect(markers).toEqual([pos(0, 0)])
+    expect(labels.get("a")).toEqual(pos(0, 0))
+    expect(labels.get("b")).toEqual(pos(1, 1))
+    expect(size).toEqual({ x: 2, y: 2 })
+  })
+})
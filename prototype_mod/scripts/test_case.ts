@@ -10,13 +10,16 @@ import {
   type BeltTier,
 } from "../common/belts"
 import {
+  addVec,
   Axis,
   boundsUnion,
   createRay,
   Direction,
   directionAxis,
+  mulVec,
   oppositeDirection,
   pos,
+  posEquals,
   rayPosition,
   raySnap,
   vec2,
@@ -75,11 +78,23 @@ interface TestCaseSerialized {
   expected_errors?: string[]
   not_reversible?: boolean
   forward_back?: boolean
+  start?: PositionRef
+  end?: PositionRef
+  direction?: string
+  /** 1-based, as in the grid */
+  tier?: number
 }
 
+/** A position in a test case: `[x, y]` grid coordinates, or a marker label. */
+type PositionRef = [number, number] | string
+
 export interface WorldParseResult {
   entities: [TilePosition, TestEntity][]
   markers: TilePosition[]
+  /** Positions of `[label]` markers. */
+  labels: Map<string, TilePosition>
+  /** Number of columns (of the widest row) and rows in the grid. */
+  size: TileVec
 }
 
 export function serializeError(pos: TilePosition, error: ActionError): string {
@@ -92,23 +107,47 @@ export function deserializeError(str: string): [TilePosition, ActionError] {
   return [pos(Number(xStr), Number(yStr)), errorStr as ActionError]
 }
 
+/**
+ * Words may be prefixed with `*` markers, and with `[label]` to name their
+ * position, e.g. `[a]_` or `[pivot]2>`.
+ */
 export function parseWorld(input: string): WorldParseResult {
   const entities: [TilePosition, TestEntity][] = []
   const markers: TilePosition[] = []
+  const labels = new Map<string, TilePosition>()
+  let width = 0
+  let height = 0
 
   const lines = input.split("\n")
   for (let y = 0; y < lines.length; y++) {
     const line = lines[y]
     if (!line) continue
+    height = y + 1
     const words = line.split(/\s+/).filter((w) => w.length > 0)
+    width = Math.max(width, words.length)
     for (let x = 0; x < words.length; x++) {
       const position = pos(x, y)
       let word = words[x]
       if (!word) continue
 
-      while (word.startsWith("*")) {
-        markers.push(position)
-        word = word.slice(1)
+      while (true) {
+        if (word.startsWith("*")) {
+          markers.push(position)
+          word = word.slice(1)
+        } else if (word.startsWith("[")) {
+          const close = word.indexOf("]")
+          if (close < 0) {
+            throw new Error(`Unclosed marker label in ${word}`)
+          }
+          const label = word.slice(1, close)
+          if (labels.has(label)) {
+            throw new Error(`Duplicate marker label ${label}`)
+          }
+          labels.set(label, position)
+          word = word.slice(close + 1)
+        } else {
+          break
+        }
       }
 
       const entity = parseWord(word)
@@ -118,7 +157,7 @@ export function parseWorld(input: string): WorldParseResult {
     }
   }
 
-  return { entities, markers }
+  return { entities, markers, labels, size: vec2(width, height) }
 }
 
 function parseWord(input: string): TestEntity | undefined {
@@ -466,17 +505,85 @@ function isBeltLikeEntity(
   )
 }
 
+function directionFromName(name: string): Direction {
+  switch (name) {
+    case "north":
+      return Direction.North
+    case "east":
+      return Direction.East
+    case "south":
+      return Direction.South
+    case "west":
+      return Direction.West
+    default:
+      throw new Error(`Invalid direction: ${name}`)
+  }
+}
+
+function resolvePosition(
+  position: PositionRef,
+  labels: Map<string, TilePosition>,
+): TilePosition {
+  if (typeof position !== "string") {
+    return pos(position[0], position[1])
+  }
+  const labelled = labels.get(position)
+  if (!labelled) {
+    throw new Error(`No marker labelled ${position}`)
+  }
+  return labelled
+}
+
+/** Labels from all grids, which must agree on where each label is. */
+function mergeLabels(grids: WorldParseResult[]): Map<string, TilePosition> {
+  const labels = new Map<string, TilePosition>()
+  for (const grid of grids) {
+    for (const [label, position] of grid.labels) {
+      const existing = labels.get(label)
+      if (existing && !posEquals(existing, position)) {
+        throw new Error(
+          `Marker ${label} is at different positions in different grids`,
+        )
+      }
+      labels.set(label, position)
+    }
+  }
+  return labels
+}
+
+function entityAt(
+  entities: [TilePosition, TestEntity][],
+  position: TilePosition,
+): TestEntity | undefined {
+  return entities.find(([p]) => posEquals(p, position))?.[1]
+}
+
+/**
+ * Anything not given explicitly is inferred from the grids: the drag starts at
+ * the `*` marker in `before`, or else the first entity in the left column,
+ * and ends at the right edge of that row. The direction and tier are those of
+ * the first belt-like entity in `after` on the way from the start to the end.
+ */
 function getEntities(serde: TestCaseSerialized): TestCaseEntities {
-  const { entities: beforeEntities, markers: beforeMarkers } = parseWorld(
-    serde.before,
-  )
-  const { entities: afterEntities, markers: afterMarkers } = parseWorld(
-    serde.after,
-  )
+  const beforeParse = parseWorld(serde.before)
+  const afterParse = parseWorld(serde.after)
+  const { entities: beforeEntities, markers: beforeMarkers } = beforeParse
+  const { entities: afterEntities, markers: afterMarkers } = afterParse
+  const labels = mergeLabels([beforeParse, afterParse])
 
   const before = toSimulatedWorld(beforeEntities)
   const after = toSimulatedWorld(afterEntities)
 
+  const gridWidth = Math.max(beforeParse.size.x, afterParse.size.x)
+  const gridHeight = Math.max(beforeParse.size.y, afterParse.size.y)
+  const resolveInGrid = (key: string, position: PositionRef) => {
+    const p = resolvePosition(position, labels)
+    if (p.x < 0 || p.y < 0 || p.x >= gridWidth || p.y >= gridHeight) {
+      throw new Error(`'${key}' (${p.x}, ${p.y}) is outside the grid`)
+    }
+    return p
+  }
+
   const expectedErrorsList = serde.expected_errors || []
 
   if (afterMarkers.length !== expectedErrorsList.length) {
@@ -492,12 +599,20 @@ function getEntities(serde: TestCaseSerialized): TestCaseEntities {
     expectedErrors.add(serializeError(position, error as ActionError))
   }
 
+  if (beforeMarkers.length > 1) {
+    throw new Error("Expected exactly one marker for drag start position")
+  }
+  const startMarker = beforeMarkers[0]
   let startPos: TilePosition
-  if (beforeMarkers.length > 0) {
-    if (beforeMarkers.length > 1) {
-      throw new Error("Expected exactly one marker for drag start position")
+  if (serde.start !== undefined) {
+    startPos = resolveInGrid("start", serde.start)
+    if (startMarker && !posEquals(startMarker, startPos)) {
+      throw new Error(
+        `'start' (${startPos.x}, ${startPos.y}) contradicts the marker at (${startMarker.x}, ${startMarker.y}) in 'before'`,
+      )
     }
-    startPos = beforeMarkers[0]!
+  } else if (startMarker) {
+    startPos = startMarker
   } else {
     const firstAtX0 = afterEntities.find(([p]) => p.x === 0)
     if (!firstAtX0) {
@@ -506,27 +621,88 @@ function getEntities(serde: TestCaseSerialized): TestCaseEntities {
     startPos = firstAtX0[0]
   }
 
-  const entitiesInRow = afterEntities
-    .filter(([p]) => p.y === startPos.y && p.x >= startPos.x)
-    .sort((a, b) => a[0].x - b[0].x)
+  const allPositions = [...beforeEntities, ...afterEntities].map(([p]) => p)
+  const maxX = Math.max(0, ...allPositions.map((p) => p.x))
+  const maxY = Math.max(0, ...allPositions.map((p) => p.y))
+  let endPos: TilePosition
+  if (serde.end !== undefined) {
+    endPos = resolveInGrid("end", serde.end)
+  } else if (
+    serde.direction !== undefined &&
+    directionAxis(directionFromName(serde.direction)) === Axis.Y
+  ) {
+    endPos = pos(startPos.x, maxY)
+  } else {
+    endPos = pos(maxX, startPos.y)
+  }
+  if (endPos.x !== startPos.x && endPos.y !== startPos.y) {
+    throw new Error(
+      `'end' (${endPos.x}, ${endPos.y}) is not in a straight line from the start (${startPos.x}, ${startPos.y})`,
+    )
+  }
 
-  const firstBelt = entitiesInRow.find(([, ent]) => isBeltLikeEntity(ent))
-  if (!firstBelt) {
-    throw new Error("No belt found in drag row")
+  const step = vec2(
+    Math.sign(endPos.x - startPos.x),
+    Math.sign(endPos.y - startPos.y),
+  )
+  const distance =
+    Math.abs(endPos.x - startPos.x) + Math.abs(endPos.y - startPos.y)
+  let firstBelt: Extract<TestEntity, { direction: Direction }> | undefined
+  for (let i = 0; i <= distance && !firstBelt; i++) {
+    const entity = entityAt(afterEntities, addVec(startPos, mulVec(step, i)))
+    if (entity && isBeltLikeEntity(entity)) {
+      firstBelt = entity
+    }
   }
 
-  const firstBeltEntity = firstBelt[1] as Extract<
-    TestEntity,
-    { direction: Direction }
-  >
-  const tier = BELT_TIERS[firstBeltEntity.tier - 1]!
-  const direction = firstBeltEntity.direction
+  let direction: Direction
+  if (serde.direction !== undefined) {
+    direction = directionFromName(serde.direction)
+  } else if (firstBelt) {
+    direction = firstBelt.direction
+  } else {
+    throw new Error("No belt found in drag row to infer 'direction' from")
+  }
+  let tier: BeltTier
+  if (serde.tier !== undefined) {
+    if (serde.tier < 1 || serde.tier > BELT_TIERS.length) {
+      throw new Error(
+        `'tier' ${serde.tier} is not between 1 and ${BELT_TIERS.length}`,
+      )
+    }
+    tier = BELT_TIERS[serde.tier - 1]!
+  } else if (firstBelt) {
+    tier = BELT_TIERS[firstBelt.tier - 1]!
+  } else {
+    throw new Error("No belt found in drag row to infer 'tier' from")
+  }
 
-  const allPositions = [...beforeEntities, ...afterEntities].map(([p]) => p.x)
-  const maxX = Math.max(...allPositions)
-  const endPos = pos(maxX, startPos.y)
+  const snapped = raySnap(createRay(startPos, direction), endPos)
+  if (!posEquals(snapped, endPos)) {
+    throw new Error(
+      `'end' (${endPos.x}, ${endPos.y}) is not in line with the start (${startPos.x}, ${startPos.y}) in the drag direction`,
+    )
+  }
+
+  // The first click places a belt in the drag direction, with the drag tier
+  const startEntity = entityAt(afterEntities, startPos)
+  if (
+    startEntity?.kind === "belt" &&
+    JSON.stringify(entityAt(beforeEntities, startPos)) !==
+      JSON.stringify(startEntity) &&
+    (startEntity.direction !== direction ||
+      BELT_TIERS[startEntity.tier - 1] !== tier)
+  ) {
+    throw new Error(
+      `The belt placed at the start (${startPos.x}, ${startPos.y}) contradicts the drag direction and tier`,
+    )
+  }
 
-  const leftmostPos = pos(0, startPos.y)
+  // The far edge of the grid behind the start
+  const leftmostPos =
+    directionAxis(direction) === Axis.X
+      ? pos(endPos.x < startPos.x ? maxX : 0, startPos.y)
+      : pos(startPos.x, endPos.y < startPos.y ? maxY : 0)
 
   return {
     before,
@@ -0,0 +1,343 @@
+//! Runs a smart belt drag over a world and prints the result, to try out
+//! drags without writing a test.
+//!
+//! ```sh
+//! cargo run -p smart_belt_cli -- world.txt --start 0,0 --direction east \
+//!     move_to:6,0 rotate:6,0 wiggle:6,4 --png drag.png
+//! ```
+//!
+//! Worlds are grids as in the test suite (`[label]`s can be used as
+//! positions), blueprint strings, or blueprint JSON. Steps can also come from
+//! a YAML script:
+//!
+//! ```yaml
+//! start: [0, 0]
+//! direction: east
+//! tier: 2
+//! steps:
+//!   - move_to: [6, 0]
+//!   - rotate: [6, 0]
+//!   - wiggle: end
+//! ```
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Read};
+use std::path::PathBuf;
+
+use anyhow::{Context, Result, bail};
+use clap::{Parser, ValueEnum};
+use image_renderer::ImageRenderer;
+use image_renderer::annotations::Annotation;
+use prototype_abstract::blueprint::{
+    BlueprintImport, parse_blueprint_json, parse_blueprint_string,
+};
+use prototype_abstract::test_case::{
+    DragStep, PositionRef, TestStepSerde, parse_labeled_world, print_world, resolve_steps,
+    run_drag_steps,
+};
+use prototype_abstract::{BELT_TIERS, BeltTier, Direction, TilePosition, WorldImpl};
+use serde::Deserialize;
+use serde::de::IntoDeserializer;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum WorldFormat {
+    /// JSON if it starts with `{`, a blueprint string if it starts with `0`,
+    /// otherwise a grid
+    Auto,
+    Grid,
+    Blueprint,
+    Json,
+}
+
+#[derive(Debug, Parser)]
+struct Args {
+    #[arg(help = "World file, or - for stdin")]
+    world: PathBuf,
+    #[arg(long, value_enum, default_value = "auto")]
+    format: WorldFormat,
+    #[arg(long, help = "Drag script (YAML); arguments take precedence over it")]
+    script: Option<PathBuf>,
+    #[arg(long, value_parser = parse_position, help = "Where the drag starts: X,Y or a label")]
+    start: Option<PositionRef>,
+    #[arg(
+        long,
+        value_parser = parse_direction,
+        help = "Direction of the belts placed: north, east, south or west"
+    )]
+    direction: Option<Direction>,
+    #[arg(long, help = "Belt tier, from 1 (yellow) to 3 (blue) [default: 1]")]
+    tier: Option<usize>,
+    #[arg(
+        value_parser = parse_step,
+        help = "Cursor steps: move_to:X,Y, rotate:X,Y or wiggle:X,Y (or with labels)"
+    )]
+    steps: Vec<TestStepSerde>,
+    #[arg(long, help = "Also render the result to this PNG")]
+    png: Option<PathBuf>,
+}
+
+/// Everything in a drag script is optional, so that it can be completed or
+/// overridden from the command line.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Script {
+    start: Option<PositionRef>,
+    direction: Option<Direction>,
+    tier: Option<usize>,
+    #[serde(default, with = "serde_yaml::with::singleton_map_recursive")]
+    steps: Vec<TestStepSerde>,
+}
+
+fn parse_position(s: &str) -> Result<PositionRef> {
+    let Some((x, y)) = s.split_once(',') else {
+        return Ok(PositionRef::Label(s.to_string()));
+    };
+    let coord = |c: &str| {
+        c.trim()
+            .parse::<i32>()
+            .with_context(|| format!("Invalid coordinate {c:?}"))
+    };
+    Ok(PositionRef::Coords([coord(x)?, coord(y)?]))
+}
+
+/// As in test cases and scripts.
+fn parse_direction(s: &str) -> Result<Direction> {
+    let direction: Result<_, serde::de::value::Error> =
+        Direction::deserialize(s.into_deserializer());
+    Ok(direction?)
+}
+
+fn parse_step(s: &str) -> Result<TestStepSerde> {
+    let (kind, position) = s
+        .split_once(':')
+        .context("Steps are written as kind:X,Y, e.g. move_to:3,0")?;
+    let position = parse_position(position)?;
+    Ok(match kind {
+        "move_to" => TestStepSerde::MoveTo(position),
+        "rotate" => TestStepSerde::Rotate(position),
+        "wiggle" => TestStepSerde::Wiggle(position),
+        _ => bail!("Unknown step {kind:?}; expected move_to, rotate or wiggle"),
+    })
+}
+
+/// The world, and the labels it has (only grids have any).
+fn read_world(args: &Args) -> Result<(WorldImpl, HashMap<String, TilePosition>)> {
+    let input = if args.world.as_os_str() == "-" {
+        let mut input = String::new();
+        io::stdin().read_to_string(&mut input)?;
+        input
+    } else {
+        fs::read_to_string(&args.world)
+            .with_context(|| format!("Reading {}", args.world.display()))?
+    };
+    let format = match args.format {
+        WorldFormat::Auto => match input.trim_start().chars().next() {
+            Some('{') => WorldFormat::Json,
+            Some('0') => WorldFormat::Blueprint,
+            _ => WorldFormat::Grid,
+        },
+        format => format,
+    };
+    Ok(match format {
+        WorldFormat::Auto | WorldFormat::Grid => {
+            let parse = parse_labeled_world(&input)?;
+            (parse.world, parse.labels)
+        }
+        WorldFormat::Blueprint => (
+            blueprint_world(parse_blueprint_string(&input)?),
+            HashMap::new(),
+        ),
+        WorldFormat::Json => (
+            blueprint_world(parse_blueprint_json(&input)?),
+            HashMap::new(),
+        ),
+    })
+}
+
+fn blueprint_world(import: BlueprintImport) -> WorldImpl {
+    if !import.other_entities.is_empty() {
+        let names = import.other_entities.into_iter().collect::<Vec<_>>();
+        eprintln!(
+            "Warning: these block only the tile at their centre, whatever their size: {}",
+            names.join(", ")
+        );
+    }
+    import.world
+}
+
+/// A drag, from the arguments or else the script.
+#[derive(Debug, PartialEq)]
+struct Drag {
+    start: TilePosition,
+    direction: Direction,
+    tier: BeltTier,
+    steps: Vec<DragStep>,
+}
+
+fn merge_drag(
+    args: &Args,
+    script: &Script,
+    labels: &HashMap<String, TilePosition>,
+) -> Result<Drag> {
+    let start = args
+        .start
+        .as_ref()
+        .or(script.start.as_ref())
+        .context("No start position; pass --start or set it in the script")?
+        .resolve(labels)?;
+    let direction = args
+        .direction
+        .or(script.direction)
+        .context("No belt direction; pass --direction or set it in the script")?;
+    let tier_index = args.tier.or(script.tier).unwrap_or(1);
+    let tier = BeltTier::from_number(tier_index)
+        .with_context(|| format!("Tier should be from 1 to {}", BELT_TIERS.len()))?;
+    let steps = if args.steps.is_empty() {
+        &script.steps
+    } else {
+        &args.steps
+    };
+    Ok(Drag {
+        start,
+        direction,
+        tier,
+        steps: resolve_steps(start, steps, labels)?,
+    })
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+    let (mut world, labels) = read_world(&args)?;
+    let script = match &args.script {
+        Some(path) => {
+            let yaml =
+                fs::read_to_string(path).with_context(|| format!("Reading {}", path.display()))?;
+            serde_yaml::from_str(&yaml).with_context(|| format!("Parsing {}", path.display()))?
+        }
+        None => Script::default(),
+    };
+
+    let Drag {
+        start,
+        direction,
+        tier,
+        steps,
+    } = merge_drag(&args, &script, &labels)?;
+
+    let before = world.clone();
+    let errors = run_drag_steps(&mut world, tier, start, direction, &steps)?;
+
+    let bounds = before.bounds().union(&world.bounds());
+    let mut errors = errors.into_iter().collect::<Vec<_>>();
+    errors.sort_by_key(|(p, error)| (p.y, p.x, error.clone()));
+    let markers = errors.iter().map(|(p, _)| *p).collect::<Vec<_>>();
+    println!("{}", print_world(&world, bounds, &markers));
+    if errors.is_empty() {
+        println!("No errors");
+    } else {
+        println!("Errors:");
+        for (p, error) in &errors {
+            println!("  ({}, {}): {error:?}", p.x, p.y);
+        }
+    }
+
+    if let Some(path) = &args.png {
+        let annotations = errors
+            .iter()
+            .map(|(p, error)| Annotation::Error(*p, error.clone()))
+            .collect::<Vec<_>>();
+        ImageRenderer::new()?
+            .render_world_annotated(&world, bounds, &annotations)
+            .save_png(path)
+            .with_context(|| format!("Saving {}", path.display()))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prototype_abstract::{RED_BELT, pos};
+
+    #[test]
+    fn test_parse_position() {
+        assert_eq!(
+            parse_position("3, -1").unwrap(),
+            PositionRef::Coords([3, -1])
+        );
+        assert_eq!(
+            parse_position("end").unwrap(),
+            PositionRef::Label("end".to_string())
+        );
+        assert!(parse_position("3,x").is_err());
+        assert!(parse_position("1,2,3").is_err());
+    }
+
+    #[test]
+    fn test_parse_step() {
+        assert_eq!(
+            parse_step("move_to:3,0").unwrap(),
+            TestStepSerde::MoveTo(PositionRef::Coords([3, 0]))
+        );
+        assert_eq!(
+            parse_step("wiggle:end").unwrap(),
+            TestStepSerde::Wiggle(PositionRef::Label("end".to_string()))
+        );
+        assert!(parse_step("3,0").is_err());
+        assert!(parse_step("jump:3,0").is_err());
+        assert!(parse_step("rotate:a,0").is_err());
+    }
+
+    #[test]
+    fn test_parse_direction() {
+        assert_eq!(parse_direction("west").unwrap(), Direction::West);
+        assert!(parse_direction("up").is_err());
+    }
+
+    #[test]
+    fn test_arguments_override_the_script() {
+        let script: Script =
+            serde_yaml::from_str("start: a\ndirection: east\ntier: 2\nsteps:\n  - move_to: [4, 0]")
+                .unwrap();
+        let labels = HashMap::from([("a".to_string(), pos(1, 0))]);
+
+        let args = Args::try_parse_from(["smart-belt", "world.txt"]).unwrap();
+        let drag = merge_drag(&args, &script, &labels).unwrap();
+        assert_eq!(
+            drag,
+            Drag {
+                start: pos(1, 0),
+                direction: Direction::East,
+                tier: RED_BELT,
+                steps: vec![DragStep::MoveTo(pos(4, 0))],
+            }
+        );
+
+        let args = Args::try_parse_from([
+            "smart-belt",
+            "world.txt",
+            "--start",
+            "0,2",
+            "--direction",
+            "south",
+            "--tier",
+            "1",
+            "move_to:0,5",
+        ])
+        .unwrap();
+        let drag = merge_drag(&args, &script, &labels).unwrap();
+        assert_eq!(
+            drag,
+            Drag {
+                start: pos(0, 2),
+                direction: Direction::South,
+                tier: BELT_TIERS[0],
+                steps: vec![DragStep::MoveTo(pos(0, 5))],
+            }
+        );
+
+        let args = Args::try_parse_from(["smart-belt", "world.txt"]).unwrap();
+        assert!(merge_drag(&args, &Script::default(), &labels).is_err());
+    }
+}
@@ -0,0 +1,43 @@
+//! Generates `smart_belt.h` into `OUT_DIR` from the `extern "C"` API in
+//! `src/lib.rs`. The header is also checked in as `include/smart_belt.h`, so C
+//! users don't need to build this crate to read it; `tests/header.rs` checks
+//! that it is up to date.
+
+use std::env;
+use std::path::PathBuf;
+
+use cbindgen::{Builder, Config, EnumConfig, ExportConfig, Language, RenameRule};
+
+fn main() {
+    let crate_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
+    let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
+    println!("cargo:rerun-if-changed=src/lib.rs");
+
+    let config = Config {
+        language: Language::C,
+        header: Some("/* Generated from src/lib.rs by build.rs; do not edit. */".to_string()),
+        include_guard: Some("SMART_BELT_H".to_string()),
+        documentation: true,
+        cpp_compat: true,
+        enumeration: EnumConfig {
+            prefix_with_name: true,
+            rename_variants: RenameRule::ScreamingSnakeCase,
+            ..Default::default()
+        },
+        export: ExportConfig {
+            // Fields and parameters are plain bytes, so these aren't
+            // referenced by anything
+            include: ["SbDirection", "SbEntityKind", "SbError"]
+                .map(String::from)
+                .to_vec(),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    Builder::new()
+        .with_src(crate_dir.join("src/lib.rs"))
+        .with_config(config)
+        .generate()
+        .expect("Failed to generate the C header")
+        .write_to_file(out_dir.join("smart_belt.h"));
+}
@@ -0,0 +1,240 @@
+/* Generated from src/lib.rs by build.rs; do not edit. */
+
+#ifndef SMART_BELT_H
+#define SMART_BELT_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+enum SbResult
+#if defined(__cplusplus) || __STDC_VERSION__ >= 202311L
+  : uint8_t
+#endif // defined(__cplusplus) || __STDC_VERSION__ >= 202311L
+ {
+  SB_RESULT_OK = 0,
+  /**
+   * `sb_drag_rotate` with the cursor on the belt line; nothing happened.
+   */
+  SB_RESULT_NOT_ROTATED = 1,
+  /**
+   * A null drag, or an out of range tier or direction.
+   */
+  SB_RESULT_INVALID_ARGUMENT = 2,
+  /**
+   * `get_entity` gave an entity with an out of range field. The drag is
+   * left as it was before the call.
+   */
+  SB_RESULT_INVALID_ENTITY = 3,
+  /**
+   * The engine panicked (a bug; the message went to stderr). The drag may
+   * be half-way through the call, and should only be ended.
+   */
+  SB_RESULT_INTERNAL_ERROR = 4,
+};
+#ifndef __cplusplus
+#if __STDC_VERSION__ >= 202311L
+typedef enum SbResult SbResult;
+#else
+typedef uint8_t SbResult;
+#endif // __STDC_VERSION__ >= 202311L
+#endif // __cplusplus
+
+enum SbDirection
+#if defined(__cplusplus) || __STDC_VERSION__ >= 202311L
+  : uint8_t
+#endif // defined(__cplusplus) || __STDC_VERSION__ >= 202311L
+ {
+  SB_DIRECTION_NORTH = 0,
+  SB_DIRECTION_EAST = 1,
+  SB_DIRECTION_SOUTH = 2,
+  SB_DIRECTION_WEST = 3,
+};
+#ifndef __cplusplus
+#if __STDC_VERSION__ >= 202311L
+typedef enum SbDirection SbDirection;
+#else
+typedef uint8_t SbDirection;
+#endif // __STDC_VERSION__ >= 202311L
+#endif // __cplusplus
+
+enum SbEntityKind
+#if defined(__cplusplus) || __STDC_VERSION__ >= 202311L
+  : uint8_t
+#endif // defined(__cplusplus) || __STDC_VERSION__ >= 202311L
+ {
+  SB_ENTITY_KIND_BELT = 0,
+  SB_ENTITY_KIND_UNDERGROUND_BELT = 1,
+  SB_ENTITY_KIND_SPLITTER = 2,
+  /**
+   * Loaders and linked belts.
+   */
+  SB_ENTITY_KIND_LOADER_LIKE = 3,
+  /**
+   * Any other entity, or a tile belts can't be built on.
+   */
+  SB_ENTITY_KIND_OBSTACLE = 4,
+  /**
+   * A tile undergrounds can't pass under either.
+   */
+  SB_ENTITY_KIND_IMPASSABLE_TILE = 5,
+};
+#ifndef __cplusplus
+#if __STDC_VERSION__ >= 202311L
+typedef enum SbEntityKind SbEntityKind;
+#else
+typedef uint8_t SbEntityKind;
+#endif // __STDC_VERSION__ >= 202311L
+#endif // __cplusplus
+
+enum SbError
+#if defined(__cplusplus) || __STDC_VERSION__ >= 202311L
+  : uint8_t
+#endif // defined(__cplusplus) || __STDC_VERSION__ >= 202311L
+ {
+  SB_ERROR_TOO_FAR_TO_CONNECT = 0,
+  SB_ERROR_ENTITY_IN_THE_WAY = 1,
+  SB_ERROR_CANNOT_UPGRADE_UNDERGROUND = 2,
+  SB_ERROR_BELT_LINE_BROKEN = 3,
+};
+#ifndef __cplusplus
+#if __STDC_VERSION__ >= 202311L
+typedef enum SbError SbError;
+#else
+typedef uint8_t SbError;
+#endif // __STDC_VERSION__ >= 202311L
+#endif // __cplusplus
+
+/**
+ * A drag in progress; see `sb_drag_start`.
+ */
+typedef struct SbDrag SbDrag;
+
+/**
+ * An entity on a tile. Fields are plain bytes, so that values from C are
+ * checked rather than trusted.
+ */
+typedef struct SbEntity {
+  /**
+   * An `SbEntityKind`.
+   */
+  uint8_t kind;
+  /**
+   * An `SbDirection`; ignored for obstacles and impassable tiles.
+   */
+  uint8_t direction;
+  /**
+   * 1 (yellow) to 3 (blue), as in test grids; ignored for obstacles and
+   * impassable tiles.
+   */
+  uint8_t tier;
+  /**
+   * For undergrounds and loader-likes: whether items go into it from the
+   * belt behind it (the entrance), rather than out of it.
+   */
+  bool is_input;
+} SbEntity;
+
+typedef bool (*SbGetEntity)(void *user_data, int32_t x, int32_t y, struct SbEntity *out);
+
+typedef void (*SbBuild)(void *user_data, int32_t x, int32_t y, const struct SbEntity *entity);
+
+typedef void (*SbTileAction)(void *user_data, int32_t x, int32_t y);
+
+typedef void (*SbUpgrade)(void *user_data, int32_t x, int32_t y, uint8_t tier);
+
+typedef void (*SbOnError)(void *user_data, int32_t x, int32_t y, uint8_t error);
+
+/**
+ * The game's world, and where to report errors. Every callback gets
+ * `user_data` first. Only `on_error` may be null.
+ */
+typedef struct SbCallbacks {
+  void *user_data;
+  /**
+   * Writes the entity at a tile to `out` and returns true, or returns
+   * false if the tile is empty.
+   */
+  SbGetEntity get_entity;
+  /**
+   * Builds an entity, replacing whatever is on the tile.
+   */
+  SbBuild build;
+  /**
+   * Removes the entity on a tile.
+   */
+  SbTileAction mine;
+  /**
+   * Turns an underground around: swaps input and output, and reverses its
+   * direction.
+   */
+  SbTileAction flip;
+  /**
+   * Changes the tier of an underground or splitter, keeping the rest.
+   */
+  SbUpgrade upgrade;
+  /**
+   * Called once per error (an `SbError`) at a tile, as the drag reports it.
+   */
+  SbOnError on_error;
+} SbCallbacks;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Starts a drag at `(x, y)` placing belts of `tier` (1 to 3) facing
+ * `direction` (an `SbDirection`), as the first click does.
+ *
+ * Returns null if a callback other than `on_error` is null, an argument is
+ * out of range, the world has an invalid entity, or the engine panicked.
+ * Otherwise the drag must be ended with `sb_drag_end`.
+ *
+ * # Safety
+ *
+ * `callbacks` must point to valid callbacks, which must stay valid (with
+ * their `user_data`) until the drag ends. The callbacks must not call back
+ * into the drag.
+ */
+struct SbDrag *sb_drag_start(const struct SbCallbacks *callbacks,
+                             uint8_t tier,
+                             int32_t x,
+                             int32_t y,
+                             uint8_t direction);
+
+/**
+ * Moves the cursor to `(x, y)`, extending or retracting the drag along its
+ * line.
+ *
+ * # Safety
+ *
+ * `drag` must be null or a drag from `sb_drag_start` that hasn't ended.
+ */
+SbResult sb_drag_interpolate(struct SbDrag *drag, int32_t x, int32_t y);
+
+/**
+ * Rotates the drag towards the cursor at `(x, y)`, which must be off the
+ * drag's line; returns `NotRotated` otherwise.
+ *
+ * # Safety
+ *
+ * `drag` must be null or a drag from `sb_drag_start` that hasn't ended.
+ */
+SbResult sb_drag_rotate(struct SbDrag *drag, int32_t x, int32_t y);
+
+/**
+ * Ends the drag, freeing it. Everything it did has already been reported.
+ *
+ * # Safety
+ *
+ * `drag` must be null or a drag from `sb_drag_start` that hasn't ended.
+ */
+void sb_drag_end(struct SbDrag *drag);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* SMART_BELT_H */
@@ -0,0 +1,541 @@
+//! A C ABI for the drag engine, to embed it in a game. See
+//! `include/smart_belt.h` (generated by `build.rs`) for the API.
+//!
+//! The game keeps its own world and exposes it through [`SbCallbacks`]. A drag
+//! reads the tiles it may need through `get_entity` into a mirror, and after
+//! each cursor move replays its steps over the mirror with
+//! [`replay_drag_steps`], the same way the test suite runs them. Whatever the
+//! replay changed since the last move is then sent back to the game as
+//! `build`, `mine`, `flip` and `upgrade` calls, and new errors as `on_error`.
+//!
+//! Entities are per tile, as in the model: a splitter is two `Splitter` tiles,
+//! and each underground of a pair is flipped or upgraded by its own call.
+
+use std::collections::HashSet;
+use std::ffi::c_void;
+use std::panic::{AssertUnwindSafe, catch_unwind};
+
+use prototype_abstract::smart_belt::action::Error;
+use prototype_abstract::test_case::{DragStep, replay_drag_steps};
+use prototype_abstract::test_entity::{IoType, TestEntity};
+use prototype_abstract::{
+    BELT_TIERS, BeltCollidable, BeltTier, BoundingBox, Direction, TilePosition, TileVec, WorldImpl,
+    pos,
+};
+
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SbDirection {
+    North = 0,
+    East = 1,
+    South = 2,
+    West = 3,
+}
+
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SbEntityKind {
+    Belt = 0,
+    UndergroundBelt = 1,
+    Splitter = 2,
+    /// Loaders and linked belts.
+    LoaderLike = 3,
+    /// Any other entity, or a tile belts can't be built on.
+    Obstacle = 4,
+    /// A tile undergrounds can't pass under either.
+    ImpassableTile = 5,
+}
+
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SbError {
+    TooFarToConnect = 0,
+    EntityInTheWay = 1,
+    CannotUpgradeUnderground = 2,
+    BeltLineBroken = 3,
+}
+
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SbResult {
+    Ok = 0,
+    /// `sb_drag_rotate` with the cursor on the belt line; nothing happened.
+    NotRotated = 1,
+    /// A null drag, or an out of range tier or direction.
+    InvalidArgument = 2,
+    /// `get_entity` gave an entity with an out of range field. The drag is
+    /// left as it was before the call.
+    InvalidEntity = 3,
+    /// The engine panicked (a bug; the message went to stderr). The drag may
+    /// be half-way through the call, and should only be ended.
+    InternalError = 4,
+}
+
+/// An entity on a tile. Fields are plain bytes, so that values from C are
+/// checked rather than trusted.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SbEntity {
+    /// An `SbEntityKind`.
+    pub kind: u8,
+    /// An `SbDirection`; ignored for obstacles and impassable tiles.
+    pub direction: u8,
+    /// 1 (yellow) to 3 (blue), as in test grids; ignored for obstacles and
+    /// impassable tiles.
+    pub tier: u8,
+    /// For undergrounds and loader-likes: whether items go into it from the
+    /// belt behind it (the entrance), rather than out of it.
+    pub is_input: bool,
+}
+
+pub type SbGetEntity = Option<
+    unsafe extern "C" fn(user_data: *mut c_void, x: i32, y: i32, out: *mut SbEntity) -> bool,
+>;
+pub type SbBuild =
+    Option<unsafe extern "C" fn(user_data: *mut c_void, x: i32, y: i32, entity: *const SbEntity)>;
+pub type SbTileAction = Option<unsafe extern "C" fn(user_data: *mut c_void, x: i32, y: i32)>;
+pub type SbUpgrade = Option<unsafe extern "C" fn(user_data: *mut c_void, x: i32, y: i32, tier: u8)>;
+pub type SbOnError =
+    Option<unsafe extern "C" fn(user_data: *mut c_void, x: i32, y: i32, error: u8)>;
+
+/// The game's world, and where to report errors. Every callback gets
+/// `user_data` first. Only `on_error` may be null.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct SbCallbacks {
+    pub user_data: *mut c_void,
+    /// Writes the entity at a tile to `out` and returns true, or returns
+    /// false if the tile is empty.
+    pub get_entity: SbGetEntity,
+    /// Builds an entity, replacing whatever is on the tile.
+    pub build: SbBuild,
+    /// Removes the entity on a tile.
+    pub mine: SbTileAction,
+    /// Turns an underground around: swaps input and output, and reverses its
+    /// direction.
+    pub flip: SbTileAction,
+    /// Changes the tier of an underground or splitter, keeping the rest.
+    pub upgrade: SbUpgrade,
+    /// Called once per error (an `SbError`) at a tile, as the drag reports it.
+    pub on_error: SbOnError,
+}
+
+/// Tiles further than this from every cursor position can't affect a drag:
+/// the longest underground, and a tile either side for belt curvature.
+fn load_margin() -> i32 {
+    let longest = BELT_TIERS.iter().map(|t| t.underground_distance).max();
+    longest.unwrap_or(0) as i32 + 2
+}
+
+/// A drag in progress; see `sb_drag_start`.
+pub struct SbDrag {
+    callbacks: SbCallbacks,
+    tier: BeltTier,
+    start: TilePosition,
+    direction: Direction,
+    steps: Vec<DragStep>,
+    /// The world as the game had it before the drag, on `loaded` tiles.
+    before: WorldImpl,
+    loaded: BoundingBox,
+    /// The world as last reported to the game.
+    current: WorldImpl,
+    reported_errors: HashSet<(TilePosition, Error)>,
+}
+
+struct InvalidEntity;
+
+impl SbDrag {
+    /// Reads every tile within the margin of `position` not read yet.
+    ///
+    /// The loaded area is a box around every cursor position so far, which
+    /// also holds every position the drag projects them to (including
+    /// rotation pivots).
+    fn load_around(&mut self, position: TilePosition) -> Result<(), InvalidEntity> {
+        let margin = load_margin();
+        let around = BoundingBox::new(
+            position - TileVec::new(margin, margin),
+            position + TileVec::new(margin + 1, margin + 1),
+        );
+        let bounds = if self.loaded.is_empty() {
+            around
+        } else {
+            self.loaded.union(&around)
+        };
+        let mut loaded = Vec::new();
+        for y in bounds.min.y..bounds.max.y {
+            for x in bounds.min.x..bounds.max.x {
+                let p = pos(x, y);
+                if !self.loaded.contains(p)
+                    && let Some(entity) = self.read_entity(p)?
+                {
+                    loaded.push((p, entity));
+                }
+            }
+        }
+        for (p, entity) in loaded {
+            self.before.build(p, entity.clone());
+            self.current.build(p, entity);
+        }
+        self.loaded = bounds;
+        Ok(())
+    }
+
+    fn read_entity(&self, p: TilePosition) -> Result<Option<BeltCollidable>, InvalidEntity> {
+        let get_entity = self.callbacks.get_entity.expect("checked on start");
+        let mut entity = SbEntity {
+            kind: 0,
+            direction: 0,
+            tier: 0,
+            is_input: false,
+        };
+        // SAFETY: the caller of `sb_drag_start` vouched for the callbacks
+        if !unsafe { get_entity(self.callbacks.user_data, p.x, p.y, &mut entity) } {
+            return Ok(None);
+        }
+        entity.to_entity().map(Some).ok_or(InvalidEntity)
+    }
+
+    /// Moves the cursor to `position` with `step`, and reports what changed.
+    fn step(&mut self, position: TilePosition, step: DragStep) -> SbResult {
+        if self.load_around(position).is_err() {
+            return SbResult::InvalidEntity;
+        }
+        self.steps.push(step);
+        if !self.replay() {
+            // Only rotations can fail
+            self.steps.pop();
+            return SbResult::NotRotated;
+        }
+        SbResult::Ok
+    }
+
+    /// Runs the drag from the start over the world before it, and reports
+    /// the difference from what was last reported. Returns false, reporting
+    /// nothing, if the last step is a rotation that fails.
+    fn replay(&mut self) -> bool {
+        let mut world = self.before.clone();
+        let (errors, rotated) = replay_drag_steps(
+            &mut world,
+            self.tier,
+            self.start,
+            self.direction,
+            &self.steps,
+        );
+        if !rotated {
+            return false;
+        }
+        self.report_changes(&world);
+        self.current = world;
+
+        for (p, error) in errors {
+            if self.reported_errors.contains(&(p, error.clone())) {
+                continue;
+            }
+            if let Some(on_error) = self.callbacks.on_error {
+                // SAFETY: the caller of `sb_drag_start` vouched for the callbacks
+                unsafe {
+                    on_error(
+                        self.callbacks.user_data,
+                        p.x,
+                        p.y,
+                        SbError::from(&error) as u8,
+                    )
+                };
+            }
+            self.reported_errors.insert((p, error));
+        }
+        true
+    }
+
+    fn report_changes(&self, world: &WorldImpl) {
+        let SbCallbacks {
+            user_data,
+            build,
+            mine,
+            flip,
+            upgrade,
+            ..
+        } = self.callbacks;
+        let (build, mine) = (build.expect("checked"), mine.expect("checked"));
+        let (flip, upgrade) = (flip.expect("checked"), upgrade.expect("checked"));
+
+        let mut changed = world
+            .entities
+            .keys()
+            .chain(self.current.entities.keys())
+            .copied()
+            .filter(|&p| world.get(p) != self.current.get(p))
+            .collect::<Vec<_>>();
+        changed.sort_by_key(|p| (p.y, p.x));
+        changed.dedup();
+        // SAFETY: the caller of `sb_drag_start` vouched for the callbacks
+        unsafe {
+            for p in changed {
+                match (self.current.get(p), world.get(p)) {
+                    (_, None) => mine(user_data, p.x, p.y),
+                    (Some(old), Some(new)) if is_flip(old, new) => flip(user_data, p.x, p.y),
+                    (Some(old), Some(new)) if is_upgrade(old, new) => {
+                        let tier = SbEntity::from(new).tier;
+                        upgrade(user_data, p.x, p.y, tier)
+                    }
+                    (_, Some(new)) => build(user_data, p.x, p.y, &SbEntity::from(new)),
+                }
+            }
+        }
+    }
+}
+
+fn is_flip(old: &BeltCollidable, new: &BeltCollidable) -> bool {
+    match (old, new) {
+        (BeltCollidable::UndergroundBelt(old), BeltCollidable::UndergroundBelt(new)) => {
+            let mut flipped = old.clone();
+            flipped.flip_self();
+            &flipped == new
+        }
+        _ => false,
+    }
+}
+
+fn is_upgrade(old: &BeltCollidable, new: &BeltCollidable) -> bool {
+    match (old, new) {
+        (BeltCollidable::UndergroundBelt(old), BeltCollidable::UndergroundBelt(new)) => {
+            old.direction == new.direction && old.is_input == new.is_input
+        }
+        (BeltCollidable::Splitter(old), BeltCollidable::Splitter(new)) => {
+            old.direction == new.direction
+        }
+        _ => false,
+    }
+}
+
+impl From<&Error> for SbError {
+    fn from(error: &Error) -> Self {
+        match error {
+            Error::TooFarToConnect => SbError::TooFarToConnect,
+            Error::EntityInTheWay => SbError::EntityInTheWay,
+            Error::CannotUpgradeUnderground => SbError::CannotUpgradeUnderground,
+            Error::BeltLineBroken => SbError::BeltLineBroken,
+        }
+    }
+}
+
+fn kind_from_ffi(kind: u8) -> Option<SbEntityKind> {
+    Some(match kind {
+        0 => SbEntityKind::Belt,
+        1 => SbEntityKind::UndergroundBelt,
+        2 => SbEntityKind::Splitter,
+        3 => SbEntityKind::LoaderLike,
+        4 => SbEntityKind::Obstacle,
+        5 => SbEntityKind::ImpassableTile,
+        _ => return None,
+    })
+}
+
+impl SbEntity {
+    /// The entity, or `None` if a field is out of range.
+    pub fn to_entity(&self) -> Option<BeltCollidable> {
+        let (direction, tier) = (self.direction, self.tier as usize);
+        let io_type = IoType::from(self.is_input);
+        let entity = match kind_from_ffi(self.kind)? {
+            SbEntityKind::Belt => TestEntity::Belt { direction, tier },
+            SbEntityKind::UndergroundBelt => TestEntity::UndergroundBelt {
+                direction,
+                tier,
+                io_type,
+            },
+            SbEntityKind::Splitter => TestEntity::Splitter { direction, tier },
+            SbEntityKind::LoaderLike => TestEntity::Loader {
+                direction,
+                tier,
+                io_type,
+            },
+            SbEntityKind::Obstacle => TestEntity::Obstacle,
+            SbEntityKind::ImpassableTile => TestEntity::Impassable,
+        };
+        entity.to_entity().ok()
+    }
+}
+
+impl From<&BeltCollidable> for SbEntity {
+    fn from(entity: &BeltCollidable) -> Self {
+        let (kind, direction, tier, io_type) = match TestEntity::from(entity) {
+            TestEntity::Belt { direction, tier } => (SbEntityKind::Belt, direction, tier, None),
+            TestEntity::UndergroundBelt {
+                direction,
+                tier,
+                io_type,
+            } => (
+                SbEntityKind::UndergroundBelt,
+                direction,
+                tier,
+                Some(io_type),
+            ),
+            TestEntity::Splitter { direction, tier } => {
+                (SbEntityKind::Splitter, direction, tier, None)
+            }
+            TestEntity::Loader {
+                direction,
+                tier,
+                io_type,
+            } => (SbEntityKind::LoaderLike, direction, tier, Some(io_type)),
+            TestEntity::Obstacle => (SbEntityKind::Obstacle, 0, 1, None),
+            TestEntity::Impassable => (SbEntityKind::ImpassableTile, 0, 1, None),
+        };
+        SbEntity {
+            kind: kind as u8,
+            direction,
+            tier: tier as u8,
+            is_input: io_type.is_some_and(IoType::is_input),
+        }
+    }
+}
+
+/// Starts a drag at `(x, y)` placing belts of `tier` (1 to 3) facing
+/// `direction` (an `SbDirection`), as the first click does.
+///
+/// Returns null if a callback other than `on_error` is null, an argument is
+/// out of range, the world has an invalid entity, or the engine panicked.
+/// Otherwise the drag must be ended with `sb_drag_end`.
+///
+/// # Safety
+///
+/// `callbacks` must point to valid callbacks, which must stay valid (with
+/// their `user_data`) until the drag ends. The callbacks must not call back
+/// into the drag.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sb_drag_start(
+    callbacks: *const SbCallbacks,
+    tier: u8,
+    x: i32,
+    y: i32,
+    direction: u8,
+) -> *mut SbDrag {
+    // SAFETY: passed on from the caller
+    catch_panic(std::ptr::null_mut(), || unsafe {
+        drag_start(callbacks, tier, pos(x, y), direction)
+    })
+}
+
+/// `sb_drag_start`, which may panic.
+///
+/// # Safety
+///
+/// As for `sb_drag_start`.
+unsafe fn drag_start(
+    callbacks: *const SbCallbacks,
+    tier: u8,
+    start: TilePosition,
+    direction: u8,
+) -> *mut SbDrag {
+    // SAFETY: the caller vouches for the pointer
+    let Some(&callbacks) = (unsafe { callbacks.as_ref() }) else {
+        return std::ptr::null_mut();
+    };
+    let SbCallbacks {
+        get_entity,
+        build,
+        mine,
+        flip,
+        upgrade,
+        ..
+    } = callbacks;
+    let has_callbacks = get_entity.is_some()
+        && build.is_some()
+        && mine.is_some()
+        && flip.is_some()
+        && upgrade.is_some();
+    let (Some(tier), Some(direction), true) = (
+        BeltTier::from_number(tier as usize),
+        Direction::from_ordinal(direction),
+        has_callbacks,
+    ) else {
+        return std::ptr::null_mut();
+    };
+
+    let mut drag = SbDrag {
+        callbacks,
+        tier,
+        start,
+        direction,
+        steps: Vec::new(),
+        before: WorldImpl::new(),
+        loaded: BoundingBox::zero(),
+        current: WorldImpl::new(),
+        reported_errors: HashSet::new(),
+    };
+    if drag.load_around(start).is_err() {
+        return std::ptr::null_mut();
+    }
+    // With no steps, there is no rotation to fail
+    drag.replay();
+    Box::into_raw(Box::new(drag))
+}
+
+/// Moves the cursor to `(x, y)`, extending or retracting the drag along its
+/// line.
+///
+/// # Safety
+///
+/// `drag` must be null or a drag from `sb_drag_start` that hasn't ended.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sb_drag_interpolate(drag: *mut SbDrag, x: i32, y: i32) -> SbResult {
+    // SAFETY: the caller vouches for the pointer
+    let Some(drag) = (unsafe { drag.as_mut() }) else {
+        return SbResult::InvalidArgument;
+    };
+    catch_panic(SbResult::InternalError, || {
+        drag.step(pos(x, y), DragStep::MoveTo(pos(x, y)))
+    })
+}
+
+/// Rotates the drag towards the cursor at `(x, y)`, which must be off the
+/// drag's line; returns `NotRotated` otherwise.
+///
+/// # Safety
+///
+/// `drag` must be null or a drag from `sb_drag_start` that hasn't ended.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sb_drag_rotate(drag: *mut SbDrag, x: i32, y: i32) -> SbResult {
+    // SAFETY: the caller vouches for the pointer
+    let Some(drag) = (unsafe { drag.as_mut() }) else {
+        return SbResult::InvalidArgument;
+    };
+    catch_panic(SbResult::InternalError, || {
+        drag.step(pos(x, y), DragStep::Rotate(pos(x, y)))
+    })
+}
+
+/// Ends the drag, freeing it. Everything it did has already been reported.
+///
+/// # Safety
+///
+/// `drag` must be null or a drag from `sb_drag_start` that hasn't ended.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sb_drag_end(drag: *mut SbDrag) {
+    if !drag.is_null() {
+        // SAFETY: the caller vouches for the pointer, which came from Box::into_raw
+        let drag = unsafe { Box::from_raw(drag) };
+        catch_panic((), || drop(drag));
+    }
+}
+
+/// Runs `f`, returning `on_panic` if it panics: unwinding into C would abort
+/// the game.
+fn catch_panic<R>(on_panic: R, f: impl FnOnce() -> R) -> R {
+    catch_unwind(AssertUnwindSafe(f)).unwrap_or(on_panic)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_panics_become_results() {
+        let result = catch_panic(SbResult::InternalError, || panic!("a bug"));
+        assert_eq!(result, SbResult::InternalError);
+        assert_eq!(
+            catch_panic(SbResult::InternalError, || SbResult::Ok),
+            SbResult::Ok
+        );
+    }
+}
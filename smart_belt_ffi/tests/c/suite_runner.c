@@ -0,0 +1,180 @@
+/*
+ * Runs drags read from stdin through the C API, over a world kept in C, and
+ * prints the world and errors after each drag. `tests/c_suite.rs` feeds it
+ * the YAML test suite and checks the output.
+ *
+ * Input, one command per line:
+ *   entity X Y KIND DIRECTION TIER IS_INPUT   add to the world
+ *   drag TIER X Y DIRECTION                   start a drag
+ *   move X Y / rotate X Y                     cursor steps
+ *   end                                       end the drag, print the result
+ *   clear                                     empty the world
+ *
+ * Output, after each `end`:
+ *   entity ... (as above) for every entity, then
+ *   error X Y CODE for every error, then
+ *   done
+ */
+
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+#include "smart_belt.h"
+
+#define MAX_TILES 4096
+
+typedef struct Tile {
+    int32_t x, y;
+    SbEntity entity;
+} Tile;
+
+typedef struct World {
+    Tile tiles[MAX_TILES];
+    size_t num_tiles;
+    int32_t errors[MAX_TILES][3];
+    size_t num_errors;
+} World;
+
+static Tile *find(World *world, int32_t x, int32_t y) {
+    for (size_t i = 0; i < world->num_tiles; i++) {
+        if (world->tiles[i].x == x && world->tiles[i].y == y) {
+            return &world->tiles[i];
+        }
+    }
+    return NULL;
+}
+
+static void fail(const char *message) {
+    fprintf(stderr, "suite_runner: %s\n", message);
+    exit(1);
+}
+
+static bool get_entity(void *user_data, int32_t x, int32_t y, SbEntity *out) {
+    Tile *tile = find(user_data, x, y);
+    if (tile == NULL) {
+        return false;
+    }
+    *out = tile->entity;
+    return true;
+}
+
+static void build(void *user_data, int32_t x, int32_t y, const SbEntity *entity) {
+    World *world = user_data;
+    Tile *tile = find(world, x, y);
+    if (tile == NULL) {
+        if (world->num_tiles == MAX_TILES) {
+            fail("too many tiles");
+        }
+        tile = &world->tiles[world->num_tiles++];
+        tile->x = x;
+        tile->y = y;
+    }
+    tile->entity = *entity;
+}
+
+static void mine(void *user_data, int32_t x, int32_t y) {
+    World *world = user_data;
+    Tile *tile = find(world, x, y);
+    if (tile == NULL) {
+        fail("mined an empty tile");
+    }
+    *tile = world->tiles[--world->num_tiles];
+}
+
+static void flip(void *user_data, int32_t x, int32_t y) {
+    Tile *tile = find(user_data, x, y);
+    if (tile == NULL || tile->entity.kind != SB_ENTITY_KIND_UNDERGROUND_BELT) {
+        fail("flipped something other than an underground");
+    }
+    tile->entity.is_input = !tile->entity.is_input;
+    tile->entity.direction = (tile->entity.direction + 2) % 4;
+}
+
+static void upgrade(void *user_data, int32_t x, int32_t y, uint8_t tier) {
+    Tile *tile = find(user_data, x, y);
+    if (tile == NULL) {
+        fail("upgraded an empty tile");
+    }
+    tile->entity.tier = tier;
+}
+
+static void on_error(void *user_data, int32_t x, int32_t y, uint8_t error) {
+    World *world = user_data;
+    if (world->num_errors == MAX_TILES) {
+        fail("too many errors");
+    }
+    int32_t *entry = world->errors[world->num_errors++];
+    entry[0] = x;
+    entry[1] = y;
+    entry[2] = error;
+}
+
+int main(void) {
+    static World world;
+    SbCallbacks callbacks = {
+        .user_data = &world,
+        .get_entity = get_entity,
+        .build = build,
+        .mine = mine,
+        .flip = flip,
+        .upgrade = upgrade,
+        .on_error = on_error,
+    };
+    SbDrag *drag = NULL;
+
+    char line[256];
+    while (fgets(line, sizeof line, stdin) != NULL) {
+        char command[16];
+        int x, y, kind, direction, tier, is_input;
+        if (sscanf(line, "%15s", command) != 1) {
+            continue;
+        }
+        if (strcmp(command, "entity") == 0) {
+            if (sscanf(line, "entity %d %d %d %d %d %d", &x, &y, &kind, &direction, &tier,
+                       &is_input) != 6) {
+                fail("bad entity line");
+            }
+            SbEntity entity = {kind, direction, tier, is_input};
+            build(&world, x, y, &entity);
+        } else if (strcmp(command, "drag") == 0) {
+            if (sscanf(line, "drag %d %d %d %d", &tier, &x, &y, &direction) != 4) {
+                fail("bad drag line");
+            }
+            world.num_errors = 0;
+            drag = sb_drag_start(&callbacks, tier, x, y, direction);
+            if (drag == NULL) {
+                fail("sb_drag_start failed");
+            }
+        } else if (strcmp(command, "move") == 0 || strcmp(command, "rotate") == 0) {
+            if (sscanf(line, "%*s %d %d", &x, &y) != 2) {
+                fail("bad step line");
+            }
+            SbResult result = command[0] == 'm' ? sb_drag_interpolate(drag, x, y)
+                                                : sb_drag_rotate(drag, x, y);
+            if (result != SB_RESULT_OK) {
+                fprintf(stderr, "suite_runner: step %s %d %d gave %d\n", command, x, y, result);
+                exit(1);
+            }
+        } else if (strcmp(command, "end") == 0) {
+            sb_drag_end(drag);
+            drag = NULL;
+            for (size_t i = 0; i < world.num_tiles; i++) {
+                Tile *tile = &world.tiles[i];
+                printf("entity %d %d %d %d %d %d\n", tile->x, tile->y, tile->entity.kind,
+                       tile->entity.direction, tile->entity.tier, tile->entity.is_input);
+            }
+            for (size_t i = 0; i < world.num_errors; i++) {
+                printf("error %d %d %d\n", world.errors[i][0], world.errors[i][1],
+                       world.errors[i][2]);
+            }
+            printf("done\n");
+            fflush(stdout);
+        } else if (strcmp(command, "clear") == 0) {
+            world.num_tiles = 0;
+        } else {
+            fail("unknown command");
+        }
+    }
+    return 0;
+}
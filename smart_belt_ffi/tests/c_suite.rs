@@ -0,0 +1,254 @@
+//! Runs the YAML test suite through the C API: compiles `tests/c/suite_runner.c`
+//! against the built library, feeds it every case's drags, and checks the
+//! world it ends up with (through the callbacks alone) against the case.
+#![cfg(target_os = "linux")]
+
+use std::collections::HashSet;
+use std::fmt::Write as _;
+use std::fs;
+use std::io::Write as _;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use prototype_abstract::smart_belt::action::Error;
+use prototype_abstract::test_case::{DragStep, SuiteCase, TestVariant};
+use prototype_abstract::{BeltTier, Direction, TilePosition, WorldImpl, pos};
+use smart_belt_ffi::{SbEntity, SbError};
+
+/// A drag of a case, and the world and errors expected after it, if checked.
+struct Drag {
+    start: TilePosition,
+    direction: Direction,
+    tier: BeltTier,
+    steps: Vec<DragStep>,
+    expected: Option<(WorldImpl, HashSet<(TilePosition, Error)>)>,
+    /// Wiggles may report more errors than expected, as in `check_test_case`.
+    extra_errors_allowed: bool,
+}
+
+struct Case {
+    name: String,
+    before: WorldImpl,
+    drags: Vec<Drag>,
+}
+
+fn suite_cases() -> Vec<Case> {
+    let suite_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("../test_suite");
+    let mut files = fs::read_dir(&suite_dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|e| e == "yaml"))
+        .collect::<Vec<_>>();
+    files.sort();
+
+    let mut cases = Vec::new();
+    for file in files {
+        let values: Vec<serde_yaml::Value> =
+            serde_yaml::from_str(&fs::read_to_string(&file).unwrap()).unwrap();
+        for value in &values {
+            let case = SuiteCase::from_value(value)
+                .unwrap_or_else(|e| panic!("{}: {e:#}", file.display()));
+            cases.extend(to_cases(case));
+        }
+    }
+    cases
+}
+
+/// Cases as drags; a drag case becomes one per variant it runs forwards.
+fn to_cases(case: SuiteCase) -> Vec<Case> {
+    match case {
+        SuiteCase::Drag(case) => {
+            let test = &case.entities;
+            case.variants()
+                .into_iter()
+                .filter(|&(reverse, _)| !reverse)
+                .map(|(_, variant)| Case {
+                    name: format!("{} ({variant:?})", case.name),
+                    before: test.before.clone(),
+                    drags: vec![Drag {
+                        start: test.start_pos,
+                        direction: test.belt_direction,
+                        tier: test.tier,
+                        steps: test.variant_steps(variant).unwrap(),
+                        expected: Some((test.after.clone(), test.expected_errors.clone())),
+                        extra_errors_allowed: variant != TestVariant::Normal,
+                    }],
+                })
+                .collect()
+        }
+        SuiteCase::Steps(case) => vec![Case {
+            name: case.name,
+            before: case.before,
+            drags: vec![Drag {
+                start: case.start_pos,
+                direction: case.belt_direction,
+                tier: case.tier,
+                steps: case.steps,
+                expected: Some((case.after, case.expected_errors)),
+                extra_errors_allowed: false,
+            }],
+        }],
+        SuiteCase::Scenario(case) => vec![Case {
+            name: case.name,
+            before: case.before,
+            drags: case
+                .drags
+                .into_iter()
+                .map(|drag| Drag {
+                    start: drag.start_pos,
+                    direction: drag.belt_direction,
+                    tier: drag.tier,
+                    steps: drag.steps,
+                    expected: drag.expected,
+                    extra_errors_allowed: false,
+                })
+                .collect(),
+        }],
+    }
+}
+
+fn tier_number(tier: BeltTier) -> usize {
+    tier.tier_index() + 1
+}
+
+/// The runner's input for `cases`; see `suite_runner.c`.
+fn runner_input(cases: &[Case]) -> String {
+    let mut input = String::new();
+    for case in cases {
+        input += "clear\n";
+        for (p, entity) in &case.before.entities {
+            let e = SbEntity::from(entity);
+            let (kind, direction, tier, is_input) = (e.kind, e.direction, e.tier, e.is_input);
+            writeln!(
+                input,
+                "entity {} {} {kind} {direction} {tier} {}",
+                p.x, p.y, is_input as u8
+            )
+            .unwrap();
+        }
+        for drag in &case.drags {
+            let (start, direction) = (drag.start, drag.direction as u8);
+            let tier = tier_number(drag.tier);
+            writeln!(input, "drag {tier} {} {} {direction}", start.x, start.y).unwrap();
+            for step in &drag.steps {
+                match step {
+                    DragStep::MoveTo(p) => writeln!(input, "move {} {}", p.x, p.y),
+                    DragStep::Rotate(p) => writeln!(input, "rotate {} {}", p.x, p.y),
+                }
+                .unwrap();
+            }
+            input += "end\n";
+        }
+    }
+    input
+}
+
+/// The world and errors (as codes) after each drag, from the runner's output.
+fn parse_output(output: &str) -> Vec<(WorldImpl, HashSet<(TilePosition, u8)>)> {
+    let mut results = Vec::new();
+    let mut world = WorldImpl::new();
+    let mut errors = HashSet::new();
+    for line in output.lines() {
+        let words = line.split_whitespace().collect::<Vec<_>>();
+        let numbers = words[1..]
+            .iter()
+            .map(|w| w.parse::<i32>().unwrap())
+            .collect::<Vec<_>>();
+        match words[0] {
+            "entity" => {
+                let [x, y, kind, direction, tier, is_input] = numbers[..] else {
+                    panic!("Bad entity line {line:?}");
+                };
+                let entity = SbEntity {
+                    kind: kind as u8,
+                    direction: direction as u8,
+                    tier: tier as u8,
+                    is_input: is_input != 0,
+                };
+                world.build(pos(x, y), entity.to_entity().unwrap());
+            }
+            "error" => {
+                let [x, y, code] = numbers[..] else {
+                    panic!("Bad error line {line:?}");
+                };
+                errors.insert((pos(x, y), code as u8));
+            }
+            "done" => results.push((std::mem::take(&mut world), std::mem::take(&mut errors))),
+            _ => panic!("Unexpected output {line:?}"),
+        }
+    }
+    results
+}
+
+fn compile_runner() -> PathBuf {
+    let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    // The test binary is in the same directory as the library
+    let lib_dir = std::env::current_exe()
+        .unwrap()
+        .parent()
+        .unwrap()
+        .to_path_buf();
+    let runner = Path::new(env!("CARGO_TARGET_TMPDIR")).join("suite_runner");
+    let status = Command::new("cc")
+        .args(["-std=c11", "-Wall", "-Wextra", "-Werror", "-I"])
+        .arg(manifest_dir.join("include"))
+        .arg(manifest_dir.join("tests/c/suite_runner.c"))
+        .arg("-L")
+        .arg(&lib_dir)
+        .arg(format!("-Wl,-rpath,{}", lib_dir.display()))
+        .arg("-lsmart_belt_ffi")
+        .arg("-o")
+        .arg(&runner)
+        .status()
+        .expect("Failed to run cc");
+    assert!(status.success(), "Compiling suite_runner.c failed");
+    runner
+}
+
+#[test]
+fn test_suite_through_c_api() {
+    let cases = suite_cases();
+    let runner = compile_runner();
+
+    let mut child = Command::new(&runner)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let input = runner_input(&cases);
+    let mut stdin = child.stdin.take().unwrap();
+    let writer = std::thread::spawn(move || stdin.write_all(input.as_bytes()).unwrap());
+    let output = child.wait_with_output().unwrap();
+    writer.join().unwrap();
+    assert!(output.status.success(), "suite_runner failed");
+    let mut results = parse_output(&String::from_utf8(output.stdout).unwrap()).into_iter();
+
+    let mut failures = Vec::new();
+    for case in &cases {
+        for (i, drag) in case.drags.iter().enumerate() {
+            let (world, errors) = results.next().expect("Missing output");
+            let Some((expected_world, expected_errors)) = &drag.expected else {
+                continue;
+            };
+            let expected_errors = expected_errors
+                .iter()
+                .map(|(p, error)| (*p, SbError::from(error) as u8))
+                .collect::<HashSet<_>>();
+            let errors_match = if drag.extra_errors_allowed && !expected_errors.is_empty() {
+                expected_errors.is_subset(&errors)
+            } else {
+                errors == expected_errors
+            };
+            if &world != expected_world || !errors_match {
+                failures.push(format!("{}, drag {}", case.name, i + 1));
+            }
+        }
+    }
+    assert!(
+        failures.is_empty(),
+        "{} of {} cases differ through the C API:\n{}",
+        failures.len(),
+        cases.len(),
+        failures.join("\n")
+    );
+}
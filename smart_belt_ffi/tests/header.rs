@@ -0,0 +1,24 @@
+//! Checks that the checked-in `include/smart_belt.h` matches the one `build.rs`
+//! generates. To update it:
+//!
+//! ```sh
+//! BLESS=1 cargo test -p smart_belt_ffi --test header
+//! ```
+
+use std::fs;
+use std::path::Path;
+
+#[test]
+fn header_is_up_to_date() {
+    let generated = include_str!(concat!(env!("OUT_DIR"), "/smart_belt.h"));
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("include/smart_belt.h");
+    if std::env::var_os("BLESS").is_some() {
+        fs::write(&path, generated).unwrap();
+        return;
+    }
+    let checked_in = fs::read_to_string(&path).unwrap();
+    assert!(
+        checked_in == generated,
+        "include/smart_belt.h is out of date; run with BLESS=1 to update it"
+    );
+}
@@ -0,0 +1,355 @@
+//! Lua bindings for the drag engine, to run Lua scripts against the reference
+//! implementation and compare them with the mod's TypeScript-compiled logic.
+//! See the README for the API.
+//!
+//! Values have the shapes the mod uses, so the same script can drive both:
+//! positions are `{x = .., y = ..}`, directions are `0` to `3` (north, east,
+//! south, west), entities are tables like `TestEntity` in
+//! `prototype_mod/common/test_entity.ts`, and errors are `ActionError` strings.
+//!
+//! A [`Drag`] is replayed with [`replay_drag_steps`] after every cursor move.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use mlua::{
+    FromLua, Function, IntoLua, Lua, LuaSerdeExt, MetaMethod, RegistryKey, Result, Table, UserData,
+    UserDataMethods, UserDataRef, Value,
+};
+use prototype_abstract::test_case::{DragStep, parse_world, print_world, replay_drag_steps};
+use prototype_abstract::test_entity::{TestEntity, parse_direction, parse_tier};
+use prototype_abstract::{BeltCollidable, BeltTier, Direction, TilePosition, WorldImpl, pos};
+
+/// The `smart_belt` module table.
+pub fn module(lua: &Lua) -> Result<Table<'_>> {
+    let exports = lua.create_table()?;
+    exports.set(
+        "new_world",
+        lua.create_function(|_, ()| Ok(World::new(WorldImpl::new())))?,
+    )?;
+    exports.set(
+        "parse_world",
+        lua.create_function(|lua, input: String| {
+            let (world, markers) = parse_world(&input).map_err(mlua::Error::external)?;
+            let markers = lua.create_sequence_from(markers.into_iter().map(LuaPosition))?;
+            Ok((World::new(world), markers))
+        })?,
+    )?;
+    exports.set(
+        "print_world",
+        lua.create_function(
+            |_, (world, markers): (UserDataRef<World>, Option<Vec<LuaPosition>>)| {
+                let markers = markers.unwrap_or_default();
+                Ok(world.print(&markers.into_iter().map(|p| p.0).collect::<Vec<_>>()))
+            },
+        )?,
+    )?;
+
+    exports.set(
+        "belt",
+        lua.create_function(|_, (direction, tier): (u8, Option<usize>)| {
+            LuaEntity::new(TestEntity::Belt {
+                direction,
+                tier: tier.unwrap_or(1),
+            })
+        })?,
+    )?;
+    exports.set(
+        "underground_belt",
+        lua.create_function(
+            |lua, (direction, io_type, tier): (u8, Value, Option<usize>)| {
+                LuaEntity::new(TestEntity::UndergroundBelt {
+                    direction,
+                    tier: tier.unwrap_or(1),
+                    io_type: lua.from_value(io_type)?,
+                })
+            },
+        )?,
+    )?;
+    exports.set(
+        "splitter",
+        lua.create_function(|_, (direction, tier): (u8, Option<usize>)| {
+            LuaEntity::new(TestEntity::Splitter {
+                direction,
+                tier: tier.unwrap_or(1),
+            })
+        })?,
+    )?;
+    exports.set(
+        "loader",
+        lua.create_function(
+            |lua, (direction, io_type, tier): (u8, Value, Option<usize>)| {
+                LuaEntity::new(TestEntity::Loader {
+                    direction,
+                    tier: tier.unwrap_or(1),
+                    io_type: lua.from_value(io_type)?,
+                })
+            },
+        )?,
+    )?;
+    exports.set(
+        "obstacle",
+        lua.create_function(|_, ()| LuaEntity::new(TestEntity::Obstacle))?,
+    )?;
+    exports.set(
+        "impassable",
+        lua.create_function(|_, ()| LuaEntity::new(TestEntity::Impassable))?,
+    )?;
+
+    exports.set(
+        "start_drag",
+        lua.create_function(
+            |lua,
+             (world, on_error, tier, start, direction): (
+                UserDataRef<World>,
+                Option<Function>,
+                LuaTier,
+                LuaPosition,
+                LuaDirection,
+            )| {
+                let on_error = on_error.map(|f| lua.create_registry_value(f)).transpose()?;
+                Drag::start(lua, &world, on_error, tier.0, start.0, direction.0)
+            },
+        )?,
+    )?;
+
+    let directions = lua.create_table()?;
+    for direction in [
+        Direction::North,
+        Direction::East,
+        Direction::South,
+        Direction::West,
+    ] {
+        directions.set(lua.to_value(&direction)?, direction as u8)?;
+    }
+    exports.set("direction", directions)?;
+    Ok(exports)
+}
+
+/// Makes `require("smart_belt")` return [`module`] in `lua`.
+pub fn preload(lua: &Lua) -> Result<()> {
+    let loaded: Table = lua.globals().get::<_, Table>("package")?.get("loaded")?;
+    loaded.set("smart_belt", module(lua)?)
+}
+
+/// `require("smart_belt")` from a standalone interpreter.
+#[cfg(feature = "module")]
+#[mlua::lua_module]
+fn smart_belt(lua: &Lua) -> Result<Table<'_>> {
+    module(lua)
+}
+
+/// A world shared between Lua and the drags on it.
+#[derive(Clone)]
+pub struct World(Rc<RefCell<WorldImpl>>);
+
+impl World {
+    pub fn new(world: WorldImpl) -> Self {
+        World(Rc::new(RefCell::new(world)))
+    }
+
+    fn print(&self, markers: &[TilePosition]) -> String {
+        let world = self.0.borrow();
+        print_world(&world, world.bounds(), markers)
+    }
+}
+
+impl UserData for World {
+    fn add_methods<'lua, M: UserDataMethods<'lua, Self>>(methods: &mut M) {
+        methods.add_method("get", |_, this, p: LuaPosition| {
+            Ok(this.0.borrow().get(p.0).cloned().map(LuaEntity))
+        });
+        methods.add_method("build", |_, this, (p, entity): (LuaPosition, LuaEntity)| {
+            this.0
+                .borrow_mut()
+                .try_build(p.0, entity.0)
+                .map(|_| ())
+                .map_err(mlua::Error::runtime)
+        });
+        methods.add_method("mine", |_, this, p: LuaPosition| {
+            this.0.borrow_mut().mine(p.0);
+            Ok(())
+        });
+        // `{position = .., entity = ..}` for every entity, in reading order
+        methods.add_method("entities", |lua, this, ()| {
+            let world = this.0.borrow();
+            let mut positions = world.entities.keys().copied().collect::<Vec<_>>();
+            positions.sort_by_key(|p| (p.y, p.x));
+            let entries = lua.create_table()?;
+            for p in positions {
+                let entry = lua.create_table()?;
+                entry.set("position", LuaPosition(p))?;
+                entry.set("entity", LuaEntity(world.entities[&p].clone()))?;
+                entries.push(entry)?;
+            }
+            Ok(entries)
+        });
+        methods.add_method("copy", |_, this, ()| {
+            Ok(World::new(this.0.borrow().clone()))
+        });
+        methods.add_meta_method(MetaMethod::ToString, |_, this, ()| Ok(this.print(&[])));
+        methods.add_meta_method(MetaMethod::Eq, |_, this, other: UserDataRef<World>| {
+            Ok(*this.0.borrow() == *other.0.borrow())
+        });
+    }
+}
+
+/// A drag in progress; see the module docs.
+pub struct Drag {
+    world: Rc<RefCell<WorldImpl>>,
+    before: WorldImpl,
+    tier: BeltTier,
+    start: TilePosition,
+    direction: Direction,
+    steps: Vec<DragStep>,
+    on_error: Option<RegistryKey>,
+    /// How many errors the replays so far have reported.
+    reported_errors: usize,
+}
+
+impl Drag {
+    fn start(
+        lua: &Lua,
+        world: &World,
+        on_error: Option<RegistryKey>,
+        tier: BeltTier,
+        start: TilePosition,
+        direction: Direction,
+    ) -> Result<Self> {
+        let mut drag = Drag {
+            world: world.0.clone(),
+            before: world.0.borrow().clone(),
+            tier,
+            start,
+            direction,
+            steps: Vec::new(),
+            on_error,
+            reported_errors: 0,
+        };
+        drag.replay(lua)?;
+        Ok(drag)
+    }
+
+    /// Adds `step` to the drag, unless it is a rotation that fails.
+    fn step(&mut self, lua: &Lua, step: DragStep) -> Result<bool> {
+        self.steps.push(step);
+        let rotated = self.replay(lua)?;
+        if !rotated {
+            self.steps.pop();
+        }
+        Ok(rotated)
+    }
+
+    /// Runs the steps over the world from before the drag, and reports the
+    /// new errors. Returns false, changing nothing, if the last step is a
+    /// rotation that fails.
+    fn replay(&mut self, lua: &Lua) -> Result<bool> {
+        let mut world = self.before.clone();
+        let (mut errors, rotated) = replay_drag_steps(
+            &mut world,
+            self.tier,
+            self.start,
+            self.direction,
+            &self.steps,
+        );
+        if !rotated {
+            return Ok(false);
+        }
+
+        *self.world.borrow_mut() = world;
+        let new_errors = errors.split_off(self.reported_errors.min(errors.len()));
+        self.reported_errors += new_errors.len();
+        if let Some(key) = &self.on_error {
+            let on_error: Function = lua.registry_value(key)?;
+            for (p, error) in new_errors {
+                on_error.call::<_, ()>((LuaPosition(p), lua.to_value(&error)?))?;
+            }
+        }
+        Ok(true)
+    }
+}
+
+impl UserData for Drag {
+    fn add_methods<'lua, M: UserDataMethods<'lua, Self>>(methods: &mut M) {
+        methods.add_method_mut("interpolate_to", |lua, this, target: LuaPosition| {
+            this.step(lua, DragStep::MoveTo(target.0)).map(|_| ())
+        });
+        // Returns whether the drag rotated; it does not if the cursor is on
+        // the drag line
+        methods.add_method_mut("rotate", |lua, this, cursor: LuaPosition| {
+            this.step(lua, DragStep::Rotate(cursor.0))
+        });
+        methods.add_method("tier", |_, this, ()| Ok(LuaTier(this.tier)));
+    }
+}
+
+/// An `{x = .., y = ..}` table.
+struct LuaPosition(TilePosition);
+
+impl<'lua> FromLua<'lua> for LuaPosition {
+    fn from_lua(value: Value<'lua>, lua: &'lua Lua) -> Result<Self> {
+        let table = Table::from_lua(value, lua)?;
+        Ok(LuaPosition(pos(table.get("x")?, table.get("y")?)))
+    }
+}
+
+impl<'lua> IntoLua<'lua> for LuaPosition {
+    fn into_lua(self, lua: &'lua Lua) -> Result<Value<'lua>> {
+        let table = lua.create_table()?;
+        table.set("x", self.0.x)?;
+        table.set("y", self.0.y)?;
+        Ok(Value::Table(table))
+    }
+}
+
+/// `0` to `3`: north, east, south, west.
+struct LuaDirection(Direction);
+
+impl<'lua> FromLua<'lua> for LuaDirection {
+    fn from_lua(value: Value<'lua>, lua: &'lua Lua) -> Result<Self> {
+        let direction = parse_direction(u8::from_lua(value, lua)?).map_err(runtime_error)?;
+        Ok(LuaDirection(direction))
+    }
+}
+
+/// A 1-based index into [`BELT_TIERS`].
+struct LuaTier(BeltTier);
+
+impl<'lua> FromLua<'lua> for LuaTier {
+    fn from_lua(value: Value<'lua>, lua: &'lua Lua) -> Result<Self> {
+        let tier = parse_tier(usize::from_lua(value, lua)?).map_err(runtime_error)?;
+        Ok(LuaTier(tier))
+    }
+}
+
+impl<'lua> IntoLua<'lua> for LuaTier {
+    fn into_lua(self, _: &'lua Lua) -> Result<Value<'lua>> {
+        Ok(Value::Integer(self.0.tier_index() as i64 + 1))
+    }
+}
+
+fn runtime_error(error: anyhow::Error) -> mlua::Error {
+    mlua::Error::runtime(format!("{error:#}"))
+}
+
+/// An entity table: `{kind = .., direction = .., tier = .., ioType = ..}`,
+/// a [`TestEntity`].
+struct LuaEntity(BeltCollidable);
+
+impl LuaEntity {
+    fn new(entity: TestEntity) -> Result<Self> {
+        entity.to_entity().map(LuaEntity).map_err(runtime_error)
+    }
+}
+
+impl<'lua> FromLua<'lua> for LuaEntity {
+    fn from_lua(value: Value<'lua>, lua: &'lua Lua) -> Result<Self> {
+        LuaEntity::new(lua.from_value(value)?)
+    }
+}
+
+impl<'lua> IntoLua<'lua> for LuaEntity {
+    fn into_lua(self, lua: &'lua Lua) -> Result<Value<'lua>> {
+        lua.to_value(&TestEntity::from(&self.0))
+    }
+}
@@ -0,0 +1,37 @@
+//! Runs a Lua script with the `smart_belt` module available to `require`, for
+//! when there is no standalone interpreter to load the module into.
+//!
+//! ```sh
+//! cargo run -p smart_belt_lua -- script.lua [args...]
+//! ```
+
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use clap::Parser;
+use mlua::Lua;
+
+#[derive(Debug, Parser)]
+struct Args {
+    #[arg(help = "Lua script to run")]
+    script: PathBuf,
+    #[arg(help = "Arguments for the script, in its `arg` table")]
+    args: Vec<String>,
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+    let source = fs::read_to_string(&args.script)
+        .with_context(|| format!("Reading {}", args.script.display()))?;
+
+    let lua = Lua::new();
+    smart_belt_lua::preload(&lua)?;
+    let arg = lua.create_sequence_from(args.args)?;
+    arg.set(0, args.script.display().to_string())?;
+    lua.globals().set("arg", arg)?;
+    lua.load(&source)
+        .set_name(format!("@{}", args.script.display()))
+        .exec()?;
+    Ok(())
+}
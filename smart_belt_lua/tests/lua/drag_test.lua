@@ -0,0 +1,45 @@
+local sb = require("smart_belt")
+
+local function drag(grid, steps, start, direction, tier)
+    local world = sb.parse_world(grid)
+    local errors = {}
+    local on_error = function(position, error)
+        errors[#errors + 1] = string.format("%d,%d %s", position.x, position.y, error)
+    end
+    local d = sb.start_drag(world, on_error, tier or 1, start or { x = 0, y = 0 }, direction or sb.direction.east)
+    for _, step in ipairs(steps) do
+        if step.rotate then
+            assert(d:rotate(step.rotate), "rotation failed")
+        else
+            d:interpolate_to(step)
+        end
+    end
+    return world, table.concat(errors, "; ")
+end
+
+local function assert_world(world, expected)
+    assert(world == sb.parse_world(expected), "\n" .. tostring(world))
+end
+
+-- Undergrounds under an obstacle
+local world, errors = drag("_ _ X", { { x = 4, y = 0 } })
+assert_world(world, "> >i X >o >")
+assert(errors == "", errors)
+
+-- Errors are reported once, as the cursor passes them
+world, errors = drag("_ _ X X X X X", { { x = 8, y = 0 } })
+assert_world(world, "> > X X X X X > >")
+assert(errors == "7,0 too_far_to_connect", errors)
+
+-- Dragging back leaves what was built
+world = drag("_ _ X", { { x = 4, y = 0 }, { x = 1, y = 0 } })
+assert_world(world, "> >i X >o >")
+
+-- Rotating, and failing to rotate on the drag line
+local grid = sb.parse_world("_ _ _")
+local d = sb.start_drag(grid, nil, 2, { x = 0, y = 0 }, sb.direction.east)
+d:interpolate_to({ x = 2, y = 0 })
+assert(not d:rotate({ x = 2, y = 0 }))
+assert(d:rotate({ x = 2, y = 2 }))
+assert(d:tier() == 2)
+assert_world(grid, "2> 2> 2v\n_ _ 2v\n_ _ 2v")
@@ -0,0 +1,36 @@
+local sb = require("smart_belt")
+
+-- Grids round-trip through parse_world and print_world
+local world, markers = sb.parse_world("> >i X *>o >")
+assert(#markers == 1 and markers[1].x == 3 and markers[1].y == 0)
+local printed, printed_markers = sb.parse_world(sb.print_world(world, markers))
+assert(printed == world and printed_markers[1].x == 3)
+assert(sb.parse_world(tostring(world)) == world)
+
+-- Entities are TestEntity-shaped tables
+local ug = world:get({ x = 1, y = 0 })
+assert(ug.kind == "underground-belt")
+assert(ug.direction == sb.direction.east and ug.tier == 1 and ug.ioType == "input")
+assert(world:get({ x = 2, y = 0 }).kind == "obstacle")
+assert(world:get({ x = 5, y = 0 }) == nil)
+
+-- Building and mining
+local built = sb.new_world()
+built:build({ x = 0, y = 0 }, sb.belt(sb.direction.east))
+built:build({ x = 1, y = 0 }, sb.underground_belt(sb.direction.east, "input"))
+built:build({ x = 2, y = 0 }, sb.obstacle())
+built:build({ x = 3, y = 0 }, { kind = "underground-belt", direction = 1, tier = 1, ioType = "output" })
+built:build({ x = 4, y = 0 }, sb.belt(sb.direction.east, 1))
+assert(built == world, tostring(built))
+assert(#built:entities() == 5)
+assert(built:entities()[2].position.x == 1)
+
+local copy = built:copy()
+copy:mine({ x = 4, y = 0 })
+assert(copy ~= built and #copy:entities() == 4)
+
+local fast = sb.splitter(sb.direction.south, 2)
+assert(fast.kind == "splitter" and fast.tier == 2 and fast.ioType == nil)
+assert(not pcall(sb.belt, 4))
+assert(not pcall(sb.belt, 0, 9))
+assert(not pcall(sb.loader, 0, "sideways"))
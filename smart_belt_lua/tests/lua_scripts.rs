@@ -0,0 +1,32 @@
+//! Runs the Lua scripts in `tests/lua` with the module loaded; each asserts
+//! what it checks.
+
+use std::fs;
+use std::path::Path;
+
+use mlua::Lua;
+
+#[test]
+fn lua_scripts() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/lua");
+    let mut scripts = fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|e| e == "lua"))
+        .collect::<Vec<_>>();
+    scripts.sort();
+    assert!(!scripts.is_empty());
+
+    for script in scripts {
+        let lua = Lua::new();
+        smart_belt_lua::preload(&lua).unwrap();
+        let source = fs::read_to_string(&script).unwrap();
+        if let Err(e) = lua
+            .load(&source)
+            .set_name(format!("@{}", script.display()))
+            .exec()
+        {
+            panic!("{}: {e}", script.display());
+        }
+    }
+}
@@ -0,0 +1,140 @@
+//! A long-running server that drives drags step by step over stdio, so
+//! another harness (e.g. the TypeScript port's) can compare its state with
+//! the Rust engine's after every step. See the README for the protocol.
+//!
+//! ```sh
+//! cargo run -p smart_belt_server
+//! ```
+
+mod protocol;
+mod session;
+
+use std::io::{self, BufRead, Write};
+
+use anyhow::Result;
+use serde_json::{Value, json};
+
+use crate::protocol::Request;
+use crate::session::Session;
+
+fn main() -> Result<()> {
+    let mut session = Session::new();
+    let mut stdout = io::stdout().lock();
+    for line in io::stdin().lock().lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let reply = handle_line(&mut session, &line);
+        serde_json::to_writer(&mut stdout, &reply)?;
+        stdout.write_all(b"\n")?;
+        stdout.flush()?;
+    }
+    Ok(())
+}
+
+/// The reply to one line: `{id, result}`, or `{id, error}` if it failed.
+fn handle_line(session: &mut Session, line: &str) -> Value {
+    let request = match serde_json::from_str::<Request>(line) {
+        Ok(request) => request,
+        Err(e) => {
+            // Still echo the id, if the line has one
+            let id = serde_json::from_str::<Value>(line)
+                .ok()
+                .and_then(|v| v.get("id").cloned())
+                .unwrap_or(Value::Null);
+            return json!({ "id": id, "error": format!("Invalid request: {e}") });
+        }
+    };
+    match session.handle(request.command) {
+        Ok(result) => json!({ "id": request.id, "result": result }),
+        Err(e) => json!({ "id": request.id, "error": format!("{e:#}") }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(session: &mut Session, request: Value) -> Value {
+        handle_line(session, &request.to_string())
+    }
+
+    #[test]
+    fn test_drag_step_by_step() {
+        let mut session = Session::new();
+        let reply = run(
+            &mut session,
+            json!({ "id": 1, "command": "load_world", "grid": "[a] _ X X X X X" }),
+        );
+        assert_eq!(
+            reply,
+            json!({ "id": 1, "result": { "markers": [], "labels": { "a": { "x": 0, "y": 0 } } } })
+        );
+
+        let start = json!({ "command": "start_drag", "start": { "x": 0, "y": 0 }, "direction": 1, "tier": 1 });
+        assert_eq!(run(&mut session, start)["result"]["errors"], json!([]));
+        let reply = run(
+            &mut session,
+            json!({ "command": "interpolate", "position": { "x": 8, "y": 0 } }),
+        );
+        assert_eq!(
+            reply["result"]["errors"],
+            json!([{ "position": { "x": 7, "y": 0 }, "error": "too_far_to_connect" }])
+        );
+        // Reported once
+        let reply = run(
+            &mut session,
+            json!({ "command": "interpolate", "position": { "x": 9, "y": 0 } }),
+        );
+        assert_eq!(reply["result"]["errors"], json!([]));
+
+        let reply = run(
+            &mut session,
+            json!({ "command": "get_entity", "position": { "x": 1, "y": 0 } }),
+        );
+        assert_eq!(
+            reply["result"]["entity"],
+            json!({ "kind": "belt", "direction": 1, "tier": 1 })
+        );
+        let reply = run(
+            &mut session,
+            json!({ "command": "rotate", "position": { "x": 9, "y": 0 } }),
+        );
+        assert_eq!(reply["result"]["rotated"], json!(false));
+
+        let trace = run(&mut session, json!({ "command": "get_trace" }));
+        let trace = trace["result"]["trace"].as_array().unwrap();
+        assert_eq!(trace.len(), 9);
+        assert_eq!(trace[0], json!("(1, 0) Belt + Usable -> PlaceBelt"));
+
+        let reply = run(&mut session, json!({ "command": "end_drag" }));
+        assert_eq!(reply["result"]["errors"].as_array().unwrap().len(), 1);
+        let world = run(&mut session, json!({ "command": "dump_world" }));
+        assert_eq!(world["result"]["entities"].as_array().unwrap().len(), 10);
+    }
+
+    #[test]
+    fn test_errors_are_replies() {
+        let mut session = Session::new();
+        let reply = handle_line(&mut session, r#"{"id": "a", "command": "fly"}"#);
+        assert_eq!(reply["id"], json!("a"));
+        assert!(
+            reply["error"]
+                .as_str()
+                .unwrap()
+                .starts_with("Invalid request")
+        );
+
+        let reply = run(&mut session, json!({ "id": 2, "command": "end_drag" }));
+        assert_eq!(reply, json!({ "id": 2, "error": "No drag in progress" }));
+
+        let reply = run(
+            &mut session,
+            json!({ "command": "load_world", "entities": [
+                { "position": { "x": 0, "y": 0 }, "entity": { "kind": "belt", "direction": 4, "tier": 1 } }
+            ] }),
+        );
+        assert_eq!(reply["error"], json!("Direction must be 0 to 3, got 4"));
+    }
+}
@@ -0,0 +1,86 @@
+//! The JSON the server reads and writes. Values have the shapes the mod uses,
+//! so a TypeScript harness can compare them with its own state directly:
+//! see [`prototype_abstract::test_entity`].
+
+use prototype_abstract::smart_belt::action::Error;
+use prototype_abstract::test_entity::TestEntity;
+use prototype_abstract::{TilePosition, pos};
+use serde::{Deserialize, Serialize};
+
+/// A line of input: a command, with an optional `id` to echo in the reply.
+#[derive(Debug, Deserialize)]
+pub struct Request {
+    #[serde(default)]
+    pub id: serde_json::Value,
+    #[serde(flatten)]
+    pub command: Command,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum Command {
+    /// Replaces the world, from a grid as in the test suite, or from entities.
+    LoadWorld {
+        #[serde(default)]
+        grid: Option<String>,
+        #[serde(default)]
+        entities: Vec<PlacedEntity>,
+    },
+    StartDrag {
+        start: Position,
+        direction: u8,
+        tier: usize,
+    },
+    Interpolate {
+        position: Position,
+    },
+    Rotate {
+        position: Position,
+    },
+    EndDrag,
+    GetEntity {
+        position: Position,
+    },
+    DumpWorld,
+    /// The trace of the current or last drag, as in the test suite.
+    GetTrace,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Position {
+    pub x: i32,
+    pub y: i32,
+}
+
+impl From<Position> for TilePosition {
+    fn from(p: Position) -> Self {
+        pos(p.x, p.y)
+    }
+}
+
+impl From<TilePosition> for Position {
+    fn from(p: TilePosition) -> Self {
+        Position { x: p.x, y: p.y }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlacedEntity {
+    pub position: Position,
+    pub entity: TestEntity,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DragError {
+    pub position: Position,
+    pub error: Error,
+}
+
+impl From<&(TilePosition, Error)> for DragError {
+    fn from((p, error): &(TilePosition, Error)) -> Self {
+        DragError {
+            position: (*p).into(),
+            error: error.clone(),
+        }
+    }
+}
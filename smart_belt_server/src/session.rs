@@ -0,0 +1,184 @@
+//! The server's state: a world, and the drag in progress on it, replayed with
+//! [`replay_drag_steps`] on every command.
+
+use std::collections::BTreeMap;
+
+use anyhow::{Context, Result};
+use prototype_abstract::smart_belt::action::Error;
+use prototype_abstract::test_case::{
+    DragStep, parse_labeled_world, print_world, replay_drag_steps,
+};
+use prototype_abstract::test_entity::{TestEntity, parse_direction, parse_tier};
+use prototype_abstract::trace::{TraceStep, with_trace};
+use prototype_abstract::{BeltTier, Direction, TilePosition, WorldImpl};
+use serde_json::{Value, json};
+
+use crate::protocol::{Command, DragError, PlacedEntity, Position};
+
+#[derive(Default)]
+pub struct Session {
+    world: WorldImpl,
+    drag: Option<Drag>,
+    /// The trace of the current or last drag.
+    trace: Vec<TraceStep>,
+}
+
+struct Drag {
+    before: WorldImpl,
+    tier: BeltTier,
+    start: TilePosition,
+    direction: Direction,
+    steps: Vec<DragStep>,
+    /// All errors the drag has reported, in order.
+    errors: Vec<(TilePosition, Error)>,
+}
+
+/// The result of replaying a drag.
+struct Replay {
+    world: WorldImpl,
+    errors: Vec<(TilePosition, Error)>,
+    trace: Vec<TraceStep>,
+    /// False if the last step is a rotation that failed.
+    rotated: bool,
+}
+
+impl Drag {
+    fn replay(&self) -> Replay {
+        let mut world = self.before.clone();
+        let ((errors, rotated), trace) = with_trace(|| {
+            replay_drag_steps(
+                &mut world,
+                self.tier,
+                self.start,
+                self.direction,
+                &self.steps,
+            )
+        });
+        Replay {
+            world,
+            errors,
+            trace,
+            rotated,
+        }
+    }
+}
+
+impl Session {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Runs `command`, returning the `result` to reply with.
+    pub fn handle(&mut self, command: Command) -> Result<Value> {
+        match command {
+            Command::LoadWorld { grid, entities } => self.load_world(grid, entities),
+            Command::StartDrag {
+                start,
+                direction,
+                tier,
+            } => {
+                let drag = Drag {
+                    before: self.world.clone(),
+                    tier: parse_tier(tier)?,
+                    start: start.into(),
+                    direction: parse_direction(direction)?,
+                    steps: Vec::new(),
+                    errors: Vec::new(),
+                };
+                let replay = drag.replay();
+                self.drag = Some(drag);
+                Ok(json!({ "errors": self.apply(replay) }))
+            }
+            Command::Interpolate { position } => {
+                let new_errors = self.step(DragStep::MoveTo(position.into()))?;
+                Ok(json!({ "errors": new_errors.unwrap_or_default() }))
+            }
+            Command::Rotate { position } => {
+                let new_errors = self.step(DragStep::Rotate(position.into()))?;
+                Ok(json!({
+                    "rotated": new_errors.is_some(),
+                    "errors": new_errors.unwrap_or_default(),
+                }))
+            }
+            Command::EndDrag => {
+                let drag = self.drag.take().context("No drag in progress")?;
+                Ok(json!({ "errors": errors_json(&drag.errors) }))
+            }
+            Command::GetEntity { position } => {
+                let entity = self.world.get(position.into()).map(TestEntity::from);
+                Ok(json!({ "entity": entity }))
+            }
+            Command::DumpWorld => Ok(json!({
+                "entities": self.entities(),
+                "grid": print_world(&self.world, self.world.bounds(), &[]),
+                "errors": errors_json(self.drag.as_ref().map_or(&[][..], |d| &d.errors)),
+            })),
+            Command::GetTrace => {
+                let trace = self.trace.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+                Ok(json!({ "trace": trace }))
+            }
+        }
+    }
+
+    fn load_world(&mut self, grid: Option<String>, entities: Vec<PlacedEntity>) -> Result<Value> {
+        let parse = parse_labeled_world(grid.as_deref().unwrap_or(""))?;
+        let mut world = parse.world;
+        for placed in entities {
+            world
+                .try_build(placed.position.into(), placed.entity.to_entity()?)
+                .map_err(anyhow::Error::msg)?;
+        }
+        self.world = world;
+        self.drag = None;
+        self.trace.clear();
+
+        let markers = parse.markers.into_iter().map(Position::from);
+        let labels = parse
+            .labels
+            .into_iter()
+            .map(|(label, p)| (label, Position::from(p)))
+            .collect::<BTreeMap<_, _>>();
+        Ok(json!({ "markers": markers.collect::<Vec<_>>(), "labels": labels }))
+    }
+
+    /// Adds `step` to the drag and returns the new errors, or `None` (changing
+    /// nothing) if it is a rotation that failed.
+    fn step(&mut self, step: DragStep) -> Result<Option<Vec<DragError>>> {
+        let drag = self.drag.as_mut().context("No drag in progress")?;
+        drag.steps.push(step);
+        let replay = drag.replay();
+        if !replay.rotated {
+            drag.steps.pop();
+            return Ok(None);
+        }
+        Ok(Some(self.apply(replay)))
+    }
+
+    /// Makes `replay` of the drag current, and returns its new errors.
+    fn apply(&mut self, replay: Replay) -> Vec<DragError> {
+        let drag = self.drag.as_mut().expect("replayed a drag in progress");
+        let new_errors = errors_json(&replay.errors[drag.errors.len().min(replay.errors.len())..]);
+        drag.errors = replay.errors;
+        self.world = replay.world;
+        self.trace = replay.trace;
+        new_errors
+    }
+
+    fn entities(&self) -> Vec<PlacedEntity> {
+        let mut entities = self
+            .world
+            .entities
+            .iter()
+            .map(|(&p, entity)| PlacedEntity {
+                position: p.into(),
+                entity: entity.into(),
+            })
+            .collect::<Vec<_>>();
+        entities.sort_by_key(|e| (e.position.y, e.position.x));
+        entities
+    }
+}
+
+fn errors_json(errors: &[(TilePosition, Error)]) -> Vec<DragError> {
+    errors.iter().map(DragError::from).collect()
+}
@@ -0,0 +1,112 @@
+//! A terminal playground for dragging belts: move the cursor with the
+//! keyboard, drag, rotate, place obstacles, and export what happened as a
+//! scenario test case.
+//!
+//! ```sh
+//! cargo run -p smart_belt_tui -- world.txt --output scenario.yaml
+//! ```
+
+mod playground;
+mod ui;
+
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::{Context, Result, bail};
+use clap::Parser;
+use prototype_abstract::test_case::parse_labeled_world;
+use prototype_abstract::{Direction, TileVec, WorldImpl};
+use ratatui::DefaultTerminal;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+
+use crate::playground::Playground;
+
+#[derive(Debug, Parser)]
+struct Args {
+    #[arg(help = "Grid to start from, as in the test suite [default: empty]")]
+    world: Option<PathBuf>,
+    #[arg(long, default_value_t = 16, help = "Minimum width of the world")]
+    width: i32,
+    #[arg(long, default_value_t = 8, help = "Minimum height of the world")]
+    height: i32,
+    #[arg(
+        long,
+        short,
+        default_value = "playground.yaml",
+        help = "Where to export the scenario"
+    )]
+    output: PathBuf,
+    #[arg(long, default_value = "Playground", help = "Name of the exported case")]
+    name: String,
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+    let (world, size) = match &args.world {
+        Some(path) => {
+            let grid =
+                fs::read_to_string(path).with_context(|| format!("Reading {}", path.display()))?;
+            let parse = parse_labeled_world(&grid)?;
+            (parse.world, parse.size)
+        }
+        None => (WorldImpl::new(), TileVec::zero()),
+    };
+    let size = TileVec::new(size.x.max(args.width), size.y.max(args.height));
+    let mut playground = Playground::new(world, size);
+
+    let mut terminal = ratatui::init();
+    let result = run(&mut terminal, &mut playground, &args);
+    ratatui::restore();
+    result
+}
+
+fn run(terminal: &mut DefaultTerminal, playground: &mut Playground, args: &Args) -> Result<()> {
+    let mut status = String::from("Space starts a drag at the cursor");
+    loop {
+        terminal.draw(|frame| ui::draw(frame, playground, &status))?;
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        let result = match key.code {
+            KeyCode::Char('q') => return Ok(()),
+            KeyCode::Up | KeyCode::Char('k') => playground.move_cursor(Direction::North),
+            KeyCode::Right | KeyCode::Char('l') => playground.move_cursor(Direction::East),
+            KeyCode::Down | KeyCode::Char('j') => playground.move_cursor(Direction::South),
+            KeyCode::Left | KeyCode::Char('h') => playground.move_cursor(Direction::West),
+            KeyCode::Char(' ') | KeyCode::Enter => playground.click(),
+            KeyCode::Esc => playground.cancel(),
+            KeyCode::Char('r') => playground.rotate(true),
+            KeyCode::Char('R') => playground.rotate(false),
+            KeyCode::Char(c @ '1'..='9') => playground.set_tier(c as usize - '0' as usize),
+            KeyCode::Char('x') => playground.toggle_obstacle(),
+            KeyCode::Char('#') => playground.toggle_impassable(),
+            KeyCode::Delete | KeyCode::Backspace => playground.remove(),
+            KeyCode::Char('u') => undo(playground),
+            KeyCode::Char('z') if key.modifiers.contains(KeyModifiers::CONTROL) => undo(playground),
+            KeyCode::Char('e') => export(playground, args),
+            _ => continue,
+        };
+        status = match result {
+            Ok(()) if key.code == KeyCode::Char('e') => {
+                format!("Exported to {}", args.output.display())
+            }
+            Ok(()) => String::new(),
+            Err(e) => format!("{e:#}"),
+        };
+    }
+}
+
+fn undo(playground: &mut Playground) -> Result<()> {
+    if !playground.undo() {
+        bail!("Nothing to undo");
+    }
+    Ok(())
+}
+
+fn export(playground: &Playground, args: &Args) -> Result<()> {
+    let yaml = playground.scenario_yaml(&args.name)?;
+    fs::write(&args.output, yaml).with_context(|| format!("Writing {}", args.output.display()))
+}
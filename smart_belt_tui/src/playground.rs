@@ -0,0 +1,417 @@
+//! The playground's state: a world, the drags made over it, and the cursor.
+//!
+//! Drags are kept as the steps that made them and replayed over the world
+//! they started from on every change, so that the in-progress drag, undo and
+//! export all see exactly what `replay_drag_steps` (and so the test suite) does.
+
+use anyhow::{Context, Result, bail};
+use prototype_abstract::smart_belt::action::Error;
+use prototype_abstract::test_case::{DragStep, align_columns, print_world, replay_drag_steps};
+use prototype_abstract::{
+    BELT_TIERS, BeltCollidable, BeltTier, BoundingBox, CollidingEntityOrTile, Direction,
+    ImpassableTile, TilePosition, TileVec, WorldImpl, pos,
+};
+
+#[derive(Debug, Clone)]
+pub struct Drag {
+    pub start: TilePosition,
+    /// The direction the drag started in; rotations are among the steps.
+    pub direction: Direction,
+    pub tier: BeltTier,
+    pub steps: Vec<DragStep>,
+    /// The world after the drag.
+    pub world: WorldImpl,
+    /// In reading order.
+    pub errors: Vec<(TilePosition, Error)>,
+}
+
+impl Drag {
+    /// Replays the drag over `world`.
+    fn run(&mut self, world: &WorldImpl) -> Result<()> {
+        let mut after = world.clone();
+        let (mut errors, rotated) = replay_drag_steps(
+            &mut after,
+            self.tier,
+            self.start,
+            self.direction,
+            &self.steps,
+        );
+        if !rotated {
+            bail!("The drag did not rotate");
+        }
+        errors.sort_by_key(|(p, error)| (p.y, p.x, error.clone()));
+        errors.dedup();
+        self.world = after;
+        self.errors = errors;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+struct State {
+    /// The world the drags start from, i.e. the exported `before`.
+    before: WorldImpl,
+    drags: Vec<Drag>,
+    current: Option<Drag>,
+    cursor: TilePosition,
+    direction: Direction,
+    tier: BeltTier,
+}
+
+pub struct Playground {
+    size: TileVec,
+    state: State,
+    history: Vec<State>,
+}
+
+impl Playground {
+    /// A playground over `world`, which is `size` tiles from `(0, 0)`.
+    pub fn new(world: WorldImpl, size: TileVec) -> Self {
+        Self {
+            size,
+            state: State {
+                before: world,
+                drags: Vec::new(),
+                current: None,
+                cursor: pos(0, 0),
+                direction: Direction::East,
+                tier: BELT_TIERS[0],
+            },
+            history: Vec::new(),
+        }
+    }
+
+    pub fn bounds(&self) -> BoundingBox {
+        BoundingBox::new(pos(0, 0), pos(0, 0) + self.size)
+    }
+
+    /// The world as it is now, with the drag in progress if there is one.
+    pub fn world(&self) -> &WorldImpl {
+        self.state.world()
+    }
+
+    /// Errors of the drag in progress, or else of the last drag.
+    pub fn errors(&self) -> &[(TilePosition, Error)] {
+        let state = &self.state;
+        state
+            .current
+            .as_ref()
+            .or(state.drags.last())
+            .map_or(&[], |drag| &drag.errors)
+    }
+
+    pub fn cursor(&self) -> TilePosition {
+        self.state.cursor
+    }
+
+    /// The direction the next drag starts in.
+    pub fn direction(&self) -> Direction {
+        self.state.direction
+    }
+
+    pub fn tier(&self) -> BeltTier {
+        self.state.tier
+    }
+
+    pub fn current_drag(&self) -> Option<&Drag> {
+        self.state.current.as_ref()
+    }
+
+    pub fn num_drags(&self) -> usize {
+        self.state.drags.len()
+    }
+
+    /// Applies `change` to a copy of the state, keeping it (and the old state
+    /// to undo to) only if it succeeds.
+    fn change(&mut self, change: impl FnOnce(&mut State) -> Result<()>) -> Result<()> {
+        let mut state = self.state.clone();
+        change(&mut state)?;
+        self.history.push(std::mem::replace(&mut self.state, state));
+        Ok(())
+    }
+
+    /// Moves the cursor a tile, extending or retracting the drag in progress.
+    pub fn move_cursor(&mut self, direction: Direction) -> Result<()> {
+        let cursor = self.state.cursor + direction.to_vector();
+        if !self.bounds().contains(cursor) {
+            bail!("The cursor is at the edge of the world");
+        }
+        if self.state.current.is_none() {
+            // Not worth undoing
+            self.state.cursor = cursor;
+            return Ok(());
+        }
+        self.change(|state| {
+            state.cursor = cursor;
+            state.step(DragStep::MoveTo(cursor))
+        })
+    }
+
+    /// Starts a drag at the cursor, or finishes the one in progress.
+    pub fn click(&mut self) -> Result<()> {
+        self.change(|state| {
+            if let Some(drag) = state.current.take() {
+                state.drags.push(drag);
+                return Ok(());
+            }
+            let mut drag = Drag {
+                start: state.cursor,
+                direction: state.direction,
+                tier: state.tier,
+                steps: Vec::new(),
+                world: WorldImpl::new(),
+                errors: Vec::new(),
+            };
+            drag.run(state.settled())?;
+            state.current = Some(drag);
+            Ok(())
+        })
+    }
+
+    /// Drops the drag in progress.
+    pub fn cancel(&mut self) -> Result<()> {
+        if self.state.current.is_none() {
+            bail!("Not dragging");
+        }
+        self.change(|state| {
+            state.current = None;
+            Ok(())
+        })
+    }
+
+    /// Rotates the drag in progress towards the cursor, or else turns the
+    /// direction the next drag starts in.
+    pub fn rotate(&mut self, clockwise: bool) -> Result<()> {
+        self.change(|state| {
+            if state.current.is_some() {
+                return state
+                    .step(DragStep::Rotate(state.cursor))
+                    .context("Move the cursor off the belt line to rotate");
+            }
+            state.direction = if clockwise {
+                state.direction.rotate_cw()
+            } else {
+                state.direction.rotate_ccw()
+            };
+            Ok(())
+        })
+    }
+
+    /// `tier` is 1-based, as in grids.
+    pub fn set_tier(&mut self, tier: usize) -> Result<()> {
+        let tier = BeltTier::from_number(tier)
+            .with_context(|| format!("Tiers are 1 to {}", BELT_TIERS.len()))?;
+        self.change(|state| {
+            if state.current.is_some() {
+                bail!("Finish the drag before changing tier");
+            }
+            state.tier = tier;
+            Ok(())
+        })
+    }
+
+    /// Places `entity` at the cursor, or removes it if it is already there.
+    /// The exported scenario then starts from the world as it is now.
+    fn toggle(&mut self, entity: BeltCollidable) -> Result<()> {
+        self.edit(|world, cursor| {
+            if world.get(cursor) == Some(&entity) {
+                world.mine(cursor);
+            } else {
+                world.build(cursor, entity);
+            }
+        })
+    }
+
+    pub fn toggle_obstacle(&mut self) -> Result<()> {
+        self.toggle(CollidingEntityOrTile.into())
+    }
+
+    pub fn toggle_impassable(&mut self) -> Result<()> {
+        self.toggle(ImpassableTile.into())
+    }
+
+    /// Removes the entity at the cursor.
+    pub fn remove(&mut self) -> Result<()> {
+        self.edit(|world, cursor| world.mine(cursor))
+    }
+
+    fn edit(&mut self, edit: impl FnOnce(&mut WorldImpl, TilePosition)) -> Result<()> {
+        self.change(|state| {
+            if state.current.is_some() {
+                bail!("Finish the drag before editing the world");
+            }
+            // Drags can't be replayed over a world edited after them
+            let mut world = state.settled().clone();
+            edit(&mut world, state.cursor);
+            state.before = world;
+            state.drags.clear();
+            Ok(())
+        })
+    }
+
+    /// Returns whether there was anything to undo.
+    pub fn undo(&mut self) -> bool {
+        match self.history.pop() {
+            Some(state) => {
+                self.state = state;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// The drags so far (including one in progress) as a scenario test case,
+    /// a one-case list to paste into or save as a test suite file.
+    pub fn scenario_yaml(&self, name: &str) -> Result<String> {
+        let state = &self.state;
+        let drags = state.drags.iter().chain(&state.current).collect::<Vec<_>>();
+        let Some(last) = drags.last() else {
+            bail!("Nothing to export: make a drag first");
+        };
+        let grid = |world: &WorldImpl, errors: &[(TilePosition, Error)], indent: &str| {
+            let markers = errors.iter().map(|(p, _)| *p).collect::<Vec<_>>();
+            align_columns(&print_world(world, self.bounds(), &markers))
+                .lines()
+                .map(|line| format!("{indent}{line}\n"))
+                .collect::<String>()
+        };
+        let coords = |p: TilePosition| format!("[{}, {}]", p.x, p.y);
+
+        let mut yaml = format!("- name: {name}\n  before: |\n");
+        yaml += &grid(&state.before, &[], "    ");
+        yaml += "  drags:\n";
+        for drag in &drags {
+            yaml += &format!("    - start: {}\n", coords(drag.start));
+            let direction = serde_yaml::to_string(&drag.direction)?;
+            yaml += &format!("      direction: {}\n", direction.trim());
+            let tier = drag.tier.tier_index();
+            if tier != 0 {
+                yaml += &format!("      tier: {}\n", tier + 1);
+            }
+            if drag.steps.is_empty() {
+                yaml += &format!("      end: {}\n", coords(drag.start));
+            } else {
+                yaml += "      steps:\n";
+                for step in &drag.steps {
+                    yaml += &match *step {
+                        DragStep::MoveTo(p) => format!("        - move_to: {}\n", coords(p)),
+                        DragStep::Rotate(p) => format!("        - rotate: {}\n", coords(p)),
+                    };
+                }
+            }
+            if !std::ptr::eq(*drag, *last) {
+                yaml += "      after: |\n";
+                yaml += &grid(&drag.world, &drag.errors, "        ");
+            }
+            if !drag.errors.is_empty() {
+                let errors = drag
+                    .errors
+                    .iter()
+                    .map(|(_, error)| Ok(serde_yaml::to_string(error)?.trim().to_string()))
+                    .collect::<Result<Vec<_>>>()?;
+                yaml += &format!("      expected_errors: [{}]\n", errors.join(", "));
+            }
+        }
+        yaml += "  after: |\n";
+        yaml += &grid(&last.world, &last.errors, "    ");
+        Ok(yaml)
+    }
+}
+
+impl State {
+    /// The world after the finished drags.
+    fn settled(&self) -> &WorldImpl {
+        self.drags.last().map_or(&self.before, |drag| &drag.world)
+    }
+
+    fn world(&self) -> &WorldImpl {
+        self.current
+            .as_ref()
+            .map_or_else(|| self.settled(), |drag| &drag.world)
+    }
+
+    /// Adds `step` to the drag in progress, if any.
+    fn step(&mut self, step: DragStep) -> Result<()> {
+        let Some(mut drag) = self.current.take() else {
+            return Ok(());
+        };
+        drag.steps.push(step);
+        drag.run(self.settled())?;
+        self.current = Some(drag);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prototype_abstract::test_case::{SuiteCase, parse_world};
+
+    #[test]
+    fn test_drag_undo_and_export() {
+        let (world, _) = parse_world("_ _ X _ _ _ _ _ _ _\n_ _ _ _ _ _ _ _ _ _").unwrap();
+        let mut playground = Playground::new(world, TileVec::new(10, 2));
+
+        playground.click().unwrap();
+        for _ in 0..4 {
+            playground.move_cursor(Direction::East).unwrap();
+        }
+        assert!(matches!(
+            playground.world().get(pos(1, 0)),
+            Some(BeltCollidable::UndergroundBelt(_))
+        ));
+        let extended = print_world(playground.world(), playground.bounds(), &[]);
+        playground.move_cursor(Direction::West).unwrap();
+        playground.move_cursor(Direction::West).unwrap();
+        assert!(playground.undo());
+        assert!(playground.undo());
+        assert_eq!(
+            print_world(playground.world(), playground.bounds(), &[]),
+            extended
+        );
+        playground.move_cursor(Direction::South).unwrap();
+        playground.rotate(true).unwrap();
+        playground.click().unwrap();
+
+        // Another drag, into the side of the first
+        playground.set_tier(2).unwrap();
+        playground.rotate(true).unwrap();
+        playground.rotate(true).unwrap();
+        for _ in 0..5 {
+            playground.move_cursor(Direction::East).unwrap();
+        }
+        playground.click().unwrap();
+        for _ in 0..4 {
+            playground.move_cursor(Direction::West).unwrap();
+        }
+        assert!(playground.rotate(true).is_err());
+        assert!(playground.set_tier(1).is_err());
+
+        let yaml = playground.scenario_yaml("Playground").unwrap();
+        let cases: Vec<serde_yaml::Value> = serde_yaml::from_str(&yaml).unwrap();
+        let case = SuiteCase::from_value(&cases[0]).unwrap();
+        case.check_all_variants()
+            .unwrap_or_else(|e| panic!("{e:#}\n{yaml}"));
+    }
+
+    #[test]
+    fn test_editing_restarts_the_scenario() {
+        let mut playground = Playground::new(WorldImpl::new(), TileVec::new(4, 1));
+        assert!(playground.scenario_yaml("Empty").is_err());
+        playground.click().unwrap();
+        assert!(playground.toggle_obstacle().is_err());
+        playground.click().unwrap();
+        assert_eq!(playground.num_drags(), 1);
+
+        playground.move_cursor(Direction::East).unwrap();
+        playground.toggle_obstacle().unwrap();
+        assert_eq!(playground.num_drags(), 0);
+        assert!(playground.world().get(pos(0, 0)).is_some());
+        playground.toggle_obstacle().unwrap();
+        assert!(playground.world().get(pos(1, 0)).is_none());
+
+        assert!(playground.undo());
+        assert!(playground.undo());
+        assert_eq!(playground.num_drags(), 1);
+    }
+}
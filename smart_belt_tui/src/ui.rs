@@ -0,0 +1,144 @@
+//! Drawing the playground: the grid, a side panel with the drag and its
+//! errors, and a status line.
+
+use prototype_abstract::test_case::print_entity;
+use prototype_abstract::{
+    BELT_TIERS, BeltCollidable, BeltConnectableTrait, BeltTier, Direction, TilePosition, WorldImpl,
+    pos,
+};
+use ratatui::Frame;
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Paragraph, Wrap};
+
+use crate::playground::Playground;
+
+/// Width of a tile in the grid, in characters; as wide as `print_world`'s.
+const TILE_WIDTH: u16 = 4;
+
+pub const HELP: &str = "arrows/hjkl move  space drag/finish  r/R rotate  1-3 tier  \
+x obstacle  # impassable  del remove  esc cancel  u undo  e export  q quit";
+
+pub fn draw(frame: &mut Frame, playground: &Playground, status: &str) {
+    let [main, status_area] =
+        Layout::vertical([Constraint::Min(0), Constraint::Length(2)]).areas(frame.area());
+    let [grid_area, side_area] =
+        Layout::horizontal([Constraint::Min(0), Constraint::Length(32)]).areas(main);
+
+    let grid = Paragraph::new(grid_lines(playground)).block(Block::bordered().title(" World "));
+    frame.render_widget(grid, grid_area);
+    frame.render_widget(
+        Paragraph::new(side_lines(playground))
+            .block(Block::bordered().title(" Drag "))
+            .wrap(Wrap { trim: false }),
+        side_area,
+    );
+    frame.render_widget(
+        Paragraph::new(vec![Line::from(status.to_string()), Line::from(HELP).dim()]),
+        status_area,
+    );
+}
+
+fn grid_lines(playground: &Playground) -> Vec<Line<'static>> {
+    let bounds = playground.bounds();
+    let world = playground.world();
+    let errors = playground.errors();
+    let start = playground.current_drag().map(|drag| drag.start);
+    (bounds.min.y..bounds.max.y)
+        .map(|y| {
+            let spans = (bounds.min.x..bounds.max.x).map(|x| {
+                let p = pos(x, y);
+                let text = world.get(p).map_or("_".to_string(), print_entity);
+                let mut style = entity_style(world, p);
+                if errors.iter().any(|(error_pos, _)| *error_pos == p) {
+                    style = style.bg(Color::Red).fg(Color::White);
+                } else if start == Some(p) {
+                    style = style.bg(Color::DarkGray);
+                }
+                if p == playground.cursor() {
+                    style = style.add_modifier(Modifier::REVERSED);
+                }
+                Span::styled(
+                    format!("{text:<width$}", width = TILE_WIDTH as usize),
+                    style,
+                )
+            });
+            Line::from(spans.collect::<Vec<_>>())
+        })
+        .collect()
+}
+
+fn entity_style(world: &WorldImpl, p: TilePosition) -> Style {
+    match world.get(p) {
+        None => Style::new().dark_gray(),
+        Some(BeltCollidable::CollidingEntityOrTile(_)) => Style::new().gray().bold(),
+        Some(BeltCollidable::ImpassableTile(_)) => Style::new().blue().dim(),
+        Some(_) => match world.get_belt(p) {
+            Some(belt) => Style::new().fg(tier_color(belt.tier())),
+            None => Style::new(),
+        },
+    }
+}
+
+fn tier_color(tier: BeltTier) -> Color {
+    match BELT_TIERS.contains(&tier).then(|| tier.tier_index()) {
+        Some(0) => Color::Yellow,
+        Some(1) => Color::LightRed,
+        Some(2) => Color::LightBlue,
+        _ => Color::Magenta,
+    }
+}
+
+fn side_lines(playground: &Playground) -> Vec<Line<'static>> {
+    let tier = playground.tier();
+    let tier_index = tier.tier_index();
+    let cursor = playground.cursor();
+    let mut lines = vec![
+        Line::from(vec![
+            "Tier: ".into(),
+            Span::styled(
+                format!("{} ({})", tier_index + 1, tier.name),
+                Style::new().fg(tier_color(tier)),
+            ),
+        ]),
+        Line::from(format!(
+            "Direction: {}",
+            direction_arrow(playground.direction())
+        )),
+        Line::from(format!("Cursor: {}, {}", cursor.x, cursor.y)),
+        Line::from(format!("Finished drags: {}", playground.num_drags())),
+        Line::from(""),
+    ];
+    match playground.current_drag() {
+        Some(drag) => {
+            lines.push(Line::from(format!(
+                "Dragging from {}, {} ({} steps)",
+                drag.start.x,
+                drag.start.y,
+                drag.steps.len()
+            )));
+        }
+        None => lines.push(Line::from("Not dragging").dim()),
+    }
+    lines.push(Line::from(""));
+    let errors = playground.errors();
+    if errors.is_empty() {
+        lines.push(Line::from("No errors").green());
+    } else {
+        lines.push(Line::from("Errors:").red().bold());
+        for (p, error) in errors {
+            lines.push(Line::from(format!("  {}, {}: {error:?}", p.x, p.y)).red());
+        }
+    }
+    lines
+}
+
+fn direction_arrow(direction: Direction) -> &'static str {
+    match direction {
+        Direction::North => "^ north",
+        Direction::East => "> east",
+        Direction::South => "v south",
+        Direction::West => "< west",
+    }
+}